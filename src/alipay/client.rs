@@ -1,7 +1,11 @@
 use crate::alipay::{AlipayNotify, AlipayNotifyData};
 use crate::config::{AlipayConfig, Mode};
-use crate::errors::PayError;
-use crate::utils::{get_cert_sn, get_root_cert_sn, rsa_sign_sha256_pem};
+use crate::errors::{PayError, RetryClass};
+use crate::quota::QuotaManager;
+use crate::utils::{
+    get_cert_sn, get_root_cert_sn, retry_async_if, rsa_sign_sha256_with_passphrase,
+    sm2_sign_sm3_with_passphrase,
+};
 use reqwest::Client;
 use std::collections::BTreeMap;
 use std::sync::Arc;
@@ -11,8 +15,10 @@ use urlencoding::encode;
 pub struct AlipayClient {
     cfg: Arc<AlipayConfig>,
     http: Client,
-    gateway: String,
+    pub(crate) gateway: String,
     mode: Mode,
+    max_retries: usize,
+    quota: Option<Arc<QuotaManager>>,
 }
 
 impl AlipayClient {
@@ -23,6 +29,10 @@ impl AlipayClient {
     pub fn with_mode(cfg: Arc<AlipayConfig>, mode: Mode) -> Self {
         let gateway = match mode {
             Mode::Sandbox => "https://openapi.alipaydev.com/gateway.do".to_string(),
+            // 非中国大陆区域未配置网关时留空，发送请求时报错
+            _ if cfg.gateway.is_empty() => {
+                cfg.region.alipay_gateway().unwrap_or_default().to_string()
+            }
             _ => cfg.gateway.clone(),
         };
         Self {
@@ -30,21 +40,59 @@ impl AlipayClient {
             http: Client::new(),
             gateway,
             mode,
+            max_retries: 3,
+            quota: None,
         }
     }
 
+    /// 按商户（服务商模式下为 app_auth_token 对应的子商户）限制并发与 TPS
+    pub fn with_quota(mut self, quota: Arc<QuotaManager>) -> Self {
+        self.quota = Some(quota);
+        self
+    }
+
+    /// 复用已有的 HTTP 连接池
+    pub(crate) fn with_http(mut self, http: Client) -> Self {
+        self.http = http;
+        self
+    }
+
     fn build_sign_string(params: &BTreeMap<String, String>) -> String {
-        params
-            .iter()
-            .map(|(k, v)| format!("{}={}", k, v))
-            .collect::<Vec<_>>()
-            .join("&")
+        let len = params.iter().map(|(k, v)| k.len() + v.len() + 2).sum();
+        let mut out = String::with_capacity(len);
+        for (k, v) in params {
+            if !out.is_empty() {
+                out.push('&');
+            }
+            out.push_str(k);
+            out.push('=');
+            out.push_str(v);
+        }
+        out
+    }
+
+    /// 按 sign_type 签名：RSA2（SHA256withRSA）或 SM2（SM2withSM3）
+    fn sign(&self, sign_src: &str) -> Result<String, PayError> {
+        let signed = if self.cfg.sign_type.eq_ignore_ascii_case("SM2") {
+            sm2_sign_sm3_with_passphrase(
+                &self.cfg.private_key_pem,
+                self.cfg.private_key_passphrase.as_deref(),
+                sign_src,
+            )
+        } else {
+            rsa_sign_sha256_with_passphrase(
+                &self.cfg.private_key_pem,
+                self.cfg.private_key_passphrase.as_deref(),
+                sign_src,
+            )
+        };
+        signed.map_err(|e| PayError::Crypto(e.to_string()))
     }
 
     fn build_service_provider_params(&self, order: &mut serde_json::Value) {
         if let Mode::Service = self.mode {
             if let Some(provider_id) = &self.cfg.sys_service_provider_id {
-                if !order.get("extend_params").is_some() {
+                if order.get("extend_params").is_none() {
                     order["extend_params"] = serde_json::json!({});
                 }
                 if let Some(obj) = order["extend_params"].as_object_mut() {
@@ -108,13 +156,51 @@ impl AlipayClient {
         params
     }
 
+    /// 仅签名不发送：返回含 sign 的完整请求参数，由调用方自行发往 gateway()
+    pub fn build_signed_params(
+        &self,
+        method: &str,
+        mut biz_content: serde_json::Value,
+    ) -> Result<BTreeMap<String, String>, PayError> {
+        self.build_service_provider_params(&mut biz_content);
+        let mut params = self.build_common_params(method, &biz_content);
+        params.insert("biz_content".into(), biz_content.to_string());
+        let sign = self.sign(&Self::build_sign_string(&params))?;
+        params.insert("sign".into(), sign);
+        Ok(params)
+    }
+
+    /// 当前使用的网关地址
+    pub fn gateway(&self) -> &str {
+        &self.gateway
+    }
+
+    // 配额按商户区分：优先取本次请求的 app_auth_token（公共参数或 biz_content 中），否则取配置
+    fn quota_key(&self, params: &BTreeMap<String, String>) -> String {
+        params
+            .get("app_auth_token")
+            .cloned()
+            .or_else(|| {
+                let biz: serde_json::Value =
+                    serde_json::from_str(params.get("biz_content")?).ok()?;
+                biz.get("app_auth_token")?.as_str().map(str::to_string)
+            })
+            .or_else(|| self.cfg.app_auth_token.clone())
+            .unwrap_or_else(|| self.cfg.app_id.clone())
+    }
+
     pub async fn do_request(
         &self,
         params: BTreeMap<String, String>,
     ) -> Result<serde_json::Value, PayError> {
+        if self.gateway.is_empty() {
+            return Err(PayError::Other(format!(
+                "alipay gateway must be configured for region {:?}",
+                self.cfg.region
+            )));
+        }
         let sign_src = Self::build_sign_string(&params);
-        let sign = rsa_sign_sha256_pem(&self.cfg.private_key_pem, &sign_src)
-            .map_err(|e| PayError::Crypto(e.to_string()))?;
+        let sign = self.sign(&sign_src)?;
 
         let mut params_with_sign = params;
         params_with_sign.insert("sign".into(), sign);
@@ -127,6 +213,11 @@ impl AlipayClient {
 
         let url = format!("{}?{}", self.gateway, query);
 
+        // 所有发往网关的请求（含退款、当面付）统一占用配额
+        let _permit = match &self.quota {
+            Some(q) => Some(q.acquire(&self.quota_key(&params_with_sign)).await),
+            None => None,
+        };
         let resp = self
             .http
             .get(&url)
@@ -146,6 +237,40 @@ impl AlipayClient {
         Ok(v)
     }
 
+    /// 通用接口调用：组装公共参数、biz_content 并校验业务返回码
+    /// 成功时返回 `{method}_response` 节点（如 alipay_trade_query_response）
+    ///
+    /// isp.* 等可重试错误会按退避策略重试；网关 20000、ACQ.SYSTEM_ERROR 结果未知，
+    /// 需先查询再决定是否重试，与 isv.* 等终态错误一样直接返回调用方
+    pub async fn execute(
+        &self,
+        method: &str,
+        mut biz_content: serde_json::Value,
+    ) -> Result<serde_json::Value, PayError> {
+        self.build_service_provider_params(&mut biz_content);
+        let biz_content = &biz_content;
+        let retryable = |e: &PayError| e.retry_class() == RetryClass::Retryable;
+        retry_async_if(self.max_retries, retryable, || async move {
+            let mut params = self.build_common_params(method, biz_content);
+            params.insert("biz_content".into(), biz_content.to_string());
+            let resp = self.do_request(params).await?;
+            let key = format!("{}_response", method.replace('.', "_"));
+            match resp.get(&key) {
+                Some(result) if result.get("code").and_then(|v| v.as_str()) == Some("10000") => {
+                    Ok(result.clone())
+                }
+                Some(result) => Err(PayError::from_alipay_response(result)),
+                None => Err(PayError::Other(format!("invalid alipay response: {}", resp))),
+            }
+        })
+        .await
+    }
+
+    /// 统一收单交易查询（alipay.trade.query），out_trade_no 与 trade_no 二选一
+    pub async fn query(&self, biz: serde_json::Value) -> Result<serde_json::Value, PayError> {
+        self.execute("alipay.trade.query", biz).await
+    }
+
     pub async fn app(&self, mut order: serde_json::Value) -> Result<serde_json::Value, PayError> {
         if order.get("product_code").is_none() {
             order["product_code"] = json!("QUICK_MSECURITY_PAY");
@@ -155,8 +280,7 @@ impl AlipayClient {
         params.insert("biz_content".into(), order.to_string());
 
         let sign_src = Self::build_sign_string(&params);
-        let sign = rsa_sign_sha256_pem(&self.cfg.private_key_pem, &sign_src)
-            .map_err(|e| PayError::Crypto(e.to_string()))?;
+        let sign = self.sign(&sign_src)?;
         params.insert("sign".into(), sign);
 
         let order_str = params
@@ -179,6 +303,85 @@ impl AlipayClient {
         self.do_request(params).await
     }
 
+    /// 付款码支付（商家扫用户付款码，alipay.trade.pay）
+    /// 返回码 10003 表示等待用户确认，需后续轮询查询结果，此时不视为错误
+    ///
+    /// 返回 ACQ.SYSTEM_ERROR / 20000 时支付结果未知，先查询交易：
+    /// 已支付则直接返回查询结果，否则以同一 out_trade_no 重试
+    pub async fn pos(&self, mut order: serde_json::Value) -> Result<serde_json::Value, PayError> {
+        if order.get("scene").is_none() {
+            order["scene"] = json!("bar_code");
+        }
+        if order.get("product_code").is_none() {
+            order["product_code"] = json!("FACE_TO_FACE_PAYMENT");
+        }
+        self.build_service_provider_params(&mut order);
+        let mut attempts = self.max_retries;
+        loop {
+            let err = match self.pay_once(&order).await {
+                Ok(v) => return Ok(v),
+                Err(e) => e,
+            };
+            attempts -= 1;
+            match err.retry_class() {
+                RetryClass::Terminal => return Err(err),
+                _ if attempts == 0 => return Err(err),
+                RetryClass::QueryBeforeRetry => {
+                    let biz = json!({ "out_trade_no": order.get("out_trade_no") });
+                    if let Ok(trade) = self.query(biz).await {
+                        let status = trade.get("trade_status").and_then(|v| v.as_str());
+                        if matches!(status, Some("TRADE_SUCCESS") | Some("TRADE_FINISHED")) {
+                            return Ok(trade);
+                        }
+                    }
+                }
+                RetryClass::Retryable => {}
+            }
+            tracing::warn!("alipay.trade.pay retry: {}", err);
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        }
+    }
+
+    async fn pay_once(&self, order: &serde_json::Value) -> Result<serde_json::Value, PayError> {
+        let mut params = self.build_common_params("alipay.trade.pay", order);
+        params.insert("biz_content".into(), order.to_string());
+        let resp = self.do_request(params).await?;
+        if let Some(result) = resp.get("alipay_trade_pay_response") {
+            return match result.get("code").and_then(|v| v.as_str()) {
+                Some("10000") | Some("10003") => Ok(result.clone()),
+                _ => Err(PayError::from_alipay_response(result)),
+            };
+        }
+        Err(PayError::Other(format!("invalid alipay pay response: {}", resp)))
+    }
+
+    /// 统一收单交易退款查询（alipay.trade.fastpay.refund.query）
+    pub async fn refund_query(&self, biz: serde_json::Value) -> Result<serde_json::Value, PayError> {
+        self.execute("alipay.trade.fastpay.refund.query", biz).await
+    }
+
+    /// 统一收单交易结算（alipay.trade.order.settle），用于分账
+    pub async fn order_settle(&self, biz: serde_json::Value) -> Result<serde_json::Value, PayError> {
+        self.execute("alipay.trade.order.settle", biz).await
+    }
+
+    /// 账务明细查询（alipay.data.bill.accountlog.query），用于对账与结算报表
+    pub async fn accountlog_query(&self, biz: serde_json::Value) -> Result<serde_json::Value, PayError> {
+        self.execute("alipay.data.bill.accountlog.query", biz).await
+    }
+
+    /// 统一收单交易关闭（alipay.trade.close），仅适用于未付款交易
+    pub async fn close(&self, biz: serde_json::Value) -> Result<serde_json::Value, PayError> {
+        self.execute("alipay.trade.close", biz).await
+    }
+
+    /// 统一收单交易撤销（alipay.trade.cancel），用于付款码等当面付场景
+    ///
+    /// 未付款则关闭，已付款则原路退款；应答 action 为 close / refund。
+    pub async fn cancel(&self, biz: serde_json::Value) -> Result<serde_json::Value, PayError> {
+        self.execute("alipay.trade.cancel", biz).await
+    }
+
     /// ✅ H5 支付（手机浏览器）
     pub async fn h5(&self, mut order: serde_json::Value) -> Result<serde_json::Value, PayError> {
         //没有 product_code 时，默认值为 QUICK_WAP_PAY
@@ -190,8 +393,7 @@ impl AlipayClient {
         params.insert("biz_content".into(), order.to_string());
 
         let sign_src = Self::build_sign_string(&params);
-        let sign = rsa_sign_sha256_pem(&self.cfg.private_key_pem, &sign_src)
-            .map_err(|e| PayError::Crypto(e.to_string()))?;
+        let sign = self.sign(&sign_src)?;
         params.insert("sign".into(), sign);
 
         // 拼接跳转链接
@@ -216,8 +418,7 @@ impl AlipayClient {
         params.insert("biz_content".into(), order.to_string());
 
         let sign_src = Self::build_sign_string(&params);
-        let sign = rsa_sign_sha256_pem(&self.cfg.private_key_pem, &sign_src)
-            .map_err(|e| PayError::Crypto(e.to_string()))?;
+        let sign = self.sign(&sign_src)?;
         params.insert("sign".into(), sign);
 
         // 返回 form 表单字符串（前端可直接渲染提交）
@@ -295,7 +496,7 @@ impl AlipayClient {
                     .to_string();
                 return Ok(serde_json::json!({
                 "trade_no": trade_no,
-                "refund_amount": order.get("refund_amount").unwrap_or(&json!("0")).as_f64().unwrap_or(0.0),
+                "refund_amount": order.get("refund_amount").cloned().unwrap_or(json!("0")),
                 "msg": "refund success"
             }));
             } else {
@@ -0,0 +1,38 @@
+use crate::alipay::client::AlipayClient;
+use crate::errors::PayError;
+use serde_json::{json, Value};
+
+impl AlipayClient {
+    /// 生活缴费欠费查询（按缴费户号查询待缴账单）
+    /// `biz` 需包含 order_type（JF）、sub_order_type（如 ELECTRIC/WATER/GAS）、
+    /// charge_inst（出账机构）与 bill_key（户号）
+    pub async fn ebpp_bill_search(&self, biz: Value) -> Result<Value, PayError> {
+        self.execute("alipay.ebpp.bill.search", biz).await
+    }
+
+    /// 查询缴费单详情
+    pub async fn ebpp_bill_get(
+        &self,
+        order_type: &str,
+        merchant_order_no: &str,
+    ) -> Result<Value, PayError> {
+        let biz = json!({
+            "order_type": order_type,
+            "merchant_order_no": merchant_order_no,
+        });
+        self.execute("alipay.ebpp.bill.get", biz).await
+    }
+
+    /// 缴费机构录入账单
+    pub async fn ebpp_bill_add(&self, biz: Value) -> Result<Value, PayError> {
+        self.execute("alipay.ebpp.bill.add", biz).await
+    }
+
+    /// 缴费代扣（用户已签约代扣协议时直接扣款）
+    pub async fn ebpp_pdeduct_pay(&self, biz: Value) -> Result<Value, PayError> {
+        if biz.get("out_order_no").is_none() {
+            return Err(PayError::Other("Missing required field: 'out_order_no'".into()));
+        }
+        self.execute("alipay.ebpp.pdeduct.pay", biz).await
+    }
+}
@@ -1,4 +1,8 @@
 pub mod client;
+pub mod ebpp;
+pub mod smilepay;
+pub mod transfer;
 pub mod notify;
+pub mod poll;
 pub use client::AlipayClient;
 pub use notify::{AlipayNotify, AlipayNotifyData};
@@ -1,12 +1,23 @@
 use crate::config::{AlipayConfig};
 use crate::errors::PayError;
-use crate::utils::{rsa_verify_sha256_pem};
+use crate::model::{params_to_value, RawPayload};
+use crate::channel::Channel;
+use crate::notify::{NotifyRequest, NotifyValidators};
+use crate::utils::extract_pubkey_from_cert;
+use crate::verify::verify_alipay_params;
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
 use std::fs;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
 
-#[derive(Debug, Serialize, Deserialize)]
+// 支付宝公钥证书路径 -> (证书文件修改时间, 提取出的公钥 PEM)；修改时间变化时重新加载
+type CertKeyCache = RwLock<HashMap<String, (Option<SystemTime>, String)>>;
+static CERT_PUBLIC_KEYS: Lazy<CertKeyCache> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AlipayNotifyData {
     pub app_id: String,
     pub out_trade_no: String,
@@ -15,15 +26,34 @@ pub struct AlipayNotifyData {
     pub total_amount: String,
     pub seller_id: Option<String>,
     pub others: HashMap<String, String>,
+    /// 通知原始参数（含 sign）
+    #[serde(default)]
+    pub raw: Value,
+}
+
+impl RawPayload for AlipayNotifyData {
+    fn raw(&self) -> &Value {
+        &self.raw
+    }
 }
 
 pub struct AlipayNotify {
     cfg: Arc<AlipayConfig>,
+    validators: NotifyValidators,
 }
 
 impl AlipayNotify {
     pub fn new(cfg: Arc<AlipayConfig>) -> Self {
-        Self { cfg }
+        Self {
+            cfg,
+            validators: NotifyValidators::new(),
+        }
+    }
+
+    /// 设置验签后执行的业务校验器
+    pub fn with_validators(mut self, validators: NotifyValidators) -> Self {
+        self.validators = validators;
+        self
     }
 
     /// Verify Alipay notify parameters
@@ -31,47 +61,11 @@ impl AlipayNotify {
         &self,
         params: &HashMap<String, String>,
     ) -> Result<AlipayNotifyData, PayError> {
-        // ---- Step 1. 提取 sign 和 sign_type ----
-        let sign = params
-            .get("sign")
-            .ok_or_else(|| PayError::Other("missing sign".to_string()))?;
-       // let sign_type = params.get("sign_type").cloned().unwrap_or_default();
-
-        // ---- Step 2. 构造待签名字符串 ----
-        let mut kv: Vec<(&String, &String)> = params
-            .iter()
-            .filter(|&(k, _)| k != "sign" && k != "sign_type")
-            .collect();
-        kv.sort_by(|a, b| a.0.cmp(b.0));
-        let content = kv
-            .iter()
-            .map(|(k, v)| format!("{}={}", k, v))
-            .collect::<Vec<String>>()
-            .join("&");
-
-        // ---- Step 3. 选择验签公钥 ----
-        let mut pubkey_pem = String::new();
-
-        // 1) 证书模式优先（推荐生产使用）
-        if let Some(cert_path) = &self.cfg.alipay_cert_path {
-            if let Ok(pem) = fs::read_to_string(cert_path) {
-                pubkey_pem = pem;
-            }
-        }
-        // 2) 如果没配置证书，则使用公钥字符串模式
-        if pubkey_pem.is_empty() {
-            pubkey_pem = self.cfg.alipay_public_key.clone().unwrap_or_default();
-        }
-        if pubkey_pem.is_empty() {
-            return Err(PayError::Other("missing alipay public key".into()));
-        }
+        // ---- Step 1. 选择验签公钥 ----
+        let pubkey_pem = self.public_key_pem()?;
 
-        // ---- Step 4. 验签 ----
-        let verified = rsa_verify_sha256_pem(&pubkey_pem, &content, sign)
-            .map_err(|e| PayError::Crypto(format!("rsa verify error: {}", e)))?;
-        if !verified {
-            return Err(PayError::Other("alipay notify signature invalid".into()));
-        }
+        // ---- Step 2. 验签 ----
+        verify_alipay_params(params, &pubkey_pem, &self.cfg.sign_type)?;
 
         // ---- Step 5. 核心字段解析 ----
         let app_id = params.get("app_id").cloned().unwrap_or_default();
@@ -97,7 +91,7 @@ impl AlipayNotify {
             }
         }
 
-        Ok(AlipayNotifyData {
+        let data = AlipayNotifyData {
             app_id,
             out_trade_no,
             trade_no,
@@ -105,7 +99,47 @@ impl AlipayNotify {
             total_amount,
             seller_id,
             others,
-        })
+            raw: params_to_value(params),
+        };
+
+        // ---- Step 9. 业务校验 ----
+        self.validators.validate(Channel::Alipay, &data.raw)?;
+        Ok(data)
+    }
+
+    /// 验签公钥：证书模式优先，从 alipayCertPublicKey_RSA2.crt 提取公钥并按路径 + 修改时间缓存，
+    /// 同路径替换证书后自动重新加载；未配置证书或读取失败时使用公钥字符串
+    fn public_key_pem(&self) -> Result<String, PayError> {
+        if let Some(cert_path) = &self.cfg.alipay_cert_path {
+            let mtime = fs::metadata(cert_path).and_then(|m| m.modified()).ok();
+            if let Some((cached_mtime, pem)) = CERT_PUBLIC_KEYS.read().unwrap().get(cert_path) {
+                if *cached_mtime == mtime {
+                    return Ok(pem.clone());
+                }
+            }
+            match fs::read_to_string(cert_path)
+                .map_err(anyhow::Error::from)
+                .and_then(|pem| extract_pubkey_from_cert(&pem))
+            {
+                Ok(pem) => {
+                    CERT_PUBLIC_KEYS
+                        .write()
+                        .unwrap()
+                        .insert(cert_path.clone(), (mtime, pem.clone()));
+                    return Ok(pem);
+                }
+                Err(e) => tracing::warn!("load alipay cert {} failed: {}", cert_path, e),
+            }
+        }
+        match &self.cfg.alipay_public_key {
+            Some(key) if !key.is_empty() => Ok(key.clone()),
+            _ => Err(PayError::Other("missing alipay public key".into())),
+        }
+    }
+
+    /// 从 NotifyRequest 解析表单参数并验签
+    pub fn verify_request(&self, req: &NotifyRequest) -> Result<AlipayNotifyData, PayError> {
+        self.verify_notify(&req.form_params())
     }
 
     /// 成功响应内容
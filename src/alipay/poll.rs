@@ -0,0 +1,63 @@
+use crate::alipay::client::AlipayClient;
+use crate::errors::PayError;
+use crate::poll::{PaymentOutcome, PaymentState, PollBackoff};
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+
+// 撤销 retry_flag=Y 时的最大重试次数
+const CANCEL_ATTEMPTS: usize = 3;
+
+impl AlipayClient {
+    /// 按当面付流程轮询 alipay.trade.query，超时后调用 alipay.trade.cancel
+    ///
+    /// WAIT_BUYER_PAY 与交易不存在（用户尚未确认）继续轮询；撤销返回 retry_flag=Y 时重试。
+    pub async fn await_payment(
+        &self,
+        out_trade_no: &str,
+        deadline: Instant,
+    ) -> Result<PaymentOutcome, PayError> {
+        let mut backoff = PollBackoff::new(deadline);
+        let mut last = Value::Null;
+        loop {
+            match self.query(json!({ "out_trade_no": out_trade_no })).await {
+                Ok(resp) => {
+                    let state = match resp.get("trade_status").and_then(|v| v.as_str()) {
+                        Some("TRADE_SUCCESS") | Some("TRADE_FINISHED") => Some(PaymentState::Paid),
+                        Some("TRADE_CLOSED") => Some(PaymentState::Closed),
+                        _ => None,
+                    };
+                    if let Some(state) = state {
+                        return Ok(PaymentOutcome {
+                            state,
+                            closed_on_timeout: false,
+                            raw: resp,
+                        });
+                    }
+                    last = resp;
+                }
+                Err(e) => tracing::warn!("await_payment query {} failed: {}", out_trade_no, e),
+            }
+            if !backoff.wait().await {
+                break;
+            }
+        }
+
+        for attempt in 1..=CANCEL_ATTEMPTS {
+            let resp = self.cancel(json!({ "out_trade_no": out_trade_no })).await?;
+            if resp.get("retry_flag").and_then(|v| v.as_str()) != Some("Y") {
+                return Ok(PaymentOutcome {
+                    state: PaymentState::TimedOut,
+                    closed_on_timeout: true,
+                    raw: resp,
+                });
+            }
+            tracing::warn!("alipay.trade.cancel {} needs retry ({})", out_trade_no, attempt);
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+        Ok(PaymentOutcome {
+            state: PaymentState::TimedOut,
+            closed_on_timeout: false,
+            raw: last,
+        })
+    }
+}
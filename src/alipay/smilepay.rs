@@ -0,0 +1,30 @@
+use crate::alipay::client::AlipayClient;
+use crate::errors::PayError;
+use serde_json::{json, Value};
+
+impl AlipayClient {
+    /// 刷脸付初始化，`zimmetainfo` 为刷脸设备 SDK 采集的元信息
+    /// 返回 zimId / zimInitClientData，交给设备端拉起刷脸
+    pub async fn smilepay_initialize(&self, zimmetainfo: &str) -> Result<Value, PayError> {
+        let biz = json!({ "zimmetainfo": zimmetainfo });
+        self.execute("zoloz.authentication.smilepay.initialize", biz)
+            .await
+    }
+
+    /// 刷脸完成后校验 ftoken，返回对应的支付宝用户信息
+    pub async fn query_ftoken(&self, ftoken: &str, zim_id: Option<&str>) -> Result<Value, PayError> {
+        let mut biz = json!({ "ftoken": ftoken, "biz_type": "1" });
+        if let Some(zim_id) = zim_id {
+            biz["zim_id"] = json!(zim_id);
+        }
+        self.execute("zoloz.authentication.customer.ftoken.query", biz)
+            .await
+    }
+
+    /// 刷脸支付：以 ftoken 作为 auth_code，scene 为 security_code 调用统一收单交易支付
+    pub async fn smile_pay(&self, mut order: Value, ftoken: &str) -> Result<Value, PayError> {
+        order["scene"] = json!("security_code");
+        order["auth_code"] = json!(ftoken);
+        self.pos(order).await
+    }
+}
@@ -0,0 +1,37 @@
+use crate::alipay::client::AlipayClient;
+use crate::errors::PayError;
+use serde_json::{json, Value};
+
+impl AlipayClient {
+    /// 单笔转账到支付宝账户（旧版接口 alipay.fund.trans.toaccount.transfer）
+    /// 适用于仍签约旧版转账产品、无法使用 alipay.fund.trans.uni.transfer 的商户
+    /// `order` 需包含 out_biz_no、payee_type（ALIPAY_USERID/ALIPAY_LOGONID）、payee_account、amount
+    pub async fn toaccount_transfer(&self, order: Value) -> Result<Value, PayError> {
+        for field in ["out_biz_no", "payee_type", "payee_account", "amount"] {
+            if order.get(field).is_none() {
+                return Err(PayError::Other(format!("Missing required field: '{}'", field)));
+            }
+        }
+        self.execute("alipay.fund.trans.toaccount.transfer", order)
+            .await
+    }
+
+    /// 查询旧版转账订单（alipay.fund.trans.order.query），out_biz_no 与 order_id 二选一
+    pub async fn query_transfer_order(
+        &self,
+        out_biz_no: Option<&str>,
+        order_id: Option<&str>,
+    ) -> Result<Value, PayError> {
+        let mut biz = json!({});
+        if let Some(out_biz_no) = out_biz_no {
+            biz["out_biz_no"] = json!(out_biz_no);
+        }
+        if let Some(order_id) = order_id {
+            biz["order_id"] = json!(order_id);
+        }
+        if biz.as_object().map(|o| o.is_empty()).unwrap_or(true) {
+            return Err(PayError::Other("out_biz_no or order_id is required".into()));
+        }
+        self.execute("alipay.fund.trans.order.query", biz).await
+    }
+}
@@ -0,0 +1,115 @@
+//! 证书查看：解析已配置的 X.509 证书，便于展示与到期告警
+use crate::errors::PayError;
+use chrono::{DateTime, Duration, Utc};
+use openssl::asn1::{Asn1Time, Asn1TimeRef};
+use openssl::x509::{X509NameRef, X509};
+use serde::{Deserialize, Serialize};
+
+/// 证书信息
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CertificateInfo {
+    /// 主题，如 "CN=1900000001,O=...,C=CN"
+    pub subject: String,
+    pub issuer: String,
+    /// 序列号（十六进制大写，微信证书序列号格式）
+    pub serial_hex: String,
+    /// 序列号（十进制，支付宝 / 银联证书使用）
+    pub serial_dec: String,
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
+    /// 签名算法，如 sha256WithRSAEncryption
+    pub signature_algorithm: String,
+    pub public_key_pem: String,
+}
+
+impl CertificateInfo {
+    pub fn from_pem(pem: &str) -> Result<Self, PayError> {
+        let cert = X509::from_pem(pem.as_bytes()).map_err(crypto_err)?;
+        Self::from_x509(&cert)
+    }
+
+    pub fn from_der(der: &[u8]) -> Result<Self, PayError> {
+        let cert = X509::from_der(der).map_err(crypto_err)?;
+        Self::from_x509(&cert)
+    }
+
+    /// 从文件读取，自动识别 PEM / DER；PEM 证书链只取第一张
+    pub fn from_file(path: &str) -> Result<Self, PayError> {
+        let data = std::fs::read(path)?;
+        if data.starts_with(b"-----BEGIN") {
+            let cert = X509::from_pem(&data).map_err(crypto_err)?;
+            Self::from_x509(&cert)
+        } else {
+            Self::from_der(&data)
+        }
+    }
+
+    /// 解析 PEM 证书链中的全部证书（如 alipayRootCert.crt）
+    pub fn chain_from_pem(pem: &str) -> Result<Vec<Self>, PayError> {
+        X509::stack_from_pem(pem.as_bytes())
+            .map_err(crypto_err)?
+            .iter()
+            .map(Self::from_x509)
+            .collect()
+    }
+
+    pub fn from_x509(cert: &X509) -> Result<Self, PayError> {
+        let serial = cert.serial_number().to_bn().map_err(crypto_err)?;
+        let public_key_pem = cert
+            .public_key()
+            .and_then(|k| k.public_key_to_pem())
+            .map_err(crypto_err)?;
+        Ok(Self {
+            subject: name_to_string(cert.subject_name()),
+            issuer: name_to_string(cert.issuer_name()),
+            serial_hex: serial.to_hex_str().map_err(crypto_err)?.to_uppercase(),
+            serial_dec: serial.to_dec_str().map_err(crypto_err)?.to_string(),
+            not_before: asn1_to_utc(cert.not_before())?,
+            not_after: asn1_to_utc(cert.not_after())?,
+            signature_algorithm: cert
+                .signature_algorithm()
+                .object()
+                .nid()
+                .long_name()
+                .unwrap_or_default()
+                .to_string(),
+            public_key_pem: String::from_utf8_lossy(&public_key_pem).into_owned(),
+        })
+    }
+
+    pub fn is_expired(&self) -> bool {
+        Utc::now() >= self.not_after
+    }
+
+    /// 是否将在 `within` 时间内到期（含已过期）
+    pub fn expires_within(&self, within: Duration) -> bool {
+        Utc::now() + within >= self.not_after
+    }
+}
+
+fn name_to_string(name: &X509NameRef) -> String {
+    name.entries()
+        .map(|e| {
+            let key = e.object().nid().short_name().unwrap_or("?");
+            let value = e
+                .data()
+                .as_utf8()
+                .map(|v| v.to_string())
+                .unwrap_or_default();
+            format!("{}={}", key, value)
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn asn1_to_utc(time: &Asn1TimeRef) -> Result<DateTime<Utc>, PayError> {
+    let epoch = Asn1Time::from_unix(0).map_err(crypto_err)?;
+    let diff = epoch.diff(time).map_err(crypto_err)?;
+    let secs = diff.days as i64 * 86_400 + diff.secs as i64;
+    DateTime::from_timestamp(secs, 0)
+        .ok_or_else(|| PayError::Crypto(format!("invalid certificate time: {}", time)))
+}
+
+fn crypto_err(e: openssl::error::ErrorStack) -> PayError {
+    PayError::Crypto(format!("x509: {}", e))
+}
@@ -0,0 +1,97 @@
+use crate::errors::PayError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// 支付渠道
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    Wechat,
+    Alipay,
+    Unionpay,
+}
+
+/// 渠道支持的操作
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    Refund,
+    PartialRefund,
+    Transfer,
+    PreAuth,
+    ProfitSharing,
+}
+
+/// 单个渠道的能力集合
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChannelCapabilities {
+    pub channel: Channel,
+    pub operations: Vec<Capability>,
+}
+
+impl ChannelCapabilities {
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.operations.contains(&capability)
+    }
+}
+
+impl Channel {
+    /// 本 crate 对该渠道已实现的操作
+    pub fn capabilities(&self) -> ChannelCapabilities {
+        let operations = match self {
+            Channel::Wechat => vec![
+                Capability::Refund,
+                Capability::PartialRefund,
+                Capability::Transfer,
+                Capability::ProfitSharing,
+            ],
+            Channel::Alipay => vec![
+                Capability::Refund,
+                Capability::PartialRefund,
+                Capability::Transfer,
+            ],
+            Channel::Unionpay => vec![],
+        };
+        ChannelCapabilities {
+            channel: *self,
+            operations,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Channel::Wechat => "wechat",
+            Channel::Alipay => "alipay",
+            Channel::Unionpay => "unionpay",
+        }
+    }
+}
+
+impl FromStr for Channel {
+    type Err = PayError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "wechat" | "wx" => Ok(Channel::Wechat),
+            "alipay" | "ali" => Ok(Channel::Alipay),
+            "unionpay" | "union" => Ok(Channel::Unionpay),
+            other => Err(PayError::Other(format!("unknown channel: {}", other))),
+        }
+    }
+}
+
+/// 解析渠道标签，如 "wechat"、"wechat:mp"、"alipay:pos"，返回 (渠道, 产品)
+pub fn parse_tag(tag: &str) -> Result<(Channel, Option<String>), PayError> {
+    let (channel, product) = match tag.split_once(':') {
+        Some((c, p)) => (c, Some(p.trim().to_ascii_lowercase())),
+        None => (tag, None),
+    };
+    Ok((channel.parse()?, product.filter(|p| !p.is_empty())))
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -1,11 +1,36 @@
+use crate::channel::{Channel, ChannelCapabilities};
 use crate::config::{AlipayConfigOverride, PayConfig, WechatConfigOverride};
-use once_cell::sync::OnceCell;
-use std::sync::Arc;
+use crate::errors::PayError;
+use crate::quota::QuotaManager;
+use crate::store::OrderStore;
+use crate::wechat::certs::PlatformCerts;
+use once_cell::sync::{Lazy, OnceCell};
+use reqwest::dns::Resolve;
+use reqwest::{Client, ClientBuilder};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 static CONFIG: OnceCell<Arc<PayConfig>> = OnceCell::new();
+static ORDER_STORE: OnceCell<Arc<dyn OrderStore>> = OnceCell::new();
+static QUOTA: OnceCell<Arc<QuotaManager>> = OnceCell::new();
+// 各渠道客户端共享的 HTTP 连接池，首次使用时按配置构建
+static HTTP: OnceCell<Client> = OnceCell::new();
+// 自定义 DNS 解析器（保存为对 ClientBuilder 的设置）
+type ResolverSetter = Box<dyn Fn(ClientBuilder) -> ClientBuilder + Send + Sync>;
+static DNS_RESOLVER: OnceCell<ResolverSetter> = OnceCell::new();
+// 按商户号缓存的微信平台证书
+static WECHAT_CERTS: Lazy<Mutex<HashMap<String, Arc<PlatformCerts>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
 pub struct Pay;
 impl Pay {
+    /// 初始化全局配置，配置无效（如 dns_overrides 无法解析）时 panic；需处理错误请使用 try_config
     pub fn config(cfg: PayConfig) {
+        Self::try_config(cfg).expect("invalid pay config");
+    }
+    /// 校验并初始化全局配置
+    pub fn try_config(cfg: PayConfig) -> Result<(), PayError> {
+        cfg.resolved_overrides()?;
         let _ = CONFIG.set(Arc::new(cfg));
+        Ok(())
     }
     fn cfg() -> Arc<PayConfig> {
         CONFIG.get().expect("config not initialized").clone()
@@ -13,6 +38,59 @@ impl Pay {
     pub fn is_config() -> bool {
         CONFIG.get().is_some()
     }
+    /// 配置订单存储，启用统一退款的超额校验
+    pub fn set_order_store(store: Arc<dyn OrderStore>) {
+        let _ = ORDER_STORE.set(store);
+    }
+    pub(crate) fn order_store() -> Option<Arc<dyn OrderStore>> {
+        ORDER_STORE.get().cloned()
+    }
+    /// 配置按商户的并发 / TPS 配额，作用于此后创建的微信与支付宝客户端
+    ///
+    /// 只能设置一次，重复调用返回错误且不替换已有配额。
+    pub fn set_quota(quota: Arc<QuotaManager>) -> Result<(), PayError> {
+        QUOTA
+            .set(quota)
+            .map_err(|_| PayError::Other("quota already set".into()))
+    }
+    /// 使用自定义 DNS 解析网关域名，需在首次创建客户端前调用；
+    /// 配置中的 dns_overrides 优先于该解析器
+    pub fn set_dns_resolver<R: Resolve + 'static>(resolver: Arc<R>) {
+        let _ = DNS_RESOLVER.set(Box::new(move |b| b.dns_resolver(resolver.clone())));
+    }
+    /// 带自定义 DNS 解析器与 dns_overrides 的 ClientBuilder，所有网关连接均由此构建
+    pub(crate) fn client_builder() -> ClientBuilder {
+        let mut builder = Client::builder().user_agent("rust_pay_wf");
+        if let Some(set_resolver) = DNS_RESOLVER.get() {
+            builder = set_resolver(builder);
+        }
+        // dns_overrides 已在 try_config 中校验
+        if let Some(Ok(overrides)) = CONFIG.get().map(|cfg| cfg.resolved_overrides()) {
+            for (host, addrs) in overrides {
+                builder = builder.resolve_to_addrs(&host, &addrs);
+            }
+        }
+        builder
+    }
+    fn http() -> Client {
+        HTTP.get_or_init(|| Self::client_builder().build().expect("client"))
+            .clone()
+    }
+    /// 已配置渠道及其支持的操作
+    pub fn capabilities() -> Vec<ChannelCapabilities> {
+        let cfg = Self::cfg();
+        let mut out = Vec::new();
+        if cfg.wechat.is_some() {
+            out.push(Channel::Wechat.capabilities());
+        }
+        if cfg.alipay.is_some() {
+            out.push(Channel::Alipay.capabilities());
+        }
+        if cfg.unionpay.is_some() {
+            out.push(Channel::Unionpay.capabilities());
+        }
+        out
+    }
     pub fn wechat(over_config: Option<WechatConfigOverride>) -> crate::wechat::client::WechatClient {
         let cfg = Self::cfg();
         let wx = cfg.wechat.clone().expect("wechat config missing");
@@ -27,7 +105,24 @@ impl Pay {
             }
         }
         let final_wx_config = Arc::new(final_config);
-        crate::wechat::client::WechatClient::with_mode(final_wx_config, cfg.mode.clone())
+        let certs = WECHAT_CERTS
+            .lock()
+            .unwrap()
+            .entry(final_wx_config.mchid.clone())
+            .or_insert_with(|| {
+                Arc::new(PlatformCerts::new(final_wx_config.clone()).with_http(Self::http()))
+            })
+            .clone();
+        let client = crate::wechat::client::WechatClient::with_parts(
+            final_wx_config,
+            cfg.mode.clone(),
+            Self::http(),
+            certs,
+        );
+        match QUOTA.get() {
+            Some(q) => client.with_quota(q.clone()),
+            None => client,
+        }
     }
     pub fn alipay(over_config: Option<AlipayConfigOverride>) -> crate::alipay::client::AlipayClient {
         let cfg = Self::cfg();
@@ -42,11 +137,44 @@ impl Pay {
             }
         }
         let final_ali_config = Arc::new(final_config);
-        crate::alipay::client::AlipayClient::with_mode(final_ali_config, cfg.mode.clone())
+        let client = crate::alipay::client::AlipayClient::with_mode(final_ali_config, cfg.mode.clone())
+            .with_http(Self::http());
+        match QUOTA.get() {
+            Some(q) => client.with_quota(q.clone()),
+            None => client,
+        }
     }
     pub fn unionpay() -> crate::unionpay::client::UnionClient {
         let cfg = Self::cfg();
         let up = cfg.unionpay.clone().expect("unionpay config missing");
-        crate::unionpay::client::UnionClient::new(up)
+        crate::unionpay::client::UnionClient::with_mode(up, cfg.mode.clone()).with_http(Self::http())
+    }
+
+    /// 启动预热：与已配置渠道的网关完成 TLS 握手并预取微信平台证书，
+    /// 避免首笔支付承担冷启动延迟
+    pub async fn warmup() -> Result<(), PayError> {
+        let cfg = Self::cfg();
+        if cfg.wechat.is_some() {
+            let wx = Self::wechat(None);
+            Self::preflight(&wx.base_url).await?;
+            wx.certs
+                .refresh()
+                .await
+                .map_err(|e| PayError::Crypto(format!("refresh certs failed: {}", e)))?;
+        }
+        if cfg.alipay.is_some() {
+            Self::preflight(&Self::alipay(None).gateway).await?;
+        }
+        if cfg.unionpay.is_some() {
+            Self::preflight(&Self::unionpay().gateway).await?;
+        }
+        Ok(())
+    }
+
+    // 仅建立连接，不关心响应状态码
+    async fn preflight(url: &str) -> Result<(), PayError> {
+        Self::http().head(url).send().await?;
+        Ok(())
     }
 }
+
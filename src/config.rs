@@ -1,13 +1,73 @@
+use crate::errors::PayError;
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug)]
+// Debug 输出中替代密钥等敏感字段
+const REDACTED: &str = "***";
+
+fn redact_opt(v: &Option<String>) -> Option<&'static str> {
+    v.as_ref().map(|_| REDACTED)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Mode {
     Normal,
     Service,
     Sandbox,
 }
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// 接入区域，决定各渠道的 API 域名
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Region {
+    /// 中国大陆
+    #[default]
+    Mainland,
+    /// 中国香港
+    HongKong,
+    /// 境外（跨境）
+    Global,
+}
+
+impl Region {
+    /// 微信支付 API 域名；境外商户统一接入香港域名
+    pub fn wechat_host(&self) -> &'static str {
+        match self {
+            Region::Mainland => "https://api.mch.weixin.qq.com",
+            Region::HongKong | Region::Global => "https://apihk.mch.weixin.qq.com",
+        }
+    }
+
+    /// 微信支付容灾备用域名
+    pub fn wechat_backup_host(&self) -> Option<&'static str> {
+        match self {
+            Region::Mainland => Some("https://api2.mch.weixin.qq.com"),
+            Region::HongKong | Region::Global => None,
+        }
+    }
+
+    /// 支付宝 OpenAPI 网关；境外没有通用的 OpenAPI 网关，需在 AlipayConfig.gateway 中显式配置
+    pub fn alipay_gateway(&self) -> Option<&'static str> {
+        match self {
+            Region::Mainland => Some("https://openapi.alipay.com/gateway.do"),
+            Region::HongKong | Region::Global => None,
+        }
+    }
+}
+
+/// 微信支付签名算法套件
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SignAlgorithm {
+    /// SHA256-RSA2048 签名，AEAD_AES_256_GCM 解密
+    #[default]
+    Rsa,
+    /// 国密：SM2-WITH-SM3 签名，AEAD_SM4_GCM 解密，SM2 平台证书
+    Sm2,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct WechatConfig {
     pub mchid: String,
     pub appid: Option<String>,//主商户appid，服务号
@@ -15,13 +75,123 @@ pub struct WechatConfig {
     pub private_key_pem: String,
     pub api_v3_key: String,
     pub platform_public_key_pem: Option<String>,
+    // 微信支付公钥 ID（PUB_KEY_ID_ 开头）；与 platform_public_key_pem 同时配置即为公钥模式，
+    // 验签与敏感信息加密均使用该公钥，不再下载平台证书
+    #[serde(default)]
+    pub public_key_id: Option<String>,
+    // 预置平台证书：序列号 -> 证书或公钥 PEM，启动时无需下载即可验签、加密
+    #[serde(default)]
+    pub platform_certs: HashMap<String, String>,
+    // 预置平台证书目录：证书文件以证书序列号为键，公钥文件以文件名（去掉扩展名）为键
+    #[serde(default)]
+    pub platform_certs_dir: Option<String>,
+    // 平台证书距过期不足该小时数时主动刷新，默认 24
+    #[serde(default)]
+    pub cert_refresh_ahead_hours: Option<u32>,
     pub appid_mp: Option<String>,
     pub appid_mini: Option<String>,
     pub appid_app: Option<String>,
     pub notify_url: Option<String>,
     pub sub_mchid: Option<String>,
+    // V2 接口密钥（MD5/HMAC-SHA256 签名，部分产品仅有 V2 接口）
+    #[serde(default)]
+    pub api_v2_key: Option<String>,
+    // 签名算法，国密商户设置为 Sm2
+    #[serde(default)]
+    pub sign_algorithm: SignAlgorithm,
+    // 接入区域，默认中国大陆
+    #[serde(default)]
+    pub region: Region,
+    // 加密私钥（ENCRYPTED PRIVATE KEY / 加密 PKCS#8 DER）的口令
+    #[serde(default)]
+    pub private_key_passphrase: Option<String>,
+    // 商户 API 证书轮换期间的备用签名身份，主证书被网关拒绝后自动切换
+    #[serde(default)]
+    pub secondary_identity: Option<SigningIdentity>,
+    // 商户 API 证书（apiclient_cert.pem 内容或路径），私钥取 private_key_pem；V2 企业付款、撤销等接口双向 TLS 使用
+    #[serde(default)]
+    pub client_cert_pem: Option<String>,
+    // 商户 API 证书 apiclient_cert.p12 路径，口令为商户号；与 client_cert_pem 二选一
+    #[serde(default)]
+    pub client_cert_p12_path: Option<String>,
+    // 接口地址覆盖（如本地 mock 服务），设置后 V3 / V2 请求及平台证书下载均发往该地址；
+    // 微信支付 V3 没有官方沙箱，Sandbox 模式下必须设置
+    #[serde(default)]
+    pub base_url: Option<String>,
+    // 容灾备用地址，主地址网络故障时自动切换；未设置时中国大陆取 api2.mch.weixin.qq.com
+    #[serde(default)]
+    pub backup_base_url: Option<String>,
 }
-#[derive(Clone, Debug, Serialize, Deserialize)]
+
+impl WechatConfig {
+    /// 公钥模式下的 (公钥 ID, 公钥 PEM)
+    pub fn wechatpay_public_key(&self) -> Option<(&str, &str)> {
+        match (self.public_key_id.as_deref(), self.platform_public_key_pem.as_deref()) {
+            (Some(id), Some(pem)) if !id.is_empty() && !pem.is_empty() => Some((id, pem)),
+            _ => None,
+        }
+    }
+
+    /// 实际请求的接口地址：优先 base_url，否则按接入区域
+    pub fn api_host(&self) -> &str {
+        match self.base_url.as_deref() {
+            Some(url) => url.trim_end_matches('/'),
+            None => self.region.wechat_host(),
+        }
+    }
+}
+
+/// 商户 API 证书签名身份
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SigningIdentity {
+    pub serial_no: String,
+    pub private_key_pem: String,
+    #[serde(default)]
+    pub private_key_passphrase: Option<String>,
+}
+
+impl fmt::Debug for SigningIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SigningIdentity")
+            .field("serial_no", &self.serial_no)
+            .field("private_key_pem", &REDACTED)
+            .field("private_key_passphrase", &redact_opt(&self.private_key_passphrase))
+            .finish()
+    }
+}
+
+impl fmt::Debug for WechatConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WechatConfig")
+            .field("mchid", &self.mchid)
+            .field("appid", &self.appid)
+            .field("serial_no", &self.serial_no)
+            .field("private_key_pem", &REDACTED)
+            .field("api_v3_key", &REDACTED)
+            .field("platform_public_key_pem", &self.platform_public_key_pem)
+            .field("public_key_id", &self.public_key_id)
+            .field("platform_certs", &self.platform_certs.keys().collect::<Vec<_>>())
+            .field("platform_certs_dir", &self.platform_certs_dir)
+            .field("cert_refresh_ahead_hours", &self.cert_refresh_ahead_hours)
+            .field("appid_mp", &self.appid_mp)
+            .field("appid_mini", &self.appid_mini)
+            .field("appid_app", &self.appid_app)
+            .field("notify_url", &self.notify_url)
+            .field("sub_mchid", &self.sub_mchid)
+            .field("api_v2_key", &redact_opt(&self.api_v2_key))
+            .field("sign_algorithm", &self.sign_algorithm)
+            .field("region", &self.region)
+            .field("private_key_passphrase", &redact_opt(&self.private_key_passphrase))
+            .field("secondary_identity", &self.secondary_identity)
+            .field("client_cert_pem", &self.client_cert_pem.as_ref().map(|_| "<pem>"))
+            .field("client_cert_p12_path", &self.client_cert_p12_path)
+            .field("base_url", &self.base_url)
+            .field("backup_base_url", &self.backup_base_url)
+            .finish()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct AlipayConfig {
     pub app_id: String,
     pub private_key_pem: String,
@@ -44,6 +214,34 @@ pub struct AlipayConfig {
 
     //回调通知
     pub notify_url: Option<String>,
+
+    // 接入区域，gateway 为空时据此选择网关；非中国大陆区域必须配置 gateway
+    #[serde(default)]
+    pub region: Region,
+    // 加密私钥的口令
+    #[serde(default)]
+    pub private_key_passphrase: Option<String>,
+}
+
+impl fmt::Debug for AlipayConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AlipayConfig")
+            .field("app_id", &self.app_id)
+            .field("private_key_pem", &REDACTED)
+            .field("alipay_public_key", &self.alipay_public_key)
+            .field("app_cert_path", &self.app_cert_path)
+            .field("alipay_cert_path", &self.alipay_cert_path)
+            .field("alipay_root_cert_path", &self.alipay_root_cert_path)
+            .field("charset", &self.charset)
+            .field("sign_type", &self.sign_type)
+            .field("gateway", &self.gateway)
+            .field("sys_service_provider_id", &self.sys_service_provider_id)
+            .field("app_auth_token", &redact_opt(&self.app_auth_token))
+            .field("notify_url", &self.notify_url)
+            .field("region", &self.region)
+            .field("private_key_passphrase", &redact_opt(&self.private_key_passphrase))
+            .finish()
+    }
 }
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
@@ -58,21 +256,82 @@ pub struct WechatConfigOverride {
     // 其他可覆盖的微信配置字段...
 }
 
-#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub struct AlipayConfigOverride {
     // 服务商配置
     pub app_auth_token: Option<String>,
     // 其他可覆盖的支付宝配置字段...
 }
 
-#[derive(Clone)]
+impl fmt::Debug for AlipayConfigOverride {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AlipayConfigOverride")
+            .field("app_auth_token", &redact_opt(&self.app_auth_token))
+            .finish()
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub struct UnionpayConfig {
     pub mer_id: String,
+    // 商户签名证书（pfx）及密码
+    #[serde(default)]
+    pub sign_cert_path: Option<String>,
+    #[serde(default)]
+    pub sign_cert_pwd: Option<String>,
+    #[serde(default)]
+    pub back_url: Option<String>,
+    // 全渠道 5.1.0 验签：根证书与中级证书，用于校验报文中的 signPubKeyCert
+    #[serde(default)]
+    pub root_cert_path: Option<String>,
+    #[serde(default)]
+    pub middle_cert_path: Option<String>,
+    // 银联二维码产品的验签证书（按 certId 匹配）
+    #[serde(default)]
+    pub qr_verify_cert_path: Option<String>,
 }
-#[derive(Clone)]
+
+impl fmt::Debug for UnionpayConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnionpayConfig")
+            .field("mer_id", &self.mer_id)
+            .field("sign_cert_path", &self.sign_cert_path)
+            .field("sign_cert_pwd", &redact_opt(&self.sign_cert_pwd))
+            .field("back_url", &self.back_url)
+            .field("root_cert_path", &self.root_cert_path)
+            .field("middle_cert_path", &self.middle_cert_path)
+            .field("qr_verify_cert_path", &self.qr_verify_cert_path)
+            .finish()
+    }
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PayConfig {
     pub mode: Mode,
     pub wechat: Option<Arc<WechatConfig>>,
     pub alipay: Option<Arc<AlipayConfig>>,
     pub unionpay: Option<Arc<UnionpayConfig>>,
+    /// 网关域名静态解析：域名 -> IP 列表（可带端口，缺省 443），
+    /// 用于公共 DNS 不可用或被污染的网络
+    #[serde(default)]
+    pub dns_overrides: HashMap<String, Vec<String>>,
+}
+
+impl PayConfig {
+    /// 解析 dns_overrides 为 socket 地址
+    pub(crate) fn resolved_overrides(&self) -> Result<Vec<(String, Vec<SocketAddr>)>, PayError> {
+        self.dns_overrides
+            .iter()
+            .map(|(host, ips)| {
+                let addrs = ips
+                    .iter()
+                    .map(|ip| {
+                        ip.parse::<SocketAddr>()
+                            .or_else(|_| ip.parse::<IpAddr>().map(|ip| SocketAddr::new(ip, 443)))
+                            .map_err(|_| PayError::Other(format!("invalid dns override for {}: {}", host, ip)))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok((host.clone(), addrs))
+            })
+            .collect()
+    }
 }
@@ -13,6 +13,48 @@ pub enum PayError {
     Other(String),
     #[error("Alipay API error: {code} - {msg}")]
     Alipay { code: String, msg: String },
+    /// 支付宝业务错误，带 sub_code / sub_msg
+    #[error("Alipay API error: {code} - {msg} ({sub_code}: {sub_msg})")]
+    AlipayBusiness {
+        code: String,
+        msg: String,
+        sub_code: String,
+        sub_msg: String,
+    },
+    #[error("UnionPay error: {code} - {msg}")]
+    Unionpay { code: String, msg: String },
+    /// 微信支付 V3 接口返回非 2xx，code / message 取自应答体
+    #[error("WeChat API error: {status} {code} - {message} (Request-ID: {request_id})")]
+    Wechat {
+        status: u16,
+        code: String,
+        message: String,
+        request_id: String,
+    },
+    /// 微信 V2 接口 result_code=FAIL
+    #[error("WeChat V2 error: {err_code} - {err_code_des}")]
+    WechatV2 { err_code: String, err_code_des: String },
+    /// 回调验签通过，但未通过业务校验
+    #[error("notify rejected by {validator}: {reason}")]
+    NotifyRejected { validator: String, reason: String },
+    /// 下载的账单与申请应答中的摘要不一致（下载不完整或被篡改）
+    #[error("bill hash mismatch ({hash_type}): expected {expected}, got {actual}")]
+    BillHashMismatch {
+        hash_type: String,
+        expected: String,
+        actual: String,
+    },
+}
+
+/// 错误的重试分类
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RetryClass {
+    /// 可直接重试（网络错误等）
+    Retryable,
+    /// 结果未知，应先查询交易状态再决定是否重试
+    QueryBeforeRetry,
+    /// 确定性失败，重试无意义
+    Terminal,
 }
 
 impl PayError {
@@ -25,7 +67,69 @@ impl PayError {
             .and_then(|v| v.as_str())
             .unwrap_or("Unknown error")
             .to_string();
+        let sub_code = response.get("sub_code")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let sub_msg = response.get("sub_msg")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+
+        if sub_code.is_empty() {
+            PayError::Alipay { code, msg }
+        } else {
+            PayError::AlipayBusiness { code, msg, sub_code, sub_msg }
+        }
+    }
+
+    /// 按渠道语义对错误分类
+    ///
+    /// 银联：respCode 03/04/05 表示结果未明，应先查询。
+    ///
+    /// 支付宝：网关 20000（服务不可用）与 ACQ.SYSTEM_ERROR 表示处理结果未知，
+    /// 应先查询再重试；isv.* 参数/权限类错误及 ACQ.TRADE_HAS_CLOSE 等业务错误为终态。
+    ///
+    /// 微信 V3：429 限频可直接重试；5xx 结果未知，应先查询；其余 4xx（如 NOT_ENOUGH、
+    /// PARAM_ERROR）为明确拒绝。
+    ///
+    /// 微信 V2：err_code 为 SYSTEMERROR 时结果未知，应使用原单号查询后再决定是否重试。
+    pub fn retry_class(&self) -> RetryClass {
+        match self {
+            PayError::Http(e) if e.is_timeout() || e.is_connect() || e.is_request() => {
+                RetryClass::Retryable
+            }
+            PayError::Alipay { code, .. } if code == "20000" => RetryClass::QueryBeforeRetry,
+            PayError::AlipayBusiness { code, sub_code, .. } => {
+                let sub_code = sub_code.trim_start_matches("aop.");
+                if code == "20000" || sub_code == "ACQ.SYSTEM_ERROR" {
+                    RetryClass::QueryBeforeRetry
+                } else if sub_code.starts_with("isp.") {
+                    RetryClass::Retryable
+                } else {
+                    RetryClass::Terminal
+                }
+            }
+            // 银联：03/04/05 表示交易状态未明，需查询确认
+            PayError::Unionpay { code, .. } => match code.as_str() {
+                "03" | "04" | "05" => RetryClass::QueryBeforeRetry,
+                _ => RetryClass::Terminal,
+            },
+            PayError::Wechat { status, .. } => match status {
+                429 => RetryClass::Retryable,
+                s if *s >= 500 => RetryClass::QueryBeforeRetry,
+                _ => RetryClass::Terminal,
+            },
+            PayError::WechatV2 { err_code, .. } if err_code == "SYSTEMERROR" => {
+                RetryClass::QueryBeforeRetry
+            }
+            // 账单摘要不符多为下载被截断，重新下载即可
+            PayError::BillHashMismatch { .. } => RetryClass::Retryable,
+            _ => RetryClass::Terminal,
+        }
+    }
 
-        PayError::Alipay { code, msg }
+    pub fn is_retryable(&self) -> bool {
+        self.retry_class() != RetryClass::Terminal
     }
 }
\ No newline at end of file
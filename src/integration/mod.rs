@@ -0,0 +1,3 @@
+//! Web 框架集成：提取并验签回调通知，按渠道要求返回应答
+#[cfg(feature = "warp")]
+pub mod warp;
@@ -0,0 +1,64 @@
+use crate::alipay::AlipayNotifyData;
+use crate::channel::Channel;
+use crate::client::Pay;
+use crate::errors::PayError;
+use crate::notify::{NotifyAck, NotifyRequest};
+use ::warp::http::{header::CONTENT_TYPE, HeaderMap, Response, StatusCode};
+use ::warp::hyper::body::Bytes;
+use ::warp::{Filter, Rejection};
+use serde_json::Value;
+use std::collections::HashMap;
+
+// 回调报文体积上限
+const NOTIFY_BODY_LIMIT: u64 = 64 * 1024;
+
+/// 提取并验签、解密微信支付回调，得到解密后的 resource
+///
+/// 验签失败不会 reject，而是作为 Err 交给业务处理并返回失败应答。
+pub fn wechat_notify(
+) -> impl Filter<Extract = (Result<Value, PayError>,), Error = Rejection> + Clone {
+    ::warp::header::headers_cloned()
+        .and(::warp::body::content_length_limit(NOTIFY_BODY_LIMIT))
+        .and(::warp::body::bytes())
+        .and_then(|headers: HeaderMap, body: Bytes| async move {
+            let req = NotifyRequest::new("POST", body.to_vec()).with_header_map(&headers);
+            let result = match req.body_str() {
+                Ok(body) => Pay::wechat(None).handle_notify(req.headers.clone(), body).await,
+                Err(e) => Err(e),
+            };
+            Ok::<_, Rejection>(result)
+        })
+}
+
+/// 提取并验签支付宝异步通知（application/x-www-form-urlencoded）
+pub fn alipay_notify(
+) -> impl Filter<Extract = (Result<AlipayNotifyData, PayError>,), Error = Rejection> + Clone {
+    ::warp::body::content_length_limit(NOTIFY_BODY_LIMIT)
+        .and(::warp::body::form::<HashMap<String, String>>())
+        .map(|params: HashMap<String, String>| Pay::alipay(None).verify_notify(&params))
+}
+
+/// 构造渠道要求的应答
+pub fn ack(channel: Channel, ack: NotifyAck) -> Response<String> {
+    let content_type = match channel {
+        Channel::Wechat => "application/json",
+        _ => "text/plain; charset=utf-8",
+    };
+    let mut resp = Response::new(ack.body(channel).to_string());
+    *resp.status_mut() =
+        StatusCode::from_u16(ack.status_code(channel)).unwrap_or(StatusCode::OK);
+    if let Ok(v) = content_type.parse() {
+        resp.headers_mut().insert(CONTENT_TYPE, v);
+    }
+    resp
+}
+
+/// 按处理结果选择成功 / 失败应答
+pub fn ack_result<T, E>(channel: Channel, result: &Result<T, E>) -> Response<String> {
+    let status = if result.is_ok() {
+        NotifyAck::Success
+    } else {
+        NotifyAck::Fail
+    };
+    ack(channel, status)
+}
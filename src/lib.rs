@@ -1,9 +1,36 @@
 pub mod alipay;
+pub mod cert;
+pub mod channel;
 pub mod client;
 pub mod config;
 pub mod errors;
+pub mod integration;
+pub mod model;
+pub mod money;
+pub mod notify;
+pub mod order;
+pub mod poll;
+pub mod quota;
+pub mod report;
+pub mod router;
+pub mod split;
+pub mod store;
+#[cfg(feature = "test-util")]
+pub mod testing;
+pub mod unified;
 pub mod unionpay;
 pub mod utils;
+pub mod verify;
 pub mod wechat;
+pub use cert::CertificateInfo;
+pub use channel::{Capability, Channel, ChannelCapabilities};
 pub use client::Pay;
-pub use errors::PayError;
+pub use errors::{PayError, RetryClass};
+pub use model::{ParseMode, RawPayload};
+pub use notify::NotifyRequest;
+pub use poll::{PaymentOutcome, PaymentState};
+pub use quota::{MerchantQuota, QuotaManager};
+pub use split::{SplitAmount, SplitRule};
+pub use store::{MemoryOrderStore, OrderStore};
+pub use unified::{CloseOutcome, RefundStatus, UnifiedRefund};
+pub use verify::{verify_alipay_signature, verify_wechat_signature};
@@ -0,0 +1,60 @@
+//! 类型化应答的解析模式
+//!
+//! 渠道会不经通知新增字段。默认宽松解析：未知字段保存在 `extras` 中；
+//! 严格模式下出现未知字段即报错，用于及早发现接口变更。
+//! 类型化结果均可通过 [`RawPayload::raw`] 取得原始报文。
+use crate::errors::PayError;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+/// 解析模式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ParseMode {
+    /// 保留未知字段到 extras
+    #[default]
+    Lenient,
+    /// 出现未知字段时报错
+    Strict,
+}
+
+/// 带 extras（`#[serde(flatten)]` 收集的未知字段）的类型化模型
+pub trait WithExtras {
+    fn extras(&self) -> &Map<String, Value>;
+}
+
+/// 保留渠道原始报文的类型化模型
+///
+/// 渠道新增而本 crate 尚未建模的字段可从 raw() 读取。
+pub trait RawPayload {
+    fn raw(&self) -> &Value;
+}
+
+/// 将表单/XML 键值参数转为 JSON 对象
+pub(crate) fn params_to_value<'a, I>(params: I) -> Value
+where
+    I: IntoIterator<Item = (&'a String, &'a String)>,
+{
+    Value::Object(
+        params
+            .into_iter()
+            .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+            .collect(),
+    )
+}
+
+/// 按解析模式将 JSON 转为类型化模型
+pub fn parse_with_mode<T>(value: Value, mode: ParseMode) -> Result<T, PayError>
+where
+    T: DeserializeOwned + WithExtras,
+{
+    let parsed: T = serde_json::from_value(value)?;
+    if mode == ParseMode::Strict && !parsed.extras().is_empty() {
+        let fields: Vec<&str> = parsed.extras().keys().map(String::as_str).collect();
+        return Err(PayError::Other(format!(
+            "unexpected fields in {}: {}",
+            std::any::type_name::<T>(),
+            fields.join(", ")
+        )));
+    }
+    Ok(parsed)
+}
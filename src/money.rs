@@ -0,0 +1,86 @@
+//! 金额换算：整数分、元字符串与 Decimal 之间的转换，均做溢出与精度校验
+use crate::errors::PayError;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+
+/// 分转元字符串，如 1234 -> "12.34"
+pub fn fen_to_yuan(fen: i64) -> String {
+    let sign = if fen < 0 { "-" } else { "" };
+    let abs = fen.unsigned_abs();
+    format!("{}{}.{:02}", sign, abs / 100, abs % 100)
+}
+
+/// 元字符串转分，最多两位小数，超出精度或溢出时报错
+pub fn yuan_to_fen(yuan: &str) -> Result<i64, PayError> {
+    let d: Decimal = yuan
+        .trim()
+        .parse()
+        .map_err(|e| PayError::Other(format!("invalid amount '{}': {}", yuan, e)))?;
+    decimal_to_fen(d)
+}
+
+/// 分转 Decimal（元，保留两位小数）
+pub fn fen_to_decimal(fen: i64) -> Decimal {
+    Decimal::new(fen, 2)
+}
+
+/// Decimal（元）转分，小数超过两位时报错而非截断
+pub fn decimal_to_fen(yuan: Decimal) -> Result<i64, PayError> {
+    let fen = yuan
+        .checked_mul(Decimal::ONE_HUNDRED)
+        .ok_or_else(|| PayError::Other(format!("amount overflow: {}", yuan)))?;
+    if !fen.fract().is_zero() {
+        return Err(PayError::Other(format!(
+            "amount has more than two decimal places: {}",
+            yuan
+        )));
+    }
+    fen.to_i64()
+        .ok_or_else(|| PayError::Other(format!("amount overflow: {}", yuan)))
+}
+
+/// 分相加，溢出时报错
+pub fn checked_add_fen(a: i64, b: i64) -> Result<i64, PayError> {
+    a.checked_add(b)
+        .ok_or_else(|| PayError::Other(format!("amount overflow: {} + {}", a, b)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn fen_yuan_round_trip(fen in any::<i64>()) {
+            prop_assert_eq!(yuan_to_fen(&fen_to_yuan(fen)).unwrap(), fen);
+        }
+
+        #[test]
+        fn fen_decimal_round_trip(fen in any::<i64>()) {
+            prop_assert_eq!(decimal_to_fen(fen_to_decimal(fen)).unwrap(), fen);
+        }
+
+        #[test]
+        fn rejects_sub_fen_precision(fen in -1_000_000_000i64..1_000_000_000, milli in 1u32..10) {
+            let yuan = format!("{}{}", fen_to_yuan(fen), milli);
+            prop_assert!(yuan_to_fen(&yuan).is_err());
+        }
+
+        #[test]
+        fn checked_add_matches_i64(a in any::<i64>(), b in any::<i64>()) {
+            prop_assert_eq!(checked_add_fen(a, b).ok(), a.checked_add(b));
+        }
+    }
+
+    #[test]
+    fn parses_yuan_strings() {
+        assert_eq!(yuan_to_fen("12.34").unwrap(), 1234);
+        assert_eq!(yuan_to_fen(" 0.1 ").unwrap(), 10);
+        assert_eq!(yuan_to_fen("5").unwrap(), 500);
+        assert_eq!(yuan_to_fen("12.340").unwrap(), 1234);
+        assert_eq!(fen_to_yuan(-5), "-0.05");
+        assert!(yuan_to_fen("abc").is_err());
+        assert!(yuan_to_fen("92233720368547758.08").is_err());
+    }
+}
@@ -0,0 +1,370 @@
+use crate::channel::Channel;
+use crate::errors::PayError;
+use crate::money::yuan_to_fen;
+use crate::store::OrderStore;
+use reqwest::header::HeaderMap;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 与 HTTP 框架无关的回调请求
+///
+/// header 名统一转为小写，可直接交给 WechatNotify / AlipayNotify。
+#[derive(Clone, Debug, Default)]
+pub struct NotifyRequest {
+    pub method: String,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl NotifyRequest {
+    pub fn new(method: impl Into<String>, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            method: method.into().to_ascii_uppercase(),
+            headers: HashMap::new(),
+            body: body.into(),
+        }
+    }
+
+    /// 追加 header（名称转小写）
+    pub fn header(mut self, name: impl AsRef<str>, value: impl Into<String>) -> Self {
+        self.headers
+            .insert(name.as_ref().to_ascii_lowercase(), value.into());
+        self
+    }
+
+    /// 从任意 (名称, 值) 序列构造 header
+    pub fn with_headers<I, K, V>(mut self, headers: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: Into<String>,
+    {
+        for (k, v) in headers {
+            self.headers.insert(k.as_ref().to_ascii_lowercase(), v.into());
+        }
+        self
+    }
+
+    /// 从 http / hyper 的 HeaderMap 构造 header，非 UTF-8 的值被忽略
+    pub fn with_header_map(self, headers: &HeaderMap) -> Self {
+        self.with_headers(
+            headers
+                .iter()
+                .filter_map(|(k, v)| Some((k.as_str().to_string(), v.to_str().ok()?.to_string()))),
+        )
+    }
+
+    pub fn header_value(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
+    pub fn body_str(&self) -> Result<&str, PayError> {
+        std::str::from_utf8(&self.body)
+            .map_err(|e| PayError::Other(format!("notify body is not utf-8: {}", e)))
+    }
+
+    /// 按 application/x-www-form-urlencoded 解析报文体（支付宝、银联）
+    pub fn form_params(&self) -> HashMap<String, String> {
+        url::form_urlencoded::parse(&self.body)
+            .into_owned()
+            .collect()
+    }
+}
+
+/// 微信支付回调重发间隔（官方文档：15s/15s/30s/3m/10m/20m/30m/30m/30m/60m/3h/3h/3h/6h/6h，共 24h4m）
+pub const WECHAT_RETRY_SCHEDULE: &[Duration] = &[
+    Duration::from_secs(15),
+    Duration::from_secs(15),
+    Duration::from_secs(30),
+    Duration::from_secs(3 * 60),
+    Duration::from_secs(10 * 60),
+    Duration::from_secs(20 * 60),
+    Duration::from_secs(30 * 60),
+    Duration::from_secs(30 * 60),
+    Duration::from_secs(30 * 60),
+    Duration::from_secs(60 * 60),
+    Duration::from_secs(3 * 3600),
+    Duration::from_secs(3 * 3600),
+    Duration::from_secs(3 * 3600),
+    Duration::from_secs(6 * 3600),
+    Duration::from_secs(6 * 3600),
+];
+
+/// 支付宝异步通知重发间隔（官方文档：4m/10m/10m/1h/2h/6h/15h，25 小时内共 8 次）
+pub const ALIPAY_RETRY_SCHEDULE: &[Duration] = &[
+    Duration::from_secs(4 * 60),
+    Duration::from_secs(10 * 60),
+    Duration::from_secs(10 * 60),
+    Duration::from_secs(3600),
+    Duration::from_secs(2 * 3600),
+    Duration::from_secs(6 * 3600),
+    Duration::from_secs(15 * 3600),
+];
+
+/// 渠道公布的回调重发间隔，未公布的渠道返回空
+pub fn retry_schedule(channel: Channel) -> &'static [Duration] {
+    match channel {
+        Channel::Wechat => WECHAT_RETRY_SCHEDULE,
+        Channel::Alipay => ALIPAY_RETRY_SCHEDULE,
+        Channel::Unionpay => &[],
+    }
+}
+
+/// 回调应答
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NotifyAck {
+    Success,
+    Fail,
+}
+
+impl NotifyAck {
+    /// 应答 HTTP 状态码：微信要求失败时返回 4XX/5XX，支付宝只看响应体
+    pub fn status_code(&self, channel: Channel) -> u16 {
+        match (self, channel) {
+            (NotifyAck::Fail, Channel::Wechat) => 500,
+            _ => 200,
+        }
+    }
+
+    /// 应答响应体
+    pub fn body(&self, channel: Channel) -> &'static str {
+        match (self, channel) {
+            (NotifyAck::Success, Channel::Wechat) => r#"{"code":"SUCCESS","message":"成功"}"#,
+            (NotifyAck::Fail, Channel::Wechat) => r#"{"code":"FAIL","message":"失败"}"#,
+            (NotifyAck::Success, Channel::Alipay) => "success",
+            (NotifyAck::Fail, Channel::Alipay) => "failure",
+            (NotifyAck::Success, Channel::Unionpay) => "ok",
+            (NotifyAck::Fail, Channel::Unionpay) => "fail",
+        }
+    }
+}
+
+/// 业务处理失败的回调，交由应用内部队列补偿
+#[derive(Clone, Debug)]
+pub struct FailedNotify {
+    pub channel: Channel,
+    /// 回调原始内容（微信为解密后的 JSON，支付宝为表单）
+    pub payload: String,
+    pub error: String,
+}
+
+/// 内部补偿队列
+pub trait FailedNotifyQueue: Send + Sync {
+    fn enqueue(&self, failed: FailedNotify);
+}
+
+/// 回调重发策略
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RedeliveryStrategy {
+    /// 处理失败返回 FAIL，由渠道按重发间隔再次通知（至少一次）
+    AtLeastOnce,
+    /// 处理失败仍返回 SUCCESS 抑制渠道重发，失败事件进入内部队列
+    SuppressDuplicates,
+}
+
+/// 决定业务处理失败时的回调应答
+///
+/// 验签失败的请求不属于业务失败，应直接返回 FAIL，不经过该策略。
+#[derive(Clone)]
+pub struct RedeliveryPolicy {
+    strategy: RedeliveryStrategy,
+    queue: Option<Arc<dyn FailedNotifyQueue>>,
+}
+
+impl RedeliveryPolicy {
+    pub fn at_least_once() -> Self {
+        Self {
+            strategy: RedeliveryStrategy::AtLeastOnce,
+            queue: None,
+        }
+    }
+
+    pub fn suppress_duplicates(queue: Arc<dyn FailedNotifyQueue>) -> Self {
+        Self {
+            strategy: RedeliveryStrategy::SuppressDuplicates,
+            queue: Some(queue),
+        }
+    }
+
+    pub fn strategy(&self) -> RedeliveryStrategy {
+        self.strategy
+    }
+
+    /// 根据业务处理结果决定应答
+    pub fn decide<T, E: std::fmt::Display>(
+        &self,
+        channel: Channel,
+        payload: &str,
+        handled: &Result<T, E>,
+    ) -> NotifyAck {
+        let err = match handled {
+            Ok(_) => return NotifyAck::Success,
+            Err(e) => e,
+        };
+        match (&self.strategy, &self.queue) {
+            (RedeliveryStrategy::SuppressDuplicates, Some(queue)) => {
+                tracing::warn!("{} notify handler failed, queued: {}", channel, err);
+                queue.enqueue(FailedNotify {
+                    channel,
+                    payload: payload.to_string(),
+                    error: err.to_string(),
+                });
+                NotifyAck::Success
+            }
+            _ => {
+                tracing::warn!("{} notify handler failed, ask for redelivery: {}", channel, err);
+                NotifyAck::Fail
+            }
+        }
+    }
+}
+
+impl Default for RedeliveryPolicy {
+    fn default() -> Self {
+        Self::at_least_once()
+    }
+}
+
+/// 验签后的业务校验器，在交给业务处理前执行
+///
+/// `payload` 为微信解密后的 JSON 或支付宝 / 银联的原始参数。
+/// 返回 Err 时携带拒绝原因。
+pub trait NotifyValidator: Send + Sync {
+    fn name(&self) -> &str;
+    fn validate(&self, channel: Channel, payload: &Value) -> Result<(), String>;
+}
+
+/// 回调业务校验器注册表，按注册顺序执行，首个失败即拒绝
+#[derive(Clone, Default)]
+pub struct NotifyValidators {
+    validators: Vec<Arc<dyn NotifyValidator>>,
+}
+
+impl NotifyValidators {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(mut self, validator: Arc<dyn NotifyValidator>) -> Self {
+        self.validators.push(validator);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.validators.is_empty()
+    }
+
+    /// 依次执行校验，失败返回 PayError::NotifyRejected
+    pub fn validate(&self, channel: Channel, payload: &Value) -> Result<(), PayError> {
+        for v in &self.validators {
+            if let Err(reason) = v.validate(channel, payload) {
+                tracing::warn!("{} notify rejected by {}: {}", channel, v.name(), reason);
+                return Err(PayError::NotifyRejected {
+                    validator: v.name().to_string(),
+                    reason,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+fn payload_str<'a>(payload: &'a Value, keys: &[&str]) -> Option<&'a str> {
+    keys.iter()
+        .find_map(|k| payload.get(*k).and_then(|v| v.as_str()))
+        .filter(|s| !s.is_empty())
+}
+
+/// 校验回调中的商户号：微信 mchid / sp_mchid，支付宝 seller_id，银联 merId
+///
+/// 回调未携带对应字段时跳过。
+pub struct MerchantValidator {
+    expected: String,
+}
+
+impl MerchantValidator {
+    pub fn new(expected: impl Into<String>) -> Self {
+        Self {
+            expected: expected.into(),
+        }
+    }
+}
+
+impl NotifyValidator for MerchantValidator {
+    fn name(&self) -> &str {
+        "merchant"
+    }
+
+    fn validate(&self, channel: Channel, payload: &Value) -> Result<(), String> {
+        let keys: &[&str] = match channel {
+            Channel::Wechat => &["sp_mchid", "mchid"],
+            Channel::Alipay => &["seller_id"],
+            Channel::Unionpay => &["merId"],
+        };
+        match payload_str(payload, keys) {
+            Some(actual) if actual != self.expected => Err(format!(
+                "merchant mismatch: expected {}, got {}",
+                self.expected, actual
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 校验订单存在且金额与本地登记一致（单位分）
+///
+/// 回调未携带订单号或金额时跳过。
+pub struct OrderAmountValidator {
+    store: Arc<dyn OrderStore>,
+}
+
+impl OrderAmountValidator {
+    pub fn new(store: Arc<dyn OrderStore>) -> Self {
+        Self { store }
+    }
+}
+
+impl NotifyValidator for OrderAmountValidator {
+    fn name(&self) -> &str {
+        "order_amount"
+    }
+
+    fn validate(&self, channel: Channel, payload: &Value) -> Result<(), String> {
+        let (out_trade_no, amount) = match channel {
+            Channel::Wechat => (
+                payload_str(payload, &["out_trade_no"]),
+                payload.pointer("/amount/total").and_then(|v| v.as_i64()),
+            ),
+            Channel::Alipay => (
+                payload_str(payload, &["out_trade_no"]),
+                match payload_str(payload, &["total_amount"]) {
+                    Some(yuan) => Some(yuan_to_fen(yuan).map_err(|e| e.to_string())?),
+                    None => None,
+                },
+            ),
+            Channel::Unionpay => (
+                payload_str(payload, &["orderId", "orderNo"]),
+                payload_str(payload, &["txnAmt"]).and_then(|s| s.parse::<i64>().ok()),
+            ),
+        };
+        let out_trade_no = match out_trade_no {
+            Some(no) => no,
+            None => return Ok(()),
+        };
+        let total = self
+            .store
+            .order_total(out_trade_no)
+            .ok_or_else(|| format!("order {} not found", out_trade_no))?;
+        match amount {
+            Some(amount) if amount != total => Err(format!(
+                "amount mismatch for {}: expected {}, got {}",
+                out_trade_no, total, amount
+            )),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,280 @@
+//! 下单参数的类型化辅助
+//!
+//! 各渠道下单接口仍接收 serde_json::Value，这里提供对易错字段的封装。
+use crate::channel::Channel;
+use crate::errors::PayError;
+use crate::money::{checked_add_fen, decimal_to_fen, yuan_to_fen};
+use rust_decimal::Decimal;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// 微信 attach 最长 128 字符（见微信支付 API v3 下单接口 attach 字段）
+const WECHAT_ATTACH_MAX: usize = 128;
+/// 支付宝 passback_params 最长 512 字符（见 alipay.trade.* 下单接口 passback_params 字段）
+const ALIPAY_PASSBACK_MAX: usize = 512;
+/// 银联 reqReserved 最长 1024 字符（见银联全渠道平台接口规范 reqReserved 字段，ANS1..1024）
+const UNIONPAY_REQ_RESERVED_MAX: usize = 1024;
+
+/// 写入透传参数：微信 attach / 支付宝 passback_params / 银联 reqReserved
+///
+/// data 序列化为 JSON；支付宝要求 URL 编码后传递。
+pub fn set_passthrough<T: Serialize>(
+    order: &mut Value,
+    channel: Channel,
+    data: &T,
+) -> Result<(), PayError> {
+    let json = serde_json::to_string(data)?;
+    let (key, value, max) = match channel {
+        Channel::Wechat => ("attach", json, WECHAT_ATTACH_MAX),
+        Channel::Alipay => (
+            "passback_params",
+            urlencoding::encode(&json),
+            ALIPAY_PASSBACK_MAX,
+        ),
+        Channel::Unionpay => ("reqReserved", json, UNIONPAY_REQ_RESERVED_MAX),
+    };
+    if value.chars().count() > max {
+        return Err(PayError::Other(format!(
+            "{} too long: {} > {}",
+            key,
+            value.chars().count(),
+            max
+        )));
+    }
+    order[key] = Value::String(value);
+    Ok(())
+}
+
+/// 从查询结果或回调中读取透传参数，未携带时返回 None
+pub fn get_passthrough<T: DeserializeOwned>(
+    payload: &Value,
+    channel: Channel,
+) -> Result<Option<T>, PayError> {
+    let key = match channel {
+        Channel::Wechat => "attach",
+        Channel::Alipay => "passback_params",
+        Channel::Unionpay => "reqReserved",
+    };
+    let raw = match payload.get(key).and_then(|v| v.as_str()) {
+        Some(s) if !s.is_empty() => s,
+        _ => return Ok(None),
+    };
+    let json = match channel {
+        Channel::Alipay => urlencoding::decode(raw)
+            .map_err(|e| PayError::Other(format!("decode passback_params: {}", e)))?,
+        _ => raw.to_string(),
+    };
+    Ok(Some(serde_json::from_str(&json)?))
+}
+
+/// 设置下单时的分账标记：微信 settle_info.profit_sharing，支付宝 extend_params.royalty_freeze
+///
+/// 需在下单时开启，支付完成后才能发起分账；未开启的订单资金会直接结算给商户。
+pub fn set_profit_sharing(order: &mut Value, channel: Channel, enabled: bool) -> Result<(), PayError> {
+    let (parent, key, value) = match channel {
+        Channel::Wechat => ("settle_info", "profit_sharing", Value::Bool(enabled)),
+        Channel::Alipay => ("extend_params", "royalty_freeze", Value::String(enabled.to_string())),
+        Channel::Unionpay => {
+            return Err(PayError::Other("unionpay profit sharing not supported".into()))
+        }
+    };
+    match order.get(parent) {
+        None | Some(Value::Null) | Some(Value::Object(_)) => {}
+        Some(other) => {
+            return Err(PayError::Other(format!("{} must be an object, got {}", parent, other)))
+        }
+    }
+    order[parent][key] = value;
+    Ok(())
+}
+
+/// 微信单品优惠商品明细（detail.goods_detail）
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WechatGoodsDetail {
+    /// 商户侧商品编码
+    pub merchant_goods_id: String,
+    /// 微信支付定义的统一商品编号
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wechatpay_goods_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub goods_name: Option<String>,
+    pub quantity: i64,
+    /// 商品单价，单位分
+    pub unit_price: i64,
+}
+
+/// 支付宝商品明细（goods_detail）
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AlipayGoodsDetail {
+    pub goods_id: String,
+    pub goods_name: String,
+    pub quantity: i64,
+    /// 商品单价，单位元
+    pub price: Decimal,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub goods_category: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+}
+
+// 明细合计（分），数量或单价非法、溢出时报错
+fn goods_total<I>(lines: I) -> Result<i64, PayError>
+where
+    I: IntoIterator<Item = (i64, i64)>,
+{
+    let mut total = 0i64;
+    for (quantity, unit_price) in lines {
+        if quantity <= 0 || unit_price < 0 {
+            return Err(PayError::Other(format!(
+                "invalid goods line: quantity={}, unit_price={}",
+                quantity, unit_price
+            )));
+        }
+        let line = quantity
+            .checked_mul(unit_price)
+            .ok_or_else(|| PayError::Other("goods amount overflow".into()))?;
+        total = checked_add_fen(total, line)?;
+    }
+    Ok(total)
+}
+
+/// 写入微信商品明细；订单已设置 amount.total 时校验明细合计与之相等
+pub fn set_wechat_goods_detail(
+    order: &mut Value,
+    goods: &[WechatGoodsDetail],
+) -> Result<(), PayError> {
+    let sum = goods_total(goods.iter().map(|g| (g.quantity, g.unit_price)))?;
+    if let Some(total) = order.pointer("/amount/total").and_then(|v| v.as_i64()) {
+        if sum != total {
+            return Err(PayError::Other(format!(
+                "goods_detail sum {} != amount.total {}",
+                sum, total
+            )));
+        }
+    }
+    order["detail"]["goods_detail"] = serde_json::to_value(goods)?;
+    Ok(())
+}
+
+/// 写入支付宝商品明细；订单已设置 total_amount 时校验明细合计与之相等
+pub fn set_alipay_goods_detail(
+    order: &mut Value,
+    goods: &[AlipayGoodsDetail],
+) -> Result<(), PayError> {
+    let lines = goods
+        .iter()
+        .map(|g| Ok((g.quantity, decimal_to_fen(g.price)?)))
+        .collect::<Result<Vec<_>, PayError>>()?;
+    let sum = goods_total(lines)?;
+    let total = match order.get("total_amount") {
+        Some(Value::String(s)) => Some(yuan_to_fen(s)?),
+        Some(Value::Number(n)) => Some(yuan_to_fen(&n.to_string())?),
+        _ => None,
+    };
+    if let Some(total) = total {
+        if sum != total {
+            return Err(PayError::Other(format!(
+                "goods_detail sum {} != total_amount {}",
+                sum, total
+            )));
+        }
+    }
+    order["goods_detail"] = serde_json::to_value(goods)?;
+    Ok(())
+}
+
+/// 微信门店信息（scene_info.store_info）
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WechatStoreInfo {
+    /// 商户侧门店编号
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// 地区编码，参考行政区划代码
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub area_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+}
+
+/// 微信支付场景信息（scene_info）
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WechatSceneInfo {
+    /// 用户终端 IP；为 None 时合并不会覆盖 scene_info 中已有的 IP
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payer_client_ip: Option<String>,
+    /// 商户端设备号（门店号或收银设备 ID）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub store_info: Option<WechatStoreInfo>,
+    /// H5 场景信息，H5 下单必填
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub h5_info: Option<WechatH5Info>,
+}
+
+/// H5 场景信息（scene_info.h5_info）
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WechatH5Info {
+    /// 场景类型：iOS / Android / Wap
+    #[serde(rename = "type")]
+    pub h5_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_url: Option<String>,
+}
+
+/// 支付宝线下终端与门店参数（alipay.trade.pay 等）
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AlipayTerminalInfo {
+    /// 商户门店编号
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub store_id: Option<String>,
+    /// 商户机具终端编号
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub terminal_id: Option<String>,
+    /// 商户操作员编号
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator_id: Option<String>,
+    /// 支付宝侧门店编号
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alipay_store_id: Option<String>,
+}
+
+// 将 src 的字段合并进 order[key]（保留已有的其他字段，如 h5_info）
+fn merge_object(order: &mut Value, key: Option<&str>, src: Value) -> Result<(), PayError> {
+    let target = match key {
+        Some(k) => {
+            if order.get(k).is_none_or(Value::is_null) {
+                order[k] = Value::Object(Default::default());
+            }
+            &mut order[k]
+        }
+        None => order,
+    };
+    match (target, src) {
+        (Value::Object(dst), Value::Object(src)) => {
+            dst.extend(src);
+            Ok(())
+        }
+        _ => Err(PayError::Other(format!(
+            "{} must be an object",
+            key.unwrap_or("order")
+        ))),
+    }
+}
+
+/// 写入微信场景信息（设备号、门店），与已有 scene_info 合并
+pub fn set_wechat_scene_info(order: &mut Value, scene: &WechatSceneInfo) -> Result<(), PayError> {
+    merge_object(order, Some("scene_info"), serde_json::to_value(scene)?)
+}
+
+/// 写入支付宝终端与门店参数
+pub fn set_alipay_terminal_info(
+    order: &mut Value,
+    terminal: &AlipayTerminalInfo,
+) -> Result<(), PayError> {
+    merge_object(order, None, serde_json::to_value(terminal)?)
+}
@@ -0,0 +1,62 @@
+//! 支付 / 退款结果轮询的公共类型
+use crate::model::RawPayload;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+/// 轮询得到的交易终态
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentState {
+    /// 支付成功（含已转入退款）
+    Paid,
+    /// 已关闭或已撤销
+    Closed,
+    /// 支付失败
+    Failed,
+    /// 截止时间内未到达终态
+    TimedOut,
+}
+
+/// 轮询结果
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PaymentOutcome {
+    pub state: PaymentState,
+    /// 超时后是否已关单 / 撤销
+    pub closed_on_timeout: bool,
+    /// 最后一次查询的原始应答
+    pub raw: Value,
+}
+
+impl RawPayload for PaymentOutcome {
+    fn raw(&self) -> &Value {
+        &self.raw
+    }
+}
+
+/// 轮询退避：1s 起步，每次乘 1.5，最长 5s，且不超过截止时间
+pub(crate) struct PollBackoff {
+    delay: Duration,
+    deadline: Instant,
+}
+
+impl PollBackoff {
+    pub(crate) fn new(deadline: Instant) -> Self {
+        Self {
+            delay: Duration::from_secs(1),
+            deadline,
+        }
+    }
+
+    /// 等待下一次查询，已过截止时间时返回 false
+    pub(crate) async fn wait(&mut self) -> bool {
+        let now = Instant::now();
+        if now >= self.deadline {
+            return false;
+        }
+        let sleep = self.delay.min(self.deadline - now);
+        tokio::time::sleep(sleep).await;
+        self.delay = (self.delay.mul_f32(1.5)).min(Duration::from_secs(5));
+        true
+    }
+}
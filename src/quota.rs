@@ -0,0 +1,122 @@
+//! 按商户的并发 / TPS 配额
+//!
+//! 服务商在同一进程内为大量子商户发起请求时，为每个商户单独限流，
+//! 避免单个商户的突发流量（如批量退款）占满连接或触发渠道限频。
+//! 同一商户的等待者按先来先服务排队。
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+
+/// 单个商户的配额
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MerchantQuota {
+    /// 最大在途请求数
+    pub max_concurrency: usize,
+    /// 每秒最多发起的请求数，0 表示不限
+    pub tps: u32,
+}
+
+impl Default for MerchantQuota {
+    fn default() -> Self {
+        Self {
+            max_concurrency: 16,
+            tps: 0,
+        }
+    }
+}
+
+struct MerchantSlot {
+    semaphore: Arc<Semaphore>,
+    interval: Option<Duration>,
+    // 下一个可用的发送时刻
+    next: Mutex<Instant>,
+}
+
+impl MerchantSlot {
+    fn new(quota: MerchantQuota) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(quota.max_concurrency.max(1))),
+            interval: (quota.tps > 0).then(|| Duration::from_secs(1) / quota.tps),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+}
+
+/// 配额许可，释放时归还并发名额
+pub struct QuotaPermit {
+    _permit: OwnedSemaphorePermit,
+}
+
+/// 按商户号管理配额
+pub struct QuotaManager {
+    default: MerchantQuota,
+    overrides: HashMap<String, MerchantQuota>,
+    slots: Mutex<HashMap<String, Arc<MerchantSlot>>>,
+}
+
+impl QuotaManager {
+    /// 所有商户使用同一默认配额
+    pub fn new(default: MerchantQuota) -> Self {
+        Self {
+            default,
+            overrides: HashMap::new(),
+            slots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 为指定商户单独设置配额
+    pub fn with_merchant(mut self, merchant_id: impl Into<String>, quota: MerchantQuota) -> Self {
+        self.overrides.insert(merchant_id.into(), quota);
+        self
+    }
+
+    fn slot(&self, merchant_id: &str) -> Arc<MerchantSlot> {
+        self.slots
+            .lock()
+            .unwrap()
+            .entry(merchant_id.to_string())
+            .or_insert_with(|| {
+                let quota = self
+                    .overrides
+                    .get(merchant_id)
+                    .copied()
+                    .unwrap_or(self.default);
+                Arc::new(MerchantSlot::new(quota))
+            })
+            .clone()
+    }
+
+    /// 等待该商户的并发名额与发送时间片
+    pub async fn acquire(&self, merchant_id: &str) -> QuotaPermit {
+        let slot = self.slot(merchant_id);
+        // tokio 信号量按等待顺序分配，保证同一商户内公平
+        let permit = slot
+            .semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("quota semaphore closed");
+        if let Some(interval) = slot.interval {
+            let at = {
+                let mut next = slot.next.lock().unwrap();
+                let at = (*next).max(Instant::now());
+                *next = at + interval;
+                at
+            };
+            tokio::time::sleep_until(at).await;
+        }
+        QuotaPermit { _permit: permit }
+    }
+
+    /// 在配额内执行 fut
+    pub async fn run<F, T>(&self, merchant_id: &str, fut: F) -> T
+    where
+        F: Future<Output = T>,
+    {
+        let _permit = self.acquire(merchant_id).await;
+        fut.await
+    }
+}
@@ -0,0 +1,156 @@
+//! 日结算报表：汇总微信资金账单与支付宝账务明细，按日、按商户输出
+use crate::channel::Channel;
+use crate::errors::PayError;
+use crate::money::{fen_to_yuan, yuan_to_fen};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// 单日单商户结算汇总，金额单位分
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettlementSummary {
+    /// yyyy-MM-dd
+    pub date: String,
+    pub channel: Option<Channel>,
+    pub merchant_id: String,
+    /// 交易收入
+    pub gross: i64,
+    /// 退款支出
+    pub refunds: i64,
+    /// 手续费
+    pub fees: i64,
+    /// 净结算 = gross - refunds - fees
+    pub net: i64,
+}
+
+/// 流水分类
+enum FlowKind {
+    Gross,
+    Refund,
+    Fee,
+}
+
+#[derive(Default)]
+pub struct SettlementReport {
+    rows: BTreeMap<(String, &'static str, String), SettlementSummary>,
+}
+
+impl SettlementReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn add(&mut self, channel: Channel, merchant_id: &str, date: &str, kind: FlowKind, fen: i64) {
+        let key = (date.to_string(), channel.as_str(), merchant_id.to_string());
+        let row = self.rows.entry(key).or_insert_with(|| SettlementSummary {
+            date: date.to_string(),
+            channel: Some(channel),
+            merchant_id: merchant_id.to_string(),
+            ..Default::default()
+        });
+        let fen = fen.abs();
+        match kind {
+            FlowKind::Gross => row.gross += fen,
+            FlowKind::Refund => row.refunds += fen,
+            FlowKind::Fee => row.fees += fen,
+        }
+        row.net = row.gross - row.refunds - row.fees;
+    }
+
+    /// 汇入微信资金账单（fundflowbill 下载的 CSV 文本）
+    ///
+    /// 列：记账时间,微信支付业务单号,资金流水单号,业务名称,业务类型,收支类型,收支金额（元）,...
+    /// 字段以反引号 ` 开头；末尾汇总行被忽略。提现、充值等非交易流水不计入。
+    pub fn add_wechat_fundflow(&mut self, merchant_id: &str, csv: &str) -> Result<(), PayError> {
+        for line in csv.lines().skip(1) {
+            let cols: Vec<&str> = line
+                .split(',')
+                .map(|c| c.trim().trim_start_matches('`'))
+                .collect();
+            if cols.len() < 7 || !cols[0].starts_with(|c: char| c.is_ascii_digit()) {
+                continue;
+            }
+            let date = cols[0].get(..10).unwrap_or(cols[0]);
+            let (biz_type, in_out, amount) = (cols[4], cols[5], cols[6]);
+            let kind = if biz_type.contains("退款") {
+                FlowKind::Refund
+            } else if biz_type.contains("手续费") {
+                FlowKind::Fee
+            } else if biz_type.contains("交易") && in_out.contains("收入") {
+                FlowKind::Gross
+            } else {
+                continue;
+            };
+            self.add(
+                Channel::Wechat,
+                merchant_id,
+                date,
+                kind,
+                yuan_to_fen(amount)?,
+            );
+        }
+        Ok(())
+    }
+
+    /// 汇入支付宝账务明细（alipay.data.bill.accountlog.query 的 detail_list）
+    pub fn add_alipay_accountlog(
+        &mut self,
+        merchant_id: &str,
+        details: &[Value],
+    ) -> Result<(), PayError> {
+        for item in details {
+            let get = |k: &str| item.get(k).and_then(|v| v.as_str()).unwrap_or_default();
+            let trans_type = get("type");
+            let date = get("trans_dt").get(..10).unwrap_or_default().to_string();
+            let kind = if trans_type.contains("退款") {
+                FlowKind::Refund
+            } else if trans_type.contains("服务费")
+                || trans_type.contains("手续费")
+                || trans_type.contains("收费")
+            {
+                FlowKind::Fee
+            } else if (trans_type.contains("交易") || trans_type.contains("在线支付"))
+                && get("direction") == "收入"
+            {
+                FlowKind::Gross
+            } else {
+                continue;
+            };
+            self.add(
+                Channel::Alipay,
+                merchant_id,
+                &date,
+                kind,
+                yuan_to_fen(get("trans_amount"))?,
+            );
+        }
+        Ok(())
+    }
+
+    /// 按日期、渠道、商户排序的汇总
+    pub fn summaries(&self) -> Vec<SettlementSummary> {
+        self.rows.values().cloned().collect()
+    }
+
+    pub fn to_json(&self) -> Result<String, PayError> {
+        Ok(serde_json::to_string(&self.summaries())?)
+    }
+
+    /// 导出 CSV，金额单位元
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("date,channel,merchant_id,gross,refunds,fees,net\n");
+        for s in self.rows.values() {
+            out.push_str(&format!(
+                "{},{},{},{},{},{},{}\n",
+                s.date,
+                s.channel.map(|c| c.as_str()).unwrap_or_default(),
+                s.merchant_id,
+                fen_to_yuan(s.gross),
+                fen_to_yuan(s.refunds),
+                fen_to_yuan(s.fees),
+                fen_to_yuan(s.net)
+            ));
+        }
+        out
+    }
+}
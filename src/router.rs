@@ -0,0 +1,206 @@
+use crate::channel::{parse_tag, Channel};
+use crate::client::Pay;
+use crate::errors::PayError;
+use crate::money::fen_to_yuan;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// 客户端所在平台
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Platform {
+    /// 微信内置浏览器（公众号）
+    WechatBrowser,
+    /// 微信小程序
+    WechatMiniProgram,
+    /// 支付宝客户端内
+    AlipayClient,
+    /// 手机浏览器
+    MobileWeb,
+    /// PC 浏览器
+    Pc,
+    /// 原生 App
+    App,
+}
+
+/// 路由上下文
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RouteContext {
+    pub platform: Platform,
+    /// 金额，单位分
+    pub amount: i64,
+    /// 商户偏好的渠道标签，如 "alipay:page"
+    pub preferred: Option<String>,
+    pub client_ip: Option<String>,
+}
+
+/// 路由下单所需的通用订单信息
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RouteOrder {
+    pub out_trade_no: String,
+    pub description: String,
+    /// 金额，单位分
+    pub amount: i64,
+    /// 微信 openid 或支付宝 buyer_id（公众号 / 小程序支付需要）
+    pub payer_id: Option<String>,
+}
+
+/// 路由规则：命中时返回渠道标签（如 "wechat:mp"），否则交由下一条规则
+pub trait RouteRule: Send + Sync {
+    fn route(&self, ctx: &RouteContext) -> Option<String>;
+}
+
+/// 渠道近期成功率来源（由业务方的监控指标提供）
+pub trait SuccessRateSource: Send + Sync {
+    /// 返回 0.0 ~ 1.0，无数据时返回 None
+    fn success_rate(&self, tag: &str) -> Option<f64>;
+}
+
+/// 商户偏好优先
+pub struct PreferenceRule;
+
+impl RouteRule for PreferenceRule {
+    fn route(&self, ctx: &RouteContext) -> Option<String> {
+        ctx.preferred.clone()
+    }
+}
+
+/// 按客户端平台选择产品
+pub struct PlatformRule;
+
+impl RouteRule for PlatformRule {
+    fn route(&self, ctx: &RouteContext) -> Option<String> {
+        let tag = match ctx.platform {
+            Platform::WechatBrowser => "wechat:mp",
+            Platform::WechatMiniProgram => "wechat:miniapp",
+            Platform::AlipayClient => "alipay:h5",
+            Platform::MobileWeb => "wechat:h5",
+            Platform::Pc => "alipay:page",
+            Platform::App => "wechat:app",
+        };
+        Some(tag.to_string())
+    }
+}
+
+/// 金额区间规则：amount 落在 [min, max] 时路由到指定标签
+pub struct AmountRule {
+    pub min: i64,
+    pub max: i64,
+    pub tag: String,
+}
+
+impl RouteRule for AmountRule {
+    fn route(&self, ctx: &RouteContext) -> Option<String> {
+        (ctx.amount >= self.min && ctx.amount <= self.max).then(|| self.tag.clone())
+    }
+}
+
+/// 成功率规则：在候选标签中选择成功率最高且不低于阈值者
+pub struct SuccessRateRule {
+    pub candidates: Vec<String>,
+    pub min_rate: f64,
+    pub source: Arc<dyn SuccessRateSource>,
+}
+
+impl RouteRule for SuccessRateRule {
+    fn route(&self, _ctx: &RouteContext) -> Option<String> {
+        self.candidates
+            .iter()
+            .filter_map(|tag| self.source.success_rate(tag).map(|r| (tag, r)))
+            .filter(|(_, r)| *r >= self.min_rate)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(tag, _)| tag.clone())
+    }
+}
+
+/// 支付渠道路由器，按规则顺序匹配，均未命中时使用 fallback
+pub struct PaymentRouter {
+    rules: Vec<Box<dyn RouteRule>>,
+    fallback: String,
+}
+
+impl PaymentRouter {
+    pub fn new(fallback: impl Into<String>) -> Self {
+        Self {
+            rules: Vec::new(),
+            fallback: fallback.into(),
+        }
+    }
+
+    pub fn rule(mut self, rule: impl RouteRule + 'static) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// 计算渠道标签
+    pub fn route(&self, ctx: &RouteContext) -> String {
+        self.rules
+            .iter()
+            .find_map(|r| r.route(ctx))
+            .unwrap_or_else(|| self.fallback.clone())
+    }
+
+    /// 路由并下单，返回 (渠道标签, 可直接交给前端的支付参数)
+    pub async fn pay(
+        &self,
+        ctx: &RouteContext,
+        order: &RouteOrder,
+    ) -> Result<(String, Value), PayError> {
+        let tag = self.route(ctx);
+        let (channel, product) = parse_tag(&tag)?;
+        let product = product.unwrap_or_default();
+        let params = match channel {
+            Channel::Wechat => {
+                let client = Pay::wechat(None);
+                let mut body = json!({
+                    "description": order.description,
+                    "out_trade_no": order.out_trade_no,
+                    "amount": { "total": order.amount, "currency": "CNY" },
+                });
+                if let Some(openid) = &order.payer_id {
+                    body["payer"] = json!({ "openid": openid });
+                }
+                match product.as_str() {
+                    "mp" | "jsapi" => client.mp(body).await?,
+                    "miniapp" => client.miniapp(body).await?,
+                    "h5" => {
+                        body["scene_info"] = json!({
+                            "payer_client_ip": ctx.client_ip.clone().unwrap_or_default(),
+                            "h5_info": { "type": "Wap" },
+                        });
+                        client.h5(body).await?
+                    }
+                    "app" => client.app(body).await?,
+                    "native" | "" => client.native(body).await?,
+                    other => return Err(unsupported(&tag, other)),
+                }
+            }
+            Channel::Alipay => {
+                let client = Pay::alipay(None);
+                let mut body = json!({
+                    "out_trade_no": order.out_trade_no,
+                    "subject": order.description,
+                    "total_amount": fen_to_yuan(order.amount),
+                });
+                if let Some(buyer_id) = &order.payer_id {
+                    body["buyer_id"] = json!(buyer_id);
+                }
+                match product.as_str() {
+                    "app" => client.app(body).await?,
+                    "h5" | "wap" => client.h5(body).await?,
+                    "page" | "" => client.page(body).await?,
+                    "scan" => client.scan(body).await?,
+                    "mini_program" => client.mini_program(body).await?,
+                    other => return Err(unsupported(&tag, other)),
+                }
+            }
+            Channel::Unionpay => return Err(unsupported(&tag, &product)),
+        };
+        Ok((tag, params))
+    }
+}
+
+fn unsupported(tag: &str, product: &str) -> PayError {
+    PayError::Other(format!("router: unsupported product '{}' in {}", product, tag))
+}
@@ -0,0 +1,186 @@
+//! 统一分账描述：一次描述分账规则，编译为微信分账请求或支付宝分账结算参数
+use crate::errors::PayError;
+use crate::money::fen_to_yuan;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// 分账接收方账户类型
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiverType {
+    /// 微信商户号
+    WechatMerchant,
+    /// 微信个人 openid
+    WechatOpenid,
+    /// 支付宝用户 ID（2088 开头）
+    AlipayUserId,
+    /// 支付宝登录账号
+    AlipayLogonId,
+}
+
+/// 分账金额：固定金额（分）或按比例（万分比）
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitAmount {
+    Fixed(i64),
+    /// 万分比，如 1000 表示 10%
+    Ratio(u32),
+}
+
+/// 分账规则
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SplitRule {
+    pub receiver_type: ReceiverType,
+    pub account: String,
+    /// 接收方姓名 / 名称（微信商户号类型且需校验时填写，明文，由调用方自行加密）
+    pub name: Option<String>,
+    pub amount: SplitAmount,
+    pub description: String,
+}
+
+impl SplitRule {
+    pub fn fixed(
+        receiver_type: ReceiverType,
+        account: impl Into<String>,
+        amount: i64,
+        description: impl Into<String>,
+    ) -> Self {
+        Self {
+            receiver_type,
+            account: account.into(),
+            name: None,
+            amount: SplitAmount::Fixed(amount),
+            description: description.into(),
+        }
+    }
+
+    pub fn ratio(
+        receiver_type: ReceiverType,
+        account: impl Into<String>,
+        basis_points: u32,
+        description: impl Into<String>,
+    ) -> Self {
+        Self {
+            receiver_type,
+            account: account.into(),
+            name: None,
+            amount: SplitAmount::Ratio(basis_points),
+            description: description.into(),
+        }
+    }
+}
+
+/// 按订单金额计算每条规则的分账金额（分），比例向下取整，合计不得超过 total
+pub fn resolve_amounts(rules: &[SplitRule], total: i64) -> Result<Vec<i64>, PayError> {
+    let mut amounts = Vec::with_capacity(rules.len());
+    let mut sum: i64 = 0;
+    for rule in rules {
+        let amount = match rule.amount {
+            SplitAmount::Fixed(v) => v,
+            SplitAmount::Ratio(bp) => {
+                if bp > 10_000 {
+                    return Err(PayError::Other(format!("split ratio over 100%: {}", bp)));
+                }
+                ((total as i128) * (bp as i128) / 10_000) as i64
+            }
+        };
+        if amount <= 0 {
+            return Err(PayError::Other(format!(
+                "split amount must be positive for {}",
+                rule.account
+            )));
+        }
+        sum = sum
+            .checked_add(amount)
+            .ok_or_else(|| PayError::Other("split amount overflow".into()))?;
+        amounts.push(amount);
+    }
+    if sum > total {
+        return Err(PayError::Other(format!(
+            "split total {} exceeds order amount {}",
+            sum, total
+        )));
+    }
+    Ok(amounts)
+}
+
+/// 编译为微信请求分账（/v3/profitsharing/orders）的请求体
+pub fn to_wechat_profitsharing(
+    rules: &[SplitRule],
+    transaction_id: &str,
+    out_order_no: &str,
+    total: i64,
+    unfreeze_unsplit: bool,
+) -> Result<Value, PayError> {
+    let amounts = resolve_amounts(rules, total)?;
+    let receivers = rules
+        .iter()
+        .zip(amounts)
+        .map(|(rule, amount)| {
+            let receiver_type = match rule.receiver_type {
+                ReceiverType::WechatMerchant => "MERCHANT_ID",
+                ReceiverType::WechatOpenid => "PERSONAL_OPENID",
+                _ => {
+                    return Err(PayError::Other(format!(
+                        "receiver {} is not a wechat account",
+                        rule.account
+                    )))
+                }
+            };
+            let mut receiver = json!({
+                "type": receiver_type,
+                "account": rule.account,
+                "amount": amount,
+                "description": rule.description,
+            });
+            if let Some(name) = &rule.name {
+                receiver["name"] = json!(name);
+            }
+            Ok(receiver)
+        })
+        .collect::<Result<Vec<_>, PayError>>()?;
+    Ok(json!({
+        "transaction_id": transaction_id,
+        "out_order_no": out_order_no,
+        "receivers": receivers,
+        "unfreeze_unsplit": unfreeze_unsplit,
+    }))
+}
+
+/// 编译为支付宝统一收单交易结算（alipay.trade.order.settle）的 biz_content
+pub fn to_alipay_settle(
+    rules: &[SplitRule],
+    trade_no: &str,
+    out_request_no: &str,
+    total: i64,
+) -> Result<Value, PayError> {
+    let amounts = resolve_amounts(rules, total)?;
+    let royalty_parameters = rules
+        .iter()
+        .zip(amounts)
+        .map(|(rule, amount)| {
+            let trans_in_type = match rule.receiver_type {
+                ReceiverType::AlipayUserId => "userId",
+                ReceiverType::AlipayLogonId => "loginName",
+                _ => {
+                    return Err(PayError::Other(format!(
+                        "receiver {} is not an alipay account",
+                        rule.account
+                    )))
+                }
+            };
+            Ok(json!({
+                "royalty_type": "transfer",
+                "trans_in_type": trans_in_type,
+                "trans_in": rule.account,
+                "amount": fen_to_yuan(amount),
+                "desc": rule.description,
+            }))
+        })
+        .collect::<Result<Vec<_>, PayError>>()?;
+    Ok(json!({
+        "out_request_no": out_request_no,
+        "trade_no": trade_no,
+        "royalty_parameters": royalty_parameters,
+    }))
+}
@@ -0,0 +1,102 @@
+//! 订单存储：记录订单金额与累计退款额，用于退款前的超额校验
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// 订单存储，由业务方实现（数据库、Redis 等）
+///
+/// 金额单位均为分。
+pub trait OrderStore: Send + Sync {
+    /// 登记订单总额
+    fn record_order(&self, out_trade_no: &str, total: i64);
+    /// 订单总额，未登记返回 None（此时不做超额校验）
+    fn order_total(&self, out_trade_no: &str) -> Option<i64>;
+    /// 累计已退款（含处理中）金额
+    fn refunded(&self, out_trade_no: &str) -> i64;
+    /// 调整累计退款额，用于人工对账修正
+    fn add_refunded(&self, out_trade_no: &str, amount: i64);
+    /// 为退款单 out_refund_no 占用 amount 额度，成功返回 true
+    ///
+    /// total 为 Some 时累计退款超过 total 则不做修改并返回 false；同一 out_refund_no 已占用时
+    /// 视为重试，直接返回 true。校验与占用须为一次原子操作（如数据库条件更新），
+    /// 避免并发退款同时通过校验。
+    fn try_reserve(
+        &self,
+        out_trade_no: &str,
+        out_refund_no: &str,
+        amount: i64,
+        total: Option<i64>,
+    ) -> bool;
+    /// 释放退款单的占用额度（退款被拒绝或关闭），未占用时无操作
+    fn release(&self, out_trade_no: &str, out_refund_no: &str);
+}
+
+#[derive(Default)]
+struct OrderEntry {
+    total: Option<i64>,
+    refunded: i64,
+    // 退款单号 -> 占用金额
+    refunds: HashMap<String, i64>,
+}
+
+/// 进程内订单存储，适用于单实例或测试
+#[derive(Default)]
+pub struct MemoryOrderStore {
+    orders: Mutex<HashMap<String, OrderEntry>>,
+}
+
+impl MemoryOrderStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl OrderStore for MemoryOrderStore {
+    fn record_order(&self, out_trade_no: &str, total: i64) {
+        let mut orders = self.orders.lock().unwrap();
+        orders.entry(out_trade_no.to_string()).or_default().total = Some(total);
+    }
+
+    fn order_total(&self, out_trade_no: &str) -> Option<i64> {
+        let orders = self.orders.lock().unwrap();
+        orders.get(out_trade_no).and_then(|o| o.total)
+    }
+
+    fn refunded(&self, out_trade_no: &str) -> i64 {
+        let orders = self.orders.lock().unwrap();
+        orders.get(out_trade_no).map(|o| o.refunded).unwrap_or(0)
+    }
+
+    fn add_refunded(&self, out_trade_no: &str, amount: i64) {
+        let mut orders = self.orders.lock().unwrap();
+        orders.entry(out_trade_no.to_string()).or_default().refunded += amount;
+    }
+
+    fn try_reserve(
+        &self,
+        out_trade_no: &str,
+        out_refund_no: &str,
+        amount: i64,
+        total: Option<i64>,
+    ) -> bool {
+        let mut orders = self.orders.lock().unwrap();
+        let order = orders.entry(out_trade_no.to_string()).or_default();
+        if order.refunds.contains_key(out_refund_no) {
+            return true;
+        }
+        if total.is_some_and(|total| order.refunded + amount > total) {
+            return false;
+        }
+        order.refunded += amount;
+        order.refunds.insert(out_refund_no.to_string(), amount);
+        true
+    }
+
+    fn release(&self, out_trade_no: &str, out_refund_no: &str) {
+        let mut orders = self.orders.lock().unwrap();
+        if let Some(order) = orders.get_mut(out_trade_no) {
+            if let Some(amount) = order.refunds.remove(out_refund_no) {
+                order.refunded -= amount;
+            }
+        }
+    }
+}
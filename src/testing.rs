@@ -0,0 +1,105 @@
+//! 测试工具（需开启 `test-util` feature）：用测试密钥生成带完整签名的回调请求，
+//! 便于下游应用离线集成测试回调接口
+use crate::config::WechatConfig;
+use crate::errors::PayError;
+use crate::notify::NotifyRequest;
+use crate::utils::{gen_nonce, now_ts, rsa_sign_sha256_pem};
+use crate::wechat::certs::PlatformCerts;
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::{engine::general_purpose, Engine as _};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// 构造微信支付 V3 回调请求
+///
+/// `platform_private_key_pem` 为模拟平台证书的私钥，其公钥需通过
+/// [`platform_certs_with`] 注入 `PlatformCerts`，序列号与 `serial` 一致。
+pub fn wechat_notify_request(
+    platform_private_key_pem: &str,
+    serial: &str,
+    api_v3_key: &str,
+    event_type: &str,
+    resource: &Value,
+) -> Result<NotifyRequest, PayError> {
+    let associated_data = "transaction";
+    let resource_nonce = gen_nonce(12);
+    let cipher = Aes256Gcm::new_from_slice(api_v3_key.as_bytes())
+        .map_err(|_| PayError::Crypto("api_v3_key must be 32 bytes".into()))?;
+    #[allow(deprecated)]
+    let nonce = Nonce::from_slice(resource_nonce.as_bytes());
+    let plain = resource.to_string();
+    let ciphertext = cipher
+        .encrypt(
+            nonce,
+            Payload {
+                msg: plain.as_bytes(),
+                aad: associated_data.as_bytes(),
+            },
+        )
+        .map_err(|e| PayError::Crypto(format!("aes-gcm encrypt: {}", e)))?;
+    let body = json!({
+        "id": format!("EV-{}", gen_nonce(16)),
+        "create_time": chrono::Local::now().to_rfc3339(),
+        "resource_type": "encrypt-resource",
+        "event_type": event_type,
+        "summary": "支付成功",
+        "resource": {
+            "original_type": "transaction",
+            "algorithm": "AEAD_AES_256_GCM",
+            "ciphertext": general_purpose::STANDARD.encode(ciphertext),
+            "associated_data": associated_data,
+            "nonce": resource_nonce,
+        }
+    })
+    .to_string();
+
+    let timestamp = now_ts();
+    let nonce_str = gen_nonce(32);
+    let message = format!("{}\n{}\n{}\n", timestamp, nonce_str, body);
+    let signature = rsa_sign_sha256_pem(platform_private_key_pem, &message)
+        .map_err(|e| PayError::Crypto(format!("sign notify: {}", e)))?;
+    Ok(NotifyRequest::new("POST", body)
+        .header("Content-Type", "application/json")
+        .header("Wechatpay-Timestamp", timestamp)
+        .header("Wechatpay-Nonce", nonce_str)
+        .header("Wechatpay-Signature", signature)
+        .header("Wechatpay-Serial", serial)
+        .header("Wechatpay-Signature-Type", "WECHATPAY2-SHA256-RSA2048"))
+}
+
+/// 预置平台证书缓存，避免验签时联网刷新
+pub fn platform_certs_with(
+    cfg: Arc<WechatConfig>,
+    serial: &str,
+    public_key_pem: &str,
+) -> Arc<PlatformCerts> {
+    let certs = PlatformCerts::new(cfg);
+    certs.insert(serial, public_key_pem);
+    Arc::new(certs)
+}
+
+/// 构造支付宝异步通知（RSA2 签名），返回表单编码的请求
+pub fn alipay_notify_request(
+    alipay_private_key_pem: &str,
+    params: &BTreeMap<String, String>,
+) -> Result<NotifyRequest, PayError> {
+    let mut params = params.clone();
+    params.remove("sign");
+    params.remove("sign_type");
+    let content = params
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join("&");
+    let sign = rsa_sign_sha256_pem(alipay_private_key_pem, &content)
+        .map_err(|e| PayError::Crypto(format!("sign notify: {}", e)))?;
+    params.insert("sign_type".into(), "RSA2".into());
+    params.insert("sign".into(), sign);
+    let body = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(params.iter())
+        .finish();
+    Ok(NotifyRequest::new("POST", body)
+        .header("Content-Type", "application/x-www-form-urlencoded"))
+}
@@ -0,0 +1,334 @@
+use crate::channel::{parse_tag, Channel};
+use crate::client::Pay;
+use crate::errors::{PayError, RetryClass};
+use crate::model::RawPayload;
+use crate::money::fen_to_yuan;
+use crate::poll::PollBackoff;
+use crate::unionpay::RefundOutcome;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Instant;
+
+/// 统一退款状态
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RefundStatus {
+    /// 退款成功（资金已退回）
+    Success,
+    /// 已受理，处理中
+    Processing,
+    /// 退款关闭
+    Closed,
+    /// 退款异常，需人工处理
+    Abnormal,
+}
+
+/// 统一关单 / 撤销结果
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloseOutcome {
+    /// 未付款订单已关闭
+    Closed,
+    /// 已付款订单经撤销原路退款
+    Refunded,
+    /// 撤销已受理，最终结果以通知或查询为准
+    Accepted,
+}
+
+/// 统一退款结果
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UnifiedRefund {
+    pub channel: Channel,
+    pub out_trade_no: String,
+    pub out_refund_no: String,
+    /// 渠道退款单号（支付宝无独立退款单号时为交易号）
+    pub refund_id: String,
+    /// 退款金额，单位分
+    pub amount: i64,
+    pub status: RefundStatus,
+    /// 渠道原始应答
+    pub raw: Value,
+}
+
+impl RawPayload for UnifiedRefund {
+    fn raw(&self) -> &Value {
+        &self.raw
+    }
+}
+
+impl Pay {
+    /// 按渠道标签统一发起退款，amount 单位为分
+    ///
+    /// out_refund_no 由调用方生成并持久化：渠道按退款单号去重，重试同一笔退款须传相同单号。
+    /// 配置了 OrderStore 时，累计退款超过订单总额的请求会在发往渠道前被拒绝；额度按退款单号
+    /// 占用，以相同单号重试不会重复占用。
+    pub async fn refund_unified(
+        channel_tag: &str,
+        out_trade_no: &str,
+        out_refund_no: &str,
+        amount: i64,
+    ) -> Result<UnifiedRefund, PayError> {
+        Self::refund_unified_with_override(channel_tag, out_trade_no, out_refund_no, amount, false)
+            .await
+    }
+
+    /// 同 refund_unified，allow_over_refund 为 true 时跳过超额校验（特殊情况人工处理）
+    pub async fn refund_unified_with_override(
+        channel_tag: &str,
+        out_trade_no: &str,
+        out_refund_no: &str,
+        amount: i64,
+        allow_over_refund: bool,
+    ) -> Result<UnifiedRefund, PayError> {
+        if out_refund_no.is_empty() {
+            return Err(PayError::Other("out_refund_no is required".into()));
+        }
+        if amount <= 0 {
+            return Err(PayError::Other("refund amount must be positive".into()));
+        }
+        let (channel, _) = parse_tag(channel_tag)?;
+        // 微信退款需带原订单金额，在占用额度前查询，查询失败不影响累计退款额
+        let wechat_total = match channel {
+            Channel::Wechat => Some(Self::wechat_order_total(out_trade_no).await?),
+            Channel::Alipay => None,
+            Channel::Unionpay => {
+                return Err(PayError::Other(
+                    "unionpay refund requires origQryId and is not routed by out_trade_no".into(),
+                ))
+            }
+        };
+        let store = Pay::order_store();
+        if let Some(store) = &store {
+            // 未在存储中登记订单总额时，以微信查询到的订单金额为上限
+            let bound = if allow_over_refund {
+                None
+            } else {
+                store.order_total(out_trade_no).or(wechat_total)
+            };
+            if !store.try_reserve(out_trade_no, out_refund_no, amount, bound) {
+                return Err(PayError::Other(format!(
+                    "refund exceeds order total: total={}, refunded={}, requested={}",
+                    bound.unwrap_or_default(),
+                    store.refunded(out_trade_no),
+                    amount
+                )));
+            }
+        }
+        let result = Self::send_refund(channel, out_trade_no, out_refund_no, amount, wechat_total).await;
+        if let Some(store) = &store {
+            // 仅渠道明确拒绝（如微信 4xx）或退款关闭时回滚；超时、5xx 等结果未知，保留占用待查询确认
+            let rejected = match &result {
+                Ok(r) => r.status == RefundStatus::Closed,
+                Err(e) => {
+                    matches!(
+                        e,
+                        PayError::Wechat { .. }
+                            | PayError::Alipay { .. }
+                            | PayError::AlipayBusiness { .. }
+                    )
+                        && e.retry_class() == RetryClass::Terminal
+                }
+            };
+            if rejected {
+                store.release(out_trade_no, out_refund_no);
+            }
+        }
+        result
+    }
+
+    async fn wechat_order_total(out_trade_no: &str) -> Result<i64, PayError> {
+        let order = Pay::wechat(None).query_by_out_trade_no(out_trade_no).await?;
+        order
+            .pointer("/amount/total")
+            .and_then(|v| v.as_i64())
+            .ok_or_else(|| PayError::Other("wechat order total missing".into()))
+    }
+
+    async fn send_refund(
+        channel: Channel,
+        out_trade_no: &str,
+        out_refund_no: &str,
+        amount: i64,
+        wechat_total: Option<i64>,
+    ) -> Result<UnifiedRefund, PayError> {
+        match channel {
+            Channel::Wechat => {
+                let total = wechat_total.unwrap_or(amount);
+                let raw = Pay::wechat(None)
+                    .refund(json!({
+                        "out_trade_no": out_trade_no,
+                        "out_refund_no": out_refund_no,
+                        "amount": { "refund": amount, "total": total, "currency": "CNY" }
+                    }))
+                    .await?;
+                let status = match raw.get("status").and_then(|v| v.as_str()) {
+                    Some("SUCCESS") => RefundStatus::Success,
+                    Some("CLOSED") => RefundStatus::Closed,
+                    Some("ABNORMAL") => RefundStatus::Abnormal,
+                    _ => RefundStatus::Processing,
+                };
+                Ok(UnifiedRefund {
+                    channel,
+                    out_trade_no: out_trade_no.to_string(),
+                    out_refund_no: out_refund_no.to_string(),
+                    refund_id: str_field(&raw, "refund_id"),
+                    amount,
+                    status,
+                    raw,
+                })
+            }
+            Channel::Alipay => {
+                let raw = Pay::alipay(None)
+                    .execute(
+                        "alipay.trade.refund",
+                        json!({
+                            "out_trade_no": out_trade_no,
+                            "refund_amount": fen_to_yuan(amount),
+                            "out_request_no": out_refund_no,
+                        }),
+                    )
+                    .await?;
+                // fund_change=Y 表示本次退款资金已变动
+                let status = match raw.get("fund_change").and_then(|v| v.as_str()) {
+                    Some("Y") => RefundStatus::Success,
+                    _ => RefundStatus::Processing,
+                };
+                Ok(UnifiedRefund {
+                    channel,
+                    out_trade_no: out_trade_no.to_string(),
+                    out_refund_no: out_refund_no.to_string(),
+                    refund_id: str_field(&raw, "trade_no"),
+                    amount,
+                    status,
+                    raw,
+                })
+            }
+            Channel::Unionpay => Err(PayError::Other(
+                "unionpay refund is not routed by out_trade_no".into(),
+            )),
+        }
+    }
+}
+
+impl Pay {
+    /// 按渠道标签统一关单
+    ///
+    /// 微信调用关单；支付宝默认 alipay.trade.close，标签产品为 pos / bar_code / face 时
+    /// 使用 alipay.trade.cancel；银联执行消费撤销，此时 out_trade_no 传原交易 queryId。
+    pub async fn close_unified(
+        channel_tag: &str,
+        out_trade_no: &str,
+    ) -> Result<CloseOutcome, PayError> {
+        let (channel, product) = parse_tag(channel_tag)?;
+        match channel {
+            Channel::Wechat => {
+                Pay::wechat(None)
+                    .close(json!({ "out_trade_no": out_trade_no }))
+                    .await?;
+                Ok(CloseOutcome::Closed)
+            }
+            Channel::Alipay => {
+                let client = Pay::alipay(None);
+                let biz = json!({ "out_trade_no": out_trade_no });
+                let is_pos = matches!(product.as_deref(), Some("pos" | "bar_code" | "face"));
+                if !is_pos {
+                    client.close(biz).await?;
+                    return Ok(CloseOutcome::Closed);
+                }
+                let resp = client.cancel(biz).await?;
+                match resp.get("action").and_then(|v| v.as_str()) {
+                    Some("refund") => Ok(CloseOutcome::Refunded),
+                    Some("close") => Ok(CloseOutcome::Closed),
+                    // retry_flag=Y 说明撤销未完成
+                    _ if resp.get("retry_flag").and_then(|v| v.as_str()) == Some("Y") => {
+                        Err(PayError::Other("alipay cancel needs retry".into()))
+                    }
+                    _ => Ok(CloseOutcome::Closed),
+                }
+            }
+            Channel::Unionpay => {
+                let client = Pay::unionpay();
+                let orig = client.query_trans(Some(out_trade_no), None, None).await?;
+                let txn_amt = orig.get("txnAmt").cloned().unwrap_or_default();
+                let order_id = format!("C{}", chrono::Local::now().format("%Y%m%d%H%M%S%3f"));
+                client.cancel(out_trade_no, &order_id, &txn_amt).await?;
+                Ok(CloseOutcome::Accepted)
+            }
+        }
+    }
+}
+
+impl Pay {
+    /// 轮询退款状态直到终态或超过截止时间，超时返回 Processing
+    ///
+    /// 银联以退款交易的 queryId 作为 out_refund_no 传入，out_trade_no 不使用。
+    pub async fn await_refund(
+        channel_tag: &str,
+        out_trade_no: &str,
+        out_refund_no: &str,
+        deadline: Instant,
+    ) -> Result<RefundStatus, PayError> {
+        let (channel, _) = parse_tag(channel_tag)?;
+        let mut backoff = PollBackoff::new(deadline);
+        loop {
+            match Self::query_refund_status(channel, out_trade_no, out_refund_no).await {
+                Ok(RefundStatus::Processing) => {}
+                Ok(status) => return Ok(status),
+                Err(e) => tracing::warn!("await_refund {} failed: {}", out_refund_no, e),
+            }
+            if !backoff.wait().await {
+                return Ok(RefundStatus::Processing);
+            }
+        }
+    }
+
+    async fn query_refund_status(
+        channel: Channel,
+        out_trade_no: &str,
+        out_refund_no: &str,
+    ) -> Result<RefundStatus, PayError> {
+        match channel {
+            Channel::Wechat => {
+                let resp = Pay::wechat(None)
+                    .query_refund(json!({ "out_refund_no": out_refund_no }))
+                    .await?;
+                Ok(match resp.get("status").and_then(|v| v.as_str()) {
+                    Some("SUCCESS") => RefundStatus::Success,
+                    Some("CLOSED") => RefundStatus::Closed,
+                    Some("ABNORMAL") => RefundStatus::Abnormal,
+                    _ => RefundStatus::Processing,
+                })
+            }
+            Channel::Alipay => {
+                let resp = Pay::alipay(None)
+                    .refund_query(json!({
+                        "out_trade_no": out_trade_no,
+                        "out_request_no": out_refund_no,
+                    }))
+                    .await?;
+                // refund_status 为空表示退款未成功或仍在处理
+                Ok(match resp.get("refund_status").and_then(|v| v.as_str()) {
+                    Some("REFUND_SUCCESS") => RefundStatus::Success,
+                    _ => RefundStatus::Processing,
+                })
+            }
+            Channel::Unionpay => {
+                let outcome = Pay::unionpay()
+                    .query_refund(Some(out_refund_no), None, None)
+                    .await?;
+                Ok(match outcome {
+                    RefundOutcome::Accepted => RefundStatus::Processing,
+                    RefundOutcome::Success { .. } => RefundStatus::Success,
+                    RefundOutcome::Failed { .. } => RefundStatus::Abnormal,
+                })
+            }
+        }
+    }
+}
+
+fn str_field(v: &Value, key: &str) -> String {
+    v.get(key)
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string()
+}
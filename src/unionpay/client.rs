@@ -1,12 +1,38 @@
-use crate::config::UnionpayConfig;
+use crate::config::{Mode, UnionpayConfig};
+use crate::errors::PayError;
+use crate::unionpay::notify::{sign_content, UnionNotify, UnionpayNotifyData};
+use base64::{engine::general_purpose, Engine as _};
+use openssl::hash::{hash, MessageDigest};
+use openssl::pkcs12::Pkcs12;
+use openssl::sign::Signer;
+use reqwest::Client;
 use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 pub struct UnionClient {
-    cfg: Arc<UnionpayConfig>,
+    pub(crate) cfg: Arc<UnionpayConfig>,
+    pub(crate) http: Client,
+    pub(crate) gateway: String,
 }
 impl UnionClient {
     pub fn new(cfg: Arc<UnionpayConfig>) -> Self {
-        Self { cfg }
+        Self::with_mode(cfg, Mode::Normal)
+    }
+    pub fn with_mode(cfg: Arc<UnionpayConfig>, mode: Mode) -> Self {
+        let gateway = match mode {
+            Mode::Sandbox => "https://gateway.test.95516.com".to_string(),
+            _ => "https://gateway.95516.com".to_string(),
+        };
+        Self {
+            cfg,
+            http: Client::new(),
+            gateway,
+        }
+    }
+    /// 复用已有的 HTTP 连接池
+    pub(crate) fn with_http(mut self, http: Client) -> Self {
+        self.http = http;
+        self
     }
     pub async fn web(&self, _order: Value) -> anyhow::Result<Value> {
         let _cfg = self.cfg.clone();
@@ -24,4 +50,149 @@ impl UnionClient {
     pub async fn b2b(&self, _order: Value) -> anyhow::Result<Value> {
         Ok(serde_json::json!({"message":"unionpay b2b stub"}))
     }
+    /// 验签并解析银联通知（自动识别网关 / 二维码格式）
+    pub fn verify_notify(
+        &self,
+        params: &HashMap<String, String>,
+    ) -> Result<UnionpayNotifyData, PayError> {
+        UnionNotify::new(self.cfg.clone()).verify_notify(params)
+    }
+
+    /// 读取 pfx 签名证书，返回 (私钥, certId)
+    fn load_sign_cert(&self) -> Result<(openssl::pkey::PKey<openssl::pkey::Private>, String), PayError> {
+        let path = self
+            .cfg
+            .sign_cert_path
+            .as_ref()
+            .ok_or_else(|| PayError::Other("unionpay sign cert missing".into()))?;
+        let der = std::fs::read(path)?;
+        let pwd = self.cfg.sign_cert_pwd.clone().unwrap_or_default();
+        let parsed = Pkcs12::from_der(&der)
+            .and_then(|p| p.parse2(&pwd))
+            .map_err(|e| PayError::Crypto(format!("parse pfx: {}", e)))?;
+        let pkey = parsed
+            .pkey
+            .ok_or_else(|| PayError::Crypto("pfx has no private key".into()))?;
+        let cert = parsed
+            .cert
+            .ok_or_else(|| PayError::Crypto("pfx has no certificate".into()))?;
+        let cert_id = cert
+            .serial_number()
+            .to_bn()
+            .and_then(|bn| bn.to_dec_str().map(|s| s.to_string()))
+            .map_err(|e| PayError::Crypto(format!("cert serial: {}", e)))?;
+        Ok((pkey, cert_id))
+    }
+
+    /// 5.1.0 签名：SHA-256 小写十六进制摘要后再做 SHA256withRSA
+    pub(crate) fn sign(&self, params: &mut BTreeMap<String, String>) -> Result<(), PayError> {
+        let (pkey, cert_id) = self.load_sign_cert()?;
+        params.insert("certId".into(), cert_id);
+        params.remove("signature");
+        let map: HashMap<String, String> = params.clone().into_iter().collect();
+        let signed = (|| -> Result<String, openssl::error::ErrorStack> {
+            let digest = hash(MessageDigest::sha256(), sign_content(&map).as_bytes())?;
+            let digest_hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+            let mut signer = Signer::new(MessageDigest::sha256(), &pkey)?;
+            signer.update(digest_hex.as_bytes())?;
+            Ok(general_purpose::STANDARD.encode(signer.sign_to_vec()?))
+        })()
+        .map_err(|e| PayError::Crypto(format!("unionpay sign: {}", e)))?;
+        params.insert("signature".into(), signed);
+        Ok(())
+    }
+
+    /// 补全公共参数，调用后台接口并验签应答；path 为 backTransReq.do / queryTrans.do 等
+    pub(crate) async fn back_trans(
+        &self,
+        path: &str,
+        mut params: BTreeMap<String, String>,
+    ) -> Result<HashMap<String, String>, PayError> {
+        let defaults = [
+            ("version", "5.1.0"),
+            ("encoding", "UTF-8"),
+            ("signMethod", "01"),
+            ("accessType", "0"),
+        ];
+        for (k, v) in defaults {
+            params.entry(k.to_string()).or_insert_with(|| v.to_string());
+        }
+        params
+            .entry("merId".to_string())
+            .or_insert_with(|| self.cfg.mer_id.clone());
+        params
+            .entry("txnTime".to_string())
+            .or_insert_with(|| chrono::Local::now().format("%Y%m%d%H%M%S").to_string());
+        self.sign(&mut params)?;
+
+        let url = format!("{}{}", self.gateway, path);
+        let text = self
+            .http
+            .post(&url)
+            .header("Content-Type", "application/x-www-form-urlencoded;charset=UTF-8")
+            .form(&params)
+            .send()
+            .await?
+            .text()
+            .await?;
+        let resp = parse_response(&text);
+        if resp.is_empty() {
+            return Err(PayError::Other(format!("unionpay empty response: {}", text)));
+        }
+        if resp.contains_key("signature") {
+            UnionNotify::new(self.cfg.clone()).verify_notify(&resp)?;
+        }
+        let code = resp.get("respCode").cloned().unwrap_or_default();
+        if code != "00" {
+            return Err(PayError::Unionpay {
+                code,
+                msg: resp.get("respMsg").cloned().unwrap_or_default(),
+            });
+        }
+        Ok(resp)
+    }
+}
+
+/// 将 Value 对象转换为银联报文参数（非字符串值按 JSON 序列化）
+pub(crate) fn value_to_params(order: &Value) -> BTreeMap<String, String> {
+    order
+        .as_object()
+        .map(|obj| {
+            obj.iter()
+                .map(|(k, v)| {
+                    let v = match v {
+                        Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    (k.clone(), v)
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 解析 key=value&key=value 应答，{} 包裹的值内部可能含有 & 与 =
+pub(crate) fn parse_response(text: &str) -> HashMap<String, String> {
+    let mut out = HashMap::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    let bytes = text.as_bytes();
+    for i in 0..=bytes.len() {
+        let at_end = i == bytes.len();
+        if !at_end {
+            match bytes[i] {
+                b'{' => depth += 1,
+                b'}' => depth -= 1,
+                _ => {}
+            }
+        }
+        if at_end || (bytes[i] == b'&' && depth == 0) {
+            let pair = &text[start..i];
+            if let Some((k, v)) = pair.split_once('=') {
+                out.insert(k.to_string(), v.to_string());
+            }
+            start = i + 1;
+        }
+    }
+    out
 }
@@ -0,0 +1,49 @@
+use crate::errors::PayError;
+use crate::unionpay::client::{value_to_params, UnionClient};
+use serde_json::Value;
+use std::collections::HashMap;
+
+// 代收产品业务类型
+const BIZ_TYPE_DEDUCT: &str = "000501";
+const BACK_TRANS_PATH: &str = "/gateway/api/backTransReq.do";
+
+impl UnionClient {
+    /// 代收签约（实名认证并建立委托关系，txnType=72 / txnSubType=11）
+    ///
+    /// order 需包含 orderId、accNo、customerInfo 等字段。
+    pub async fn deduct_sign(&self, order: Value) -> Result<HashMap<String, String>, PayError> {
+        self.deduct_request(order, "72", "11").await
+    }
+
+    /// 代收扣款（txnType=11 / txnSubType=02），需已签约；结果以异步通知或查询为准
+    pub async fn deduct_pay(&self, order: Value) -> Result<HashMap<String, String>, PayError> {
+        self.deduct_request(order, "11", "02").await
+    }
+
+    /// 解约（解除委托关系，txnType=74 / txnSubType=04）
+    pub async fn deduct_unsign(&self, order: Value) -> Result<HashMap<String, String>, PayError> {
+        self.deduct_request(order, "74", "04").await
+    }
+
+    async fn deduct_request(
+        &self,
+        order: Value,
+        txn_type: &str,
+        txn_sub_type: &str,
+    ) -> Result<HashMap<String, String>, PayError> {
+        let mut params = value_to_params(&order);
+        params.insert("txnType".into(), txn_type.into());
+        params.insert("txnSubType".into(), txn_sub_type.into());
+        params.insert("bizType".into(), BIZ_TYPE_DEDUCT.into());
+        params.entry("channelType".into()).or_insert_with(|| "07".into());
+        if txn_type == "11" {
+            params.entry("currencyCode".into()).or_insert_with(|| "156".into());
+        }
+        if let Some(back_url) = &self.cfg.back_url {
+            params
+                .entry("backUrl".into())
+                .or_insert_with(|| back_url.clone());
+        }
+        self.back_trans(BACK_TRANS_PATH, params).await
+    }
+}
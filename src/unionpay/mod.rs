@@ -1,2 +1,7 @@
 pub mod client;
+pub mod deduct;
+pub mod notify;
+pub mod query;
 pub use client::UnionClient;
+pub use notify::{UnionNotify, UnionpayNotifyData};
+pub use query::RefundOutcome;
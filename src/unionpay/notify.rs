@@ -0,0 +1,232 @@
+use crate::config::UnionpayConfig;
+use crate::errors::PayError;
+use crate::model::{params_to_value, RawPayload};
+use crate::channel::Channel;
+use crate::notify::{NotifyRequest, NotifyValidators};
+use crate::utils::rsa_verify_sha256_pem;
+use openssl::hash::{hash, MessageDigest};
+use openssl::nid::Nid;
+use openssl::stack::Stack;
+use openssl::x509::store::X509StoreBuilder;
+use openssl::x509::{X509StoreContext, X509};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Arc;
+
+/// 银联通知报文格式
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UnionpayNotifyFormat {
+    /// 全渠道网关（version=5.1.0，报文携带 signPubKeyCert）
+    Gateway,
+    /// 银联二维码（报文携带 reqType，按 certId 匹配验签证书）
+    Qrcode,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UnionpayNotifyData {
+    pub format: UnionpayNotifyFormat,
+    /// 商户订单号（网关 orderId / 二维码 orderNo）
+    pub order_id: String,
+    /// 银联流水号（网关 queryId / 二维码 voucherNum）
+    pub query_id: String,
+    /// 交易金额，单位分
+    pub txn_amt: String,
+    pub resp_code: String,
+    pub resp_msg: String,
+    pub others: HashMap<String, String>,
+    /// 通知原始参数（含 signature）
+    #[serde(default)]
+    pub raw: Value,
+}
+
+impl RawPayload for UnionpayNotifyData {
+    fn raw(&self) -> &Value {
+        &self.raw
+    }
+}
+
+pub struct UnionNotify {
+    cfg: Arc<UnionpayConfig>,
+    validators: NotifyValidators,
+}
+
+impl UnionNotify {
+    pub fn new(cfg: Arc<UnionpayConfig>) -> Self {
+        Self {
+            cfg,
+            validators: NotifyValidators::new(),
+        }
+    }
+
+    /// 设置验签后执行的业务校验器
+    pub fn with_validators(mut self, validators: NotifyValidators) -> Self {
+        self.validators = validators;
+        self
+    }
+
+    /// 识别报文格式
+    pub fn detect_format(params: &HashMap<String, String>) -> UnionpayNotifyFormat {
+        if params.contains_key("reqType") && !params.contains_key("signPubKeyCert") {
+            UnionpayNotifyFormat::Qrcode
+        } else {
+            UnionpayNotifyFormat::Gateway
+        }
+    }
+
+    /// 验签并解析银联通知（网关与二维码两种格式）
+    pub fn verify_notify(
+        &self,
+        params: &HashMap<String, String>,
+    ) -> Result<UnionpayNotifyData, PayError> {
+        let signature = params
+            .get("signature")
+            .ok_or_else(|| PayError::Other("missing signature".to_string()))?;
+        let format = Self::detect_format(params);
+        let public_key = match format {
+            UnionpayNotifyFormat::Gateway => self.gateway_cert(params)?,
+            UnionpayNotifyFormat::Qrcode => self.qrcode_cert(params)?,
+        };
+
+        // 待签名串：除 signature 外按 key 排序拼接，取 SHA-256 小写十六进制后再做 RSA 验签
+        let content = sign_content(params);
+        let digest = hash(MessageDigest::sha256(), content.as_bytes())
+            .map_err(|e| PayError::Crypto(format!("unionpay sha256: {}", e)))?;
+        let digest_hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+        let verified = rsa_verify_sha256_pem(&public_key, &digest_hex, signature)
+            .map_err(|e| PayError::Crypto(format!("rsa verify error: {}", e)))?;
+        if !verified {
+            return Err(PayError::Other("unionpay notify signature invalid".into()));
+        }
+
+        let get = |keys: &[&str]| {
+            keys.iter()
+                .find_map(|k| params.get(*k).cloned())
+                .unwrap_or_default()
+        };
+        let others = params
+            .iter()
+            .filter(|(k, _)| k.as_str() != "signature" && k.as_str() != "signPubKeyCert")
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        let data = UnionpayNotifyData {
+            format,
+            order_id: get(&["orderId", "orderNo"]),
+            query_id: get(&["queryId", "voucherNum"]),
+            txn_amt: get(&["txnAmt"]),
+            resp_code: get(&["respCode", "origRespCode"]),
+            resp_msg: get(&["respMsg", "origRespMsg"]),
+            others,
+            raw: params_to_value(params),
+        };
+        self.validators.validate(Channel::Unionpay, &data.raw)?;
+        Ok(data)
+    }
+
+    /// 从 NotifyRequest 解析表单参数并验签
+    pub fn verify_request(&self, req: &NotifyRequest) -> Result<UnionpayNotifyData, PayError> {
+        self.verify_notify(&req.form_params())
+    }
+
+    /// 网关报文：校验 signPubKeyCert 证书链后取其公钥
+    fn gateway_cert(&self, params: &HashMap<String, String>) -> Result<String, PayError> {
+        let cert_pem = params
+            .get("signPubKeyCert")
+            .ok_or_else(|| PayError::Other("missing signPubKeyCert".to_string()))?;
+        let cert = X509::from_pem(cert_pem.as_bytes())
+            .map_err(|e| PayError::Crypto(format!("parse signPubKeyCert: {}", e)))?;
+        let (root_path, middle_path) = match (&self.cfg.root_cert_path, &self.cfg.middle_cert_path) {
+            (Some(r), Some(m)) => (r, m),
+            _ => return Err(PayError::Other("unionpay root/middle cert missing".into())),
+        };
+        let load = |path: &str| -> Result<X509, PayError> {
+            let pem = fs::read(path)?;
+            X509::from_pem(&pem).map_err(|e| PayError::Crypto(format!("parse {}: {}", path, e)))
+        };
+        let root = load(root_path)?;
+        let middle = load(middle_path)?;
+        let verified = (|| -> Result<bool, openssl::error::ErrorStack> {
+            let mut builder = X509StoreBuilder::new()?;
+            builder.add_cert(root)?;
+            let store = builder.build();
+            let mut chain = Stack::new()?;
+            chain.push(middle)?;
+            let mut ctx = X509StoreContext::new()?;
+            ctx.init(&store, &cert, &chain, |c| c.verify_cert())
+        })()
+        .map_err(|e| PayError::Crypto(format!("verify cert chain: {}", e)))?;
+        if !verified {
+            return Err(PayError::Other("unionpay signPubKeyCert chain invalid".into()));
+        }
+        // 证书链只说明由 CFCA 签发，还需核对证书主体确为银联签名证书
+        check_unionpay_subject(&cert)?;
+        cert_pem_public(cert_pem)
+    }
+
+    /// 二维码报文：使用配置的验签证书，并核对 certId
+    fn qrcode_cert(&self, params: &HashMap<String, String>) -> Result<String, PayError> {
+        let path = self
+            .cfg
+            .qr_verify_cert_path
+            .as_ref()
+            .ok_or_else(|| PayError::Other("unionpay qr verify cert missing".into()))?;
+        let cert_pem = fs::read_to_string(path)?;
+        if let Some(cert_id) = params.get("certId") {
+            let cert = X509::from_pem(cert_pem.as_bytes())
+                .map_err(|e| PayError::Crypto(format!("parse {}: {}", path, e)))?;
+            let serial = cert
+                .serial_number()
+                .to_bn()
+                .and_then(|bn| bn.to_dec_str().map(|s| s.to_string()))
+                .map_err(|e| PayError::Crypto(format!("cert serial: {}", e)))?;
+            if &serial != cert_id {
+                return Err(PayError::Other(format!(
+                    "unionpay certId {} does not match verify cert {}",
+                    cert_id, serial
+                )));
+            }
+        }
+        cert_pem_public(&cert_pem)
+    }
+}
+
+/// 银联签名证书主体，与官方 SDK 校验一致
+const UNIONPAY_CN: &str = "中国银联股份有限公司";
+const UNIONPAY_SIGN_OU: &str = "00040000:SIGN";
+
+fn check_unionpay_subject(cert: &X509) -> Result<(), PayError> {
+    let subject = cert.subject_name();
+    let entries = |nid: Nid| -> Vec<String> {
+        subject
+            .entries_by_nid(nid)
+            .filter_map(|e| e.data().as_utf8().ok().map(|s| s.to_string()))
+            .collect()
+    };
+    let cn = entries(Nid::COMMONNAME);
+    let ou = entries(Nid::ORGANIZATIONALUNITNAME);
+    if !cn.iter().any(|v| v == UNIONPAY_CN) || !ou.iter().any(|v| v == UNIONPAY_SIGN_OU) {
+        return Err(PayError::Other(format!(
+            "unionpay signPubKeyCert subject mismatch: CN={:?} OU={:?}",
+            cn, ou
+        )));
+    }
+    Ok(())
+}
+
+fn cert_pem_public(cert_pem: &str) -> Result<String, PayError> {
+    crate::utils::extract_pubkey_from_cert(cert_pem)
+        .map_err(|e| PayError::Crypto(format!("extract public key: {}", e)))
+}
+
+pub(crate) fn sign_content(params: &HashMap<String, String>) -> String {
+    let mut kv: Vec<(&String, &String)> = params
+        .iter()
+        .filter(|&(k, _)| k != "signature")
+        .collect();
+    kv.sort_by(|a, b| a.0.cmp(b.0));
+    kv.iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<String>>()
+        .join("&")
+}
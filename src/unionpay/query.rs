@@ -0,0 +1,104 @@
+use crate::errors::PayError;
+use crate::unionpay::client::UnionClient;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+const QUERY_TRANS_PATH: &str = "/gateway/api/queryTrans.do";
+const BACK_TRANS_PATH: &str = "/gateway/api/backTransReq.do";
+
+/// 银联退款结果
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RefundOutcome {
+    /// 已受理，处理中（origRespCode 03/04/05）
+    Accepted,
+    /// 退款成功
+    Success { query_id: String, settle_amt: String },
+    /// 退款失败
+    Failed { code: String, msg: String },
+}
+
+impl UnionClient {
+    /// 交易状态查询（queryTrans），按 queryId 或 orderId+txnTime 查询
+    pub async fn query_trans(
+        &self,
+        query_id: Option<&str>,
+        order_id: Option<&str>,
+        txn_time: Option<&str>,
+    ) -> Result<HashMap<String, String>, PayError> {
+        let mut params = BTreeMap::new();
+        params.insert("txnType".to_string(), "00".to_string());
+        params.insert("txnSubType".to_string(), "00".to_string());
+        params.insert("bizType".to_string(), "000000".to_string());
+        match (query_id, order_id, txn_time) {
+            (Some(q), _, _) => {
+                params.insert("queryId".to_string(), q.to_string());
+            }
+            (None, Some(o), Some(t)) => {
+                params.insert("orderId".to_string(), o.to_string());
+                params.insert("txnTime".to_string(), t.to_string());
+            }
+            _ => {
+                return Err(PayError::Other(
+                    "queryId or orderId with txnTime is required".into(),
+                ))
+            }
+        }
+        self.back_trans(QUERY_TRANS_PATH, params).await
+    }
+
+    /// 退款状态查询
+    ///
+    /// orig_qry_id 为退款交易的 queryId；或以退款 orderId + txnTime 查询。
+    pub async fn query_refund(
+        &self,
+        orig_qry_id: Option<&str>,
+        orig_order_id: Option<&str>,
+        txn_time: Option<&str>,
+    ) -> Result<RefundOutcome, PayError> {
+        let resp = self.query_trans(orig_qry_id, orig_order_id, txn_time).await?;
+        let get = |k: &str| resp.get(k).cloned().unwrap_or_default();
+        if let Some(txn_type) = resp.get("txnType") {
+            if txn_type != "04" {
+                return Err(PayError::Other(format!(
+                    "unionpay transaction is not a refund, txnType={}",
+                    txn_type
+                )));
+            }
+        }
+        let outcome = match get("origRespCode").as_str() {
+            "00" | "A6" => RefundOutcome::Success {
+                query_id: get("queryId"),
+                settle_amt: get("settleAmt"),
+            },
+            "03" | "04" | "05" => RefundOutcome::Accepted,
+            code => RefundOutcome::Failed {
+                code: code.to_string(),
+                msg: get("origRespMsg"),
+            },
+        };
+        Ok(outcome)
+    }
+}
+
+impl UnionClient {
+    /// 消费撤销（txnType=31），仅限当日交易；orig_qry_id 为原消费交易 queryId
+    pub async fn cancel(
+        &self,
+        orig_qry_id: &str,
+        order_id: &str,
+        txn_amt: &str,
+    ) -> Result<HashMap<String, String>, PayError> {
+        let mut params = BTreeMap::new();
+        params.insert("txnType".to_string(), "31".to_string());
+        params.insert("txnSubType".to_string(), "00".to_string());
+        params.insert("bizType".to_string(), "000201".to_string());
+        params.insert("channelType".to_string(), "07".to_string());
+        params.insert("origQryId".to_string(), orig_qry_id.to_string());
+        params.insert("orderId".to_string(), order_id.to_string());
+        params.insert("txnAmt".to_string(), txn_amt.to_string());
+        if let Some(back_url) = &self.cfg.back_url {
+            params.insert("backUrl".to_string(), back_url.clone());
+        }
+        self.back_trans(BACK_TRANS_PATH, params).await
+    }
+}
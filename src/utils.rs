@@ -1,32 +1,149 @@
-use std::thread::sleep;
 use aes_gcm::aead::{Aead, KeyInit};
 use aes_gcm::{Aes256Gcm, Nonce};
 use base64::{engine::general_purpose, DecodeError, Engine as _};
 use openssl::{hash::MessageDigest, pkey::PKey, sign::Signer,x509::X509};
+use once_cell::sync::{Lazy, OnceCell};
+use rand::distributions::Alphanumeric;
+use rand::rngs::OsRng;
 use rand::Rng;
-use std::time::Duration;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
 use std::fs;
 use std::path::Path;
-use openssl::encrypt::Encrypter;
+use openssl::encrypt::{Decrypter, Encrypter};
 use openssl::hash::hash;
 use openssl::nid::Nid;
 use openssl::rsa::Rsa;
+use openssl::bn::BigNum;
+use openssl::pkey::HasPublic;
+use openssl::pkey_ctx::PkeyCtx;
+use openssl::symm::{Cipher, Crypter};
+use ghash::universal_hash::UniversalHash;
+use ghash::GHash;
+
+/// 随机串生成器，可替换为自定义实现（如测试中的固定值、HSM 随机源）
+pub trait NonceProvider: Send + Sync {
+    fn nonce(&self, len: usize) -> String;
+}
+
+/// 默认实现：操作系统 CSPRNG，字符集 [A-Za-z0-9]
+pub struct OsNonceProvider;
+
+impl NonceProvider for OsNonceProvider {
+    fn nonce(&self, len: usize) -> String {
+        OsRng
+            .sample_iter(&Alphanumeric)
+            .take(len)
+            .map(char::from)
+            .collect()
+    }
+}
 
+static NONCE_PROVIDER: OnceCell<Arc<dyn NonceProvider>> = OnceCell::new();
+
+/// 设置全局随机串生成器，只能设置一次
+pub fn set_nonce_provider(provider: Arc<dyn NonceProvider>) -> bool {
+    NONCE_PROVIDER.set(provider).is_ok()
+}
+
+/// 生成随机串，用于签名 nonce_str 等
 pub fn gen_nonce(len: usize) -> String {
-    let mut rng = rand::thread_rng();
-    (0..len)
-        .map(|_| {
-            let n = rng.gen_range(0..36);
-            std::char::from_digit(n as u32, 36).unwrap()
-        })
-        .collect()
+    match NONCE_PROVIDER.get() {
+        Some(provider) => provider.nonce(len),
+        None => OsNonceProvider.nonce(len),
+    }
 }
 pub fn now_ts() -> String {
     ::time::OffsetDateTime::now_utc().unix_timestamp().to_string()
 }
+pub type PrivateKey = PKey<openssl::pkey::Private>;
+
+// 已解析私钥缓存：签名热路径上避免每次读取文件、包装 PEM 并重新解析。
+// 以配置中的原始字符串（PEM / 文件路径）与口令为键，RSA 与 SM2 分开缓存；
+// 文件路径同时记录修改时间，原地替换密钥文件后自动重新加载。
+type KeyCache = RwLock<HashMap<(String, Option<String>), (Option<SystemTime>, PrivateKey)>>;
+static RSA_KEY_CACHE: Lazy<KeyCache> = Lazy::new(|| RwLock::new(HashMap::new()));
+static SM2_KEY_CACHE: Lazy<KeyCache> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+fn cached_private_key(
+    cache: &KeyCache,
+    source: &str,
+    passphrase: Option<&str>,
+) -> anyhow::Result<PrivateKey> {
+    let mtime = if source.contains("-----BEGIN") {
+        None
+    } else {
+        fs::metadata(source).and_then(|m| m.modified()).ok()
+    };
+    let cache_key = (source.to_string(), passphrase.map(str::to_string));
+    if let Some((cached_mtime, key)) = cache.read().unwrap().get(&cache_key) {
+        if *cached_mtime == mtime {
+            return Ok(key.clone());
+        }
+    }
+    let key = parse_private_key(source, passphrase)?;
+    cache.write().unwrap().insert(cache_key, (mtime, key.clone()));
+    Ok(key)
+}
+
+/// 解析私钥，支持：
+/// - PEM：PKCS#1（RSA PRIVATE KEY）、PKCS#8（PRIVATE KEY）、SEC1（EC PRIVATE KEY）
+/// - 加密 PEM：PKCS#8（ENCRYPTED PRIVATE KEY）与传统 Proc-Type 加密，需提供口令
+/// - DER：二进制文件或 base64 字符串，PKCS#1 / PKCS#8 / 加密 PKCS#8
+///
+/// `source` 可以是文件路径或密钥内容本身。
+pub fn parse_private_key(source: &str, passphrase: Option<&str>) -> anyhow::Result<PrivateKey> {
+    let path = Path::new(source);
+    let data = if !source.contains("-----BEGIN") && path.is_file() {
+        fs::read(path)?
+    } else {
+        source.as_bytes().to_vec()
+    };
+    let text = std::str::from_utf8(&data).ok().map(str::trim);
+    match text {
+        Some(pem) if pem.contains("-----BEGIN") => {
+            let encrypted = pem.contains("ENCRYPTED");
+            match (encrypted, passphrase) {
+                (true, Some(pass)) => Ok(PKey::private_key_from_pem_passphrase(
+                    pem.as_bytes(),
+                    pass.as_bytes(),
+                )?),
+                (true, None) => anyhow::bail!("encrypted private key requires a passphrase"),
+                (false, _) => Ok(PKey::private_key_from_pem(pem.as_bytes())?),
+            }
+        }
+        // 裸 base64（如支付宝开放平台导出的私钥）
+        Some(b64) if !b64.is_empty() && b64.bytes().all(|b| b.is_ascii_graphic() || b.is_ascii_whitespace()) => {
+            let compact: String = b64.split_whitespace().collect();
+            let der = general_purpose::STANDARD.decode(compact)?;
+            parse_private_key_der(&der, passphrase)
+        }
+        _ => parse_private_key_der(&data, passphrase),
+    }
+}
+
+fn parse_private_key_der(der: &[u8], passphrase: Option<&str>) -> anyhow::Result<PrivateKey> {
+    if let Ok(key) = PKey::private_key_from_der(der) {
+        return Ok(key);
+    }
+    match passphrase {
+        Some(pass) => Ok(PKey::private_key_from_pkcs8_passphrase(der, pass.as_bytes())?),
+        None => anyhow::bail!("unrecognized private key (encrypted keys require a passphrase)"),
+    }
+}
+
 pub fn rsa_sign_sha256_pem(private_key_pem: &str, data: &str) -> anyhow::Result<String> {
-    let private_key_pem = load_private_key(private_key_pem);
-    let pkey = PKey::private_key_from_pem(private_key_pem.as_bytes())?;
+    rsa_sign_sha256_with_passphrase(private_key_pem, None, data)
+}
+
+/// 同 rsa_sign_sha256_pem，私钥为加密格式时传入口令
+pub fn rsa_sign_sha256_with_passphrase(
+    private_key: &str,
+    passphrase: Option<&str>,
+    data: &str,
+) -> anyhow::Result<String> {
+    let pkey = cached_private_key(&RSA_KEY_CACHE, private_key, passphrase)?;
     let mut signer = Signer::new(MessageDigest::sha256(), &pkey)?;
     signer.update(data.as_bytes())?;
     let sig = signer.sign_to_vec()?;
@@ -53,6 +170,74 @@ pub fn rsa_verify_sha256_pem(
     Ok(verifier.verify(&sig)?)
 }
 
+/// SM2 签名默认用户标识（GM/T 0009-2012）
+const SM2_DEFAULT_ID: &[u8] = b"1234567812345678";
+
+// sm2p256v1 曲线参数 a、b 与基点坐标
+const SM2_CURVE_A: &str = "FFFFFFFEFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF00000000FFFFFFFFFFFFFFFC";
+const SM2_CURVE_B: &str = "28E9FA9E9D9F5E344D5A9E4BCF6509A7F39789F515AB8F92DDBCBD414D940E93";
+const SM2_CURVE_GX: &str = "32C4AE2C1F1981195F9904466A39C9948FE30BBFF2660BE1715A4589334C74C7";
+const SM2_CURVE_GY: &str = "BC3736A2F4F6779C59BDCEE36B692153D0A9877CC62A474002DF32E52139F0A0";
+
+/// 计算 SM2 签名的消息摘要 e = SM3(Z || M)，Z 由用户标识、曲线参数与公钥生成
+fn sm2_message_digest<T: HasPublic>(pkey: &PKey<T>, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    // SubjectPublicKeyInfo 末尾 65 字节为未压缩公钥点 04 || x || y
+    let spki = pkey.public_key_to_der()?;
+    if spki.len() < 65 || spki[spki.len() - 65] != 0x04 {
+        anyhow::bail!("not an SM2 public key");
+    }
+    let point = &spki[spki.len() - 65..];
+    let bits = (SM2_DEFAULT_ID.len() * 8) as u16;
+    let mut z = Vec::with_capacity(2 + SM2_DEFAULT_ID.len() + 32 * 6);
+    z.extend_from_slice(&bits.to_be_bytes());
+    z.extend_from_slice(SM2_DEFAULT_ID);
+    for h in [SM2_CURVE_A, SM2_CURVE_B, SM2_CURVE_GX, SM2_CURVE_GY] {
+        z.extend(BigNum::from_hex_str(h)?.to_vec_padded(32)?);
+    }
+    z.extend_from_slice(&point[1..]);
+    let z_digest = hash(MessageDigest::sm3(), &z)?;
+    let mut m = Vec::with_capacity(32 + data.len());
+    m.extend_from_slice(&z_digest);
+    m.extend_from_slice(data);
+    Ok(hash(MessageDigest::sm3(), &m)?.to_vec())
+}
+
+/// SM2withSM3 签名（支付宝 sign_type=SM2），返回 base64 的 DER 签名
+pub fn sm2_sign_sm3(private_key: &str, data: &str) -> anyhow::Result<String> {
+    sm2_sign_sm3_with_passphrase(private_key, None, data)
+}
+
+/// 同 sm2_sign_sm3，私钥为加密格式时传入口令
+pub fn sm2_sign_sm3_with_passphrase(
+    private_key: &str,
+    passphrase: Option<&str>,
+    data: &str,
+) -> anyhow::Result<String> {
+    let pkey = cached_private_key(&SM2_KEY_CACHE, private_key, passphrase)?;
+    let digest = sm2_message_digest(&pkey, data.as_bytes())?;
+    let mut ctx = PkeyCtx::new(&pkey)?;
+    ctx.sign_init()?;
+    let mut sig = vec![];
+    ctx.sign_to_vec(&digest, &mut sig)?;
+    Ok(general_purpose::STANDARD.encode(sig))
+}
+
+/// SM2withSM3 验签，公钥可为 PEM 公钥、证书或 base64 DER
+pub fn sm2_verify_sm3(public_key: &str, data: &str, signature_base64: &str) -> anyhow::Result<bool> {
+    let pkey = if public_key.contains("BEGIN CERTIFICATE") {
+        X509::from_pem(public_key.as_bytes())?.public_key()?
+    } else if public_key.contains("-----BEGIN") {
+        PKey::public_key_from_pem(public_key.as_bytes())?
+    } else {
+        PKey::public_key_from_der(&general_purpose::STANDARD.decode(public_key.trim())?)?
+    };
+    let digest = sm2_message_digest(&pkey, data.as_bytes())?;
+    let sig = general_purpose::STANDARD.decode(signature_base64)?;
+    let mut ctx = PkeyCtx::new(&pkey)?;
+    ctx.verify_init()?;
+    Ok(ctx.verify(&digest, &sig)?)
+}
+
 pub fn aes_gcm_decrypt(
     api_v3_key: &str,
     associated_data: &str,
@@ -79,22 +264,96 @@ pub fn aes_gcm_decrypt(
     let plain = plain.map_err(|e| anyhow::anyhow!(e.to_string()))?;
     Ok(String::from_utf8(plain)?)
 }
-pub async fn retry_async<F, Fut, T, E>(mut attempts: usize, mut f: F) -> Result<T, E>
+fn sm4_encrypt_block(key: &[u8], block: &[u8; 16]) -> anyhow::Result<[u8; 16]> {
+    let mut c = Crypter::new(Cipher::sm4_ecb(), openssl::symm::Mode::Encrypt, key, None)?;
+    c.pad(false);
+    let mut out = [0u8; 32];
+    let n = c.update(block, &mut out)?;
+    if n != 16 {
+        anyhow::bail!("sm4 block encrypt failed");
+    }
+    let mut b = [0u8; 16];
+    b.copy_from_slice(&out[..16]);
+    Ok(b)
+}
+
+/// SM4-GCM 解密（96 位 nonce，密文末尾 16 字节为认证标签）
+/// OpenSSL 未必提供 SM4-GCM 实现，这里以 SM4-ECB/CTR 加 GHASH 组合实现
+pub fn sm4_gcm_decrypt(
+    key: &[u8],
+    associated_data: &str,
+    nonce: &str,
+    ciphertext_b64: &str,
+) -> anyhow::Result<String> {
+    if key.len() != 16 {
+        anyhow::bail!("sm4 key must be 16 bytes");
+    }
+    let nonce = nonce.as_bytes();
+    if nonce.len() != 12 {
+        anyhow::bail!("sm4-gcm nonce must be 12 bytes");
+    }
+    let data = general_purpose::STANDARD.decode(ciphertext_b64)?;
+    if data.len() < 16 {
+        anyhow::bail!("sm4-gcm ciphertext too short");
+    }
+    let (ciphertext, tag) = data.split_at(data.len() - 16);
+    let aad = associated_data.as_bytes();
+
+    let h = sm4_encrypt_block(key, &[0u8; 16])?;
+    let mut j0 = [0u8; 16];
+    j0[..12].copy_from_slice(nonce);
+    j0[15] = 1;
+    let mut ghash = GHash::new(&h.into());
+    ghash.update_padded(aad);
+    ghash.update_padded(ciphertext);
+    let mut lens = [0u8; 16];
+    lens[..8].copy_from_slice(&((aad.len() as u64) * 8).to_be_bytes());
+    lens[8..].copy_from_slice(&((ciphertext.len() as u64) * 8).to_be_bytes());
+    ghash.update(&[lens.into()]);
+    let s = ghash.finalize();
+    let ek_j0 = sm4_encrypt_block(key, &j0)?;
+    let expected: Vec<u8> = s.iter().zip(ek_j0.iter()).map(|(a, b)| a ^ b).collect();
+    if !openssl::memcmp::eq(&expected, tag) {
+        anyhow::bail!("sm4-gcm tag mismatch");
+    }
+    let mut counter = j0;
+    counter[15] = 2;
+    let plain = openssl::symm::decrypt(Cipher::sm4_ctr(), key, Some(&counter), ciphertext)?;
+    Ok(String::from_utf8(plain)?)
+}
+
+pub async fn retry_async<F, Fut, T, E>(attempts: usize, f: F) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T, E>>,
+    E: std::fmt::Debug,
+{
+    retry_async_if(attempts, |_| true, f).await
+}
+
+/// 仅在 `should_retry` 返回 true 时重试，退避间隔从 200ms 翻倍，上限 5s
+pub async fn retry_async_if<F, Fut, T, E, R>(
+    mut attempts: usize,
+    should_retry: R,
+    mut f: F,
+) -> Result<T, E>
 where
     F: FnMut() -> Fut,
     Fut: std::future::Future<Output = Result<T, E>>,
     E: std::fmt::Debug,
+    R: Fn(&E) -> bool,
 {
     let mut delay = 200u64;
     loop {
         match f().await {
             Ok(v) => return Ok(v),
             Err(e) => {
-                attempts -= 1;
-                if attempts == 0 {
+                attempts = attempts.saturating_sub(1);
+                if attempts == 0 || !should_retry(&e) {
                     return Err(e);
                 }
-                sleep(Duration::from_millis(delay));
+                tracing::warn!("retry after {}ms: {:?}", delay, e);
+                tokio::time::sleep(Duration::from_millis(delay)).await;
                 delay = std::cmp::min(delay * 2, 5000);
             }
         }
@@ -142,7 +401,7 @@ pub fn get_cert_sn_by_content(cert_content: &[u8]) -> anyhow::Result<String> {
         .map(|item| {
             item.object().nid().short_name().unwrap().to_string()
                 + "="
-                + &item.data().as_utf8().unwrap().to_string()
+                + item.data().as_utf8().unwrap().as_ref()
         })
         .collect::<Vec<String>>();
     sumary.reverse();
@@ -264,6 +523,40 @@ pub fn rsa_encrypt_oaep_with_public_key_pem(
     Ok(base64::engine::general_purpose::STANDARD.encode(&encrypted))
 }
 
+/// 使用商户私钥 RSA-OAEP（SHA1）解密 base64 密文
+pub fn rsa_decrypt_oaep_with_private_key(
+    private_key: &str,
+    passphrase: Option<&str>,
+    ciphertext_b64: &str,
+) -> anyhow::Result<Vec<u8>> {
+    let pkey = parse_private_key(private_key, passphrase)?;
+    let ciphertext = general_purpose::STANDARD.decode(ciphertext_b64)?;
+    let mut decrypter = Decrypter::new(&pkey)?;
+    decrypter.set_rsa_padding(openssl::rsa::Padding::PKCS1_OAEP)?;
+    decrypter.set_rsa_mgf1_md(MessageDigest::sha1())?;
+    decrypter.set_rsa_oaep_md(MessageDigest::sha1())?;
+    let mut plain = vec![0; decrypter.decrypt_len(&ciphertext)?];
+    let n = decrypter.decrypt(&ciphertext, &mut plain)?;
+    plain.truncate(n);
+    Ok(plain)
+}
+
+/// AES-256-GCM 解密二进制数据（密文末尾 16 字节为认证标签，无附加数据）
+pub fn aes_gcm_decrypt_bytes(key: &[u8], nonce: &[u8], ciphertext: &[u8]) -> anyhow::Result<Vec<u8>> {
+    if key.len() != 32 {
+        anyhow::bail!("aes-256-gcm key must be 32 bytes");
+    }
+    if nonce.len() != 12 {
+        anyhow::bail!("aes-256-gcm nonce must be 12 bytes, got {}", nonce.len());
+    }
+    let cipher = Aes256Gcm::new_from_slice(key).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    #[allow(deprecated)]
+    let nonce = Nonce::from_slice(nonce);
+    cipher
+        .decrypt(nonce, ciphertext)
+        .map_err(|e| anyhow::anyhow!(e.to_string()))
+}
+
 /// 从微信支付平台证书中提取序列号（16进制，大写）
 /// 微信支付要求使用16进制格式的证书序列号，且为大写
 pub fn extract_wechat_cert_serial_number(cert_pem: &str) -> anyhow::Result<String> {
@@ -0,0 +1,98 @@
+//! 独立验签函数：无需构造 WechatClient / AlipayClient，适用于网关、函数计算等边缘服务
+
+use crate::errors::PayError;
+use crate::utils::{rsa_verify_sha256_pem, sm2_verify_sm3};
+use std::collections::HashMap;
+
+/// 校验微信支付 V3 回调/应答签名
+///
+/// `headers` 的名称不区分大小写；`certs` 为 平台证书序列号 -> 平台公钥 PEM。
+/// 验签算法取自 Wechatpay-Signature-Type，缺省为 RSA。
+pub fn verify_wechat_signature(
+    headers: &HashMap<String, String>,
+    body: &str,
+    certs: &HashMap<String, String>,
+) -> Result<(), PayError> {
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    };
+    let required = |name: &str| {
+        header(name)
+            .filter(|v| !v.is_empty())
+            .ok_or_else(|| PayError::Other(format!("missing header {}", name)))
+    };
+    let ts = required("wechatpay-timestamp")?;
+    let nonce = required("wechatpay-nonce")?;
+    let signature = required("wechatpay-signature")?;
+    let serial = required("wechatpay-serial")?;
+    let pub_pem = certs
+        .get(serial)
+        .ok_or_else(|| PayError::Other(format!("platform cert {} not found", serial)))?;
+
+    let msg = format!("{}\n{}\n{}\n", ts, nonce, body);
+    let sm2 = header("wechatpay-signature-type")
+        .map(|t| t.eq_ignore_ascii_case("WECHATPAY2-SM2-WITH-SM3"))
+        .unwrap_or(false);
+    let ok = if sm2 {
+        sm2_verify_sm3(pub_pem, &msg, signature)
+    } else {
+        rsa_verify_sha256_pem(pub_pem, &msg, signature)
+    }
+    .map_err(|e| PayError::Crypto(format!("{}", e)))?;
+    if !ok {
+        return Err(PayError::Other("wechat notify invalid signature".to_string()));
+    }
+    Ok(())
+}
+
+/// 校验支付宝异步通知签名
+///
+/// `public_key` 为支付宝公钥（PEM 或裸 base64）；sign_type 缺省按 RSA2 处理。
+pub fn verify_alipay_signature(
+    params: &HashMap<String, String>,
+    public_key: &str,
+) -> Result<(), PayError> {
+    verify_alipay_params(params, public_key, "RSA2")
+}
+
+/// 按参数中的 sign_type（缺省 default_sign_type）校验支付宝签名
+pub(crate) fn verify_alipay_params(
+    params: &HashMap<String, String>,
+    public_key: &str,
+    default_sign_type: &str,
+) -> Result<(), PayError> {
+    let sign = params
+        .get("sign")
+        .ok_or_else(|| PayError::Other("missing sign".to_string()))?;
+    let sign_type = params
+        .get("sign_type")
+        .map(String::as_str)
+        .unwrap_or(default_sign_type);
+
+    // 除 sign、sign_type 外按 key 排序拼接
+    let mut kv: Vec<(&String, &String)> = params
+        .iter()
+        .filter(|&(k, _)| k != "sign" && k != "sign_type")
+        .collect();
+    kv.sort_by(|a, b| a.0.cmp(b.0));
+    let content = kv
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<String>>()
+        .join("&");
+
+    let verified = if sign_type.eq_ignore_ascii_case("SM2") {
+        sm2_verify_sm3(public_key, &content, sign)
+            .map_err(|e| PayError::Crypto(format!("sm2 verify error: {}", e)))?
+    } else {
+        rsa_verify_sha256_pem(public_key, &content, sign)
+            .map_err(|e| PayError::Crypto(format!("rsa verify error: {}", e)))?
+    };
+    if !verified {
+        return Err(PayError::Other("alipay notify signature invalid".into()));
+    }
+    Ok(())
+}
@@ -0,0 +1,324 @@
+use crate::errors::PayError;
+use crate::model::RawPayload;
+use crate::wechat::client::WechatClient;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+/// 进件中需要平台证书加密的字段（ubo_info_list 按下标另行展开）
+const SENSITIVE_FIELDS: &[&str] = &[
+    "/contact_info/contact_name",
+    "/contact_info/contact_id_number",
+    "/contact_info/mobile_phone",
+    "/contact_info/contact_email",
+    "/subject_info/identity_info/id_card_info/id_card_name",
+    "/subject_info/identity_info/id_card_info/id_card_number",
+    "/subject_info/identity_info/id_card_info/id_card_address",
+    "/subject_info/identity_info/id_doc_info/id_doc_name",
+    "/subject_info/identity_info/id_doc_info/id_doc_number",
+    "/subject_info/identity_info/id_doc_info/id_doc_address",
+    "/bank_account_info/account_name",
+    "/bank_account_info/account_number",
+];
+
+const UBO_SENSITIVE_FIELDS: &[&str] = &["ubo_id_doc_name", "ubo_id_doc_number", "ubo_id_doc_address"];
+
+/// 超级管理员信息；contact_name / contact_id_number / mobile_phone / contact_email 传明文，提交时自动加密
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ContactInfo {
+    /// LEGAL（经营者/法人）或 SUPER（经办人）
+    pub contact_type: String,
+    pub contact_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_id_doc_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_id_number: Option<String>,
+    /// 证件正面照片 media_id
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_id_doc_copy: Option<String>,
+    /// 证件反面照片 media_id
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_id_doc_copy_back: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_period_begin: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_period_end: Option<String>,
+    /// 业务办理授权函 media_id（经办人时必填）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub business_authorization_letter: Option<String>,
+    pub mobile_phone: String,
+    pub contact_email: String,
+}
+
+/// 营业执照
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BusinessLicenseInfo {
+    /// 营业执照照片 media_id
+    pub license_copy: String,
+    pub license_number: String,
+    pub merchant_name: String,
+    pub legal_person: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub period_begin: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub period_end: Option<String>,
+}
+
+/// 身份证信息；姓名、号码、地址传明文，提交时自动加密
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct IdCardInfo {
+    /// 人像面照片 media_id
+    pub id_card_copy: String,
+    /// 国徽面照片 media_id
+    pub id_card_national: String,
+    pub id_card_name: String,
+    pub id_card_number: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_card_address: Option<String>,
+    pub card_period_begin: String,
+    /// 长期有效填“长期”
+    pub card_period_end: String,
+}
+
+/// 经营者/法人身份证件
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct IdentityInfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_holder_type: Option<String>,
+    /// 如 IDENTIFICATION_TYPE_IDCARD
+    pub id_doc_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_card_info: Option<IdCardInfo>,
+    /// 其他类型证件，结构与官方文档一致
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_doc_info: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<bool>,
+}
+
+/// 主体资料
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SubjectInfo {
+    /// SUBJECT_TYPE_INDIVIDUAL / SUBJECT_TYPE_ENTERPRISE / SUBJECT_TYPE_MICRO 等
+    pub subject_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub business_license_info: Option<BusinessLicenseInfo>,
+    pub identity_info: IdentityInfo,
+    /// 其余字段（micro_biz_info、ubo_info_list 等）
+    #[serde(flatten)]
+    pub extras: Map<String, Value>,
+}
+
+/// 经营资料
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BusinessInfo {
+    pub merchant_shortname: String,
+    pub service_phone: String,
+    /// 经营场景，结构与官方文档一致（门店照片等为 media_id）
+    pub sales_info: Value,
+}
+
+/// 结算规则
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SettlementInfo {
+    pub settlement_id: String,
+    pub qualification_type: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub qualifications: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub activities_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub activities_rate: Option<String>,
+}
+
+/// 结算银行账户；account_name / account_number 传明文，提交时自动加密
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BankAccountInfo {
+    /// BANK_ACCOUNT_TYPE_CORPORATE / BANK_ACCOUNT_TYPE_PERSONAL
+    pub bank_account_type: String,
+    pub account_name: String,
+    pub account_bank: String,
+    pub bank_address_code: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bank_branch_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bank_name: Option<String>,
+    pub account_number: String,
+}
+
+/// 特约商户进件申请单
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Applyment {
+    pub business_code: String,
+    pub contact_info: ContactInfo,
+    pub subject_info: SubjectInfo,
+    pub business_info: BusinessInfo,
+    pub settlement_info: SettlementInfo,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bank_account_info: Option<BankAccountInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub addition_info: Option<Value>,
+}
+
+impl From<Applyment> for Value {
+    fn from(applyment: Applyment) -> Value {
+        serde_json::to_value(applyment).expect("applyment serialize")
+    }
+}
+
+/// 驳回原因
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AuditDetail {
+    #[serde(default)]
+    pub field: String,
+    #[serde(default)]
+    pub field_name: String,
+    #[serde(default)]
+    pub reject_reason: String,
+}
+
+/// 进件申请单状态
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ApplymentState {
+    #[serde(default)]
+    pub business_code: String,
+    #[serde(default)]
+    pub applyment_id: i64,
+    #[serde(default)]
+    pub sub_mchid: Option<String>,
+    /// 超级管理员签约链接
+    #[serde(default)]
+    pub sign_url: Option<String>,
+    /// APPLYMENT_STATE_EDITTING / AUDITING / REJECTED / TO_BE_CONFIRMED /
+    /// TO_BE_SIGNED / SIGNING / FINISHED / CANCELED
+    pub applyment_state: String,
+    #[serde(default)]
+    pub applyment_state_msg: String,
+    #[serde(default)]
+    pub audit_detail: Vec<AuditDetail>,
+    #[serde(skip)]
+    pub raw: Value,
+}
+
+impl ApplymentState {
+    pub fn from_value(value: Value) -> Result<Self, PayError> {
+        let mut state: Self = serde_json::from_value(value.clone())?;
+        state.raw = value;
+        Ok(state)
+    }
+
+    pub fn is_rejected(&self) -> bool {
+        self.applyment_state == "APPLYMENT_STATE_REJECTED"
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.applyment_state == "APPLYMENT_STATE_FINISHED"
+    }
+}
+
+impl RawPayload for ApplymentState {
+    fn raw(&self) -> &Value {
+        &self.raw
+    }
+}
+
+impl WechatClient {
+    /// 特约商户进件（服务商，/v3/applyment4sub/applyment/）
+    ///
+    /// 图片类字段先调用 `upload_image` 获取 media_id；姓名、证件号、手机号、
+    /// 邮箱、银行账号等传明文，自动使用平台证书加密并带上 Wechatpay-Serial 头。
+    pub async fn applyment(&self, applyment: impl Into<Value>) -> Result<Value, PayError> {
+        let mut applyment = applyment.into();
+        if applyment.get("business_code").is_none() {
+            return Err(PayError::Other("Missing required field: 'business_code'".into()));
+        }
+        let ubo_count = applyment
+            .pointer("/subject_info/ubo_info_list")
+            .and_then(|v| v.as_array())
+            .map_or(0, |l| l.len());
+        let mut pointers: Vec<String> = SENSITIVE_FIELDS.iter().map(|p| p.to_string()).collect();
+        for i in 0..ubo_count {
+            for field in UBO_SENSITIVE_FIELDS {
+                pointers.push(format!("/subject_info/ubo_info_list/{}/{}", i, field));
+            }
+        }
+        let pointers: Vec<&str> = pointers.iter().map(String::as_str).collect();
+        let serial = self.encrypt_fields(&mut applyment, &pointers).await?;
+        let url = self.endpoint("/v3/applyment4sub/applyment/");
+        self.sign_and_post_with_serial("POST", &url, &applyment, serial.as_deref())
+            .await
+    }
+
+    /// 小微商户进件（服务商）
+    ///
+    /// 原 V2 小微商户接口（applyment/micro/submit）已下线，小微商户改为通过
+    /// 特约商户进件接口以 `SUBJECT_TYPE_MICRO` 主体提交，门店图片等资料先调用
+    /// `upload_image` 获取 media_id。
+    pub async fn applyment_micro(&self, mut applyment: Value) -> Result<Value, PayError> {
+        if applyment.pointer("/subject_info/subject_type").is_none() {
+            if applyment.get("subject_info").is_none() {
+                applyment["subject_info"] = json!({});
+            }
+            applyment["subject_info"]["subject_type"] = json!("SUBJECT_TYPE_MICRO");
+        }
+        self.applyment(applyment).await
+    }
+
+    /// 按业务申请编号查询进件状态
+    pub async fn query_applyment_by_business_code(
+        &self,
+        business_code: &str,
+    ) -> Result<ApplymentState, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/applyment4sub/applyment/business_code/{}",
+            urlencoding::encode(business_code)
+        ));
+        let resp = self.sign_and_post("GET", &url, &Value::Null).await?;
+        ApplymentState::from_value(resp)
+    }
+
+    /// 按申请单号查询进件状态
+    pub async fn query_applyment(&self, applyment_id: i64) -> Result<ApplymentState, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/applyment4sub/applyment/applyment_id/{}",
+            applyment_id
+        ));
+        let resp = self.sign_and_post("GET", &url, &Value::Null).await?;
+        ApplymentState::from_value(resp)
+    }
+
+    /// 修改子商户结算账户，account_name / account_number 传明文，自动加密
+    ///
+    /// 返回 application_no，用于查询修改申请状态。
+    pub async fn modify_settlement(&self, sub_mchid: &str, mut account: Value) -> Result<Value, PayError> {
+        let serial = self
+            .encrypt_fields(&mut account, &["/account_name", "/account_number"])
+            .await?;
+        let url = self.endpoint(&format!(
+            "/v3/apply4sub/sub_merchants/{}/modify-settlement",
+            sub_mchid
+        ));
+        self.sign_and_post_with_serial("POST", &url, &account, serial.as_deref())
+            .await
+    }
+
+    /// 查询子商户当前结算账户（账号为掩码）
+    pub async fn query_settlement(&self, sub_mchid: &str) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!("/v3/apply4sub/sub_merchants/{}/settlement", sub_mchid));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 查询结算账户修改申请状态
+    pub async fn query_settlement_application(
+        &self,
+        sub_mchid: &str,
+        application_no: &str,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/apply4sub/sub_merchants/{}/application/{}",
+            sub_mchid, application_no
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+}
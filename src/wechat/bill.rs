@@ -0,0 +1,485 @@
+use crate::config::Mode;
+use crate::errors::PayError;
+use crate::utils::aes_gcm_decrypt_bytes;
+use crate::wechat::client::WechatClient;
+use crate::wechat::crypto::decrypt_with_merchant_key;
+use openssl::hash::{hash, MessageDigest};
+use flate2::read::GzDecoder;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::Read;
+
+/// 解析后的微信账单
+///
+/// 账单为 CSV 文本：首行表头，明细行字段以反引号 ` 开头，末尾为汇总表头与汇总行。
+/// 商品名称、商户数据包等字段可能含逗号，数据行按 ",`" 切分。
+#[derive(Clone, Debug, Default)]
+pub struct WechatBill {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    pub summary_headers: Vec<String>,
+    pub summary: Vec<String>,
+    /// 原始账单文本
+    pub raw: String,
+}
+
+impl WechatBill {
+    pub fn parse(text: &str) -> Self {
+        let split = |line: &str| -> Vec<String> {
+            let sep = if line.starts_with('`') { ",`" } else { "," };
+            line.split(sep)
+                .map(|c| c.trim().trim_start_matches('`').to_string())
+                .collect()
+        };
+        let text = text.trim_start_matches('\u{feff}');
+        let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+        let headers = lines.next().map(split).unwrap_or_default();
+        let mut rows = Vec::new();
+        let mut summary_headers = Vec::new();
+        let mut summary = Vec::new();
+        for line in lines {
+            if line.starts_with('`') && summary_headers.is_empty() {
+                rows.push(split(line));
+            } else if summary_headers.is_empty() {
+                summary_headers = split(line);
+            } else if summary.is_empty() {
+                summary = split(line);
+            }
+        }
+        Self {
+            headers,
+            rows,
+            summary_headers,
+            summary,
+            raw: text.to_string(),
+        }
+    }
+
+    /// 明细行按表头转为 列名 -> 值
+    pub fn records(&self) -> Vec<HashMap<&str, &str>> {
+        self.rows
+            .iter()
+            .map(|row| {
+                self.headers
+                    .iter()
+                    .map(String::as_str)
+                    .zip(row.iter().map(String::as_str))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// 交易账单明细行，金额单位为元
+///
+/// SUCCESS 账单不含退款列，对应字段为空。
+#[derive(Clone, Debug, Default)]
+pub struct TradeBillRecord {
+    pub trade_time: String,
+    pub appid: String,
+    pub mchid: String,
+    pub sub_mchid: String,
+    pub device_info: String,
+    pub transaction_id: String,
+    pub out_trade_no: String,
+    pub openid: String,
+    pub trade_type: String,
+    pub trade_state: String,
+    pub bank_type: String,
+    pub currency: String,
+    /// 应结订单金额
+    pub settlement_total: Decimal,
+    /// 代金券金额
+    pub coupon_amount: Decimal,
+    pub refund_id: String,
+    pub out_refund_no: String,
+    pub refund_amount: Decimal,
+    pub coupon_refund_amount: Decimal,
+    pub refund_type: String,
+    pub refund_status: String,
+    pub goods_name: String,
+    pub attach: String,
+    pub fee: Decimal,
+    pub rate: String,
+    /// 订单金额
+    pub order_amount: Decimal,
+    /// 申请退款金额
+    pub request_refund_amount: Decimal,
+    pub rate_remark: String,
+}
+
+/// 交易账单汇总行
+#[derive(Clone, Debug, Default)]
+pub struct TradeBillSummary {
+    pub total_count: u64,
+    pub settlement_total: Decimal,
+    pub refund_total: Decimal,
+    pub coupon_refund_total: Decimal,
+    pub fee_total: Decimal,
+    pub order_total: Decimal,
+    pub request_refund_total: Decimal,
+}
+
+/// 资金账单明细行，金额单位为元
+#[derive(Clone, Debug, Default)]
+pub struct FundflowBillRecord {
+    pub accounting_time: String,
+    /// 微信支付业务单号
+    pub biz_no: String,
+    /// 资金流水单号
+    pub flow_no: String,
+    pub biz_name: String,
+    pub biz_type: String,
+    /// 收入 / 支出
+    pub in_out: String,
+    pub amount: Decimal,
+    pub balance: Decimal,
+    pub applicant: String,
+    pub remark: String,
+    pub voucher_no: String,
+}
+
+/// 资金账单汇总行
+#[derive(Clone, Debug, Default)]
+pub struct FundflowBillSummary {
+    pub total_count: u64,
+    pub income_count: u64,
+    pub income_amount: Decimal,
+    pub expense_count: u64,
+    pub expense_amount: Decimal,
+}
+
+// 按列名取值；资金账单列名带"(元)"等后缀，精确匹配不到时按前缀匹配
+fn column<'a>(record: &HashMap<&str, &'a str>, name: &str) -> &'a str {
+    record
+        .get(name)
+        .or_else(|| {
+            record
+                .iter()
+                .find(|(k, _)| k.starts_with(name))
+                .map(|(_, v)| v)
+        })
+        .copied()
+        .unwrap_or_default()
+}
+
+fn text(record: &HashMap<&str, &str>, name: &str) -> String {
+    column(record, name).to_string()
+}
+
+fn amount(record: &HashMap<&str, &str>, name: &str) -> Result<Decimal, PayError> {
+    let value = column(record, name).trim_start_matches('¥');
+    if value.is_empty() {
+        return Ok(Decimal::ZERO);
+    }
+    value
+        .parse()
+        .map_err(|e| PayError::Other(format!("bill column {} '{}': {}", name, value, e)))
+}
+
+fn count(record: &HashMap<&str, &str>, name: &str) -> Result<u64, PayError> {
+    let value = column(record, name);
+    if value.is_empty() {
+        return Ok(0);
+    }
+    value
+        .parse()
+        .map_err(|e| PayError::Other(format!("bill column {} '{}': {}", name, value, e)))
+}
+
+impl WechatBill {
+    /// 按交易账单解析明细行
+    pub fn trade_records(&self) -> Result<Vec<TradeBillRecord>, PayError> {
+        self.records()
+            .iter()
+            .map(|r| {
+                Ok(TradeBillRecord {
+                    trade_time: text(r, "交易时间"),
+                    appid: text(r, "公众账号ID"),
+                    mchid: text(r, "商户号"),
+                    sub_mchid: text(r, "特约商户号"),
+                    device_info: text(r, "设备号"),
+                    transaction_id: text(r, "微信订单号"),
+                    out_trade_no: text(r, "商户订单号"),
+                    openid: text(r, "用户标识"),
+                    trade_type: text(r, "交易类型"),
+                    trade_state: text(r, "交易状态"),
+                    bank_type: text(r, "付款银行"),
+                    currency: text(r, "货币种类"),
+                    settlement_total: amount(r, "应结订单金额")?,
+                    coupon_amount: amount(r, "代金券金额")?,
+                    refund_id: text(r, "微信退款单号"),
+                    out_refund_no: text(r, "商户退款单号"),
+                    refund_amount: amount(r, "退款金额")?,
+                    coupon_refund_amount: amount(r, "充值券退款金额")?,
+                    refund_type: text(r, "退款类型"),
+                    refund_status: text(r, "退款状态"),
+                    goods_name: text(r, "商品名称"),
+                    attach: text(r, "商户数据包"),
+                    fee: amount(r, "手续费")?,
+                    rate: text(r, "费率"),
+                    order_amount: amount(r, "订单金额")?,
+                    request_refund_amount: amount(r, "申请退款金额")?,
+                    rate_remark: text(r, "费率备注"),
+                })
+            })
+            .collect()
+    }
+
+    /// 按交易账单解析汇总行
+    pub fn trade_summary(&self) -> Result<TradeBillSummary, PayError> {
+        let r = self.summary_record();
+        Ok(TradeBillSummary {
+            total_count: count(&r, "总交易单数")?,
+            settlement_total: amount(&r, "应结订单总金额")?,
+            refund_total: amount(&r, "退款总金额")?,
+            coupon_refund_total: amount(&r, "充值券退款总金额")?,
+            fee_total: amount(&r, "手续费总金额")?,
+            order_total: amount(&r, "订单总金额")?,
+            request_refund_total: amount(&r, "申请退款总金额")?,
+        })
+    }
+
+    /// 按资金账单解析明细行
+    pub fn fundflow_records(&self) -> Result<Vec<FundflowBillRecord>, PayError> {
+        self.records()
+            .iter()
+            .map(|r| {
+                Ok(FundflowBillRecord {
+                    accounting_time: text(r, "记账时间"),
+                    biz_no: text(r, "微信支付业务单号"),
+                    flow_no: text(r, "资金流水单号"),
+                    biz_name: text(r, "业务名称"),
+                    biz_type: text(r, "业务类型"),
+                    in_out: text(r, "收支类型"),
+                    amount: amount(r, "收支金额")?,
+                    balance: amount(r, "账户结余")?,
+                    applicant: text(r, "资金变更提交申请人"),
+                    remark: text(r, "备注"),
+                    voucher_no: text(r, "业务凭证号"),
+                })
+            })
+            .collect()
+    }
+
+    /// 按资金账单解析汇总行
+    pub fn fundflow_summary(&self) -> Result<FundflowBillSummary, PayError> {
+        let r = self.summary_record();
+        Ok(FundflowBillSummary {
+            total_count: count(&r, "资金流水总笔数")?,
+            income_count: count(&r, "收入笔数")?,
+            income_amount: amount(&r, "收入金额")?,
+            expense_count: count(&r, "支出笔数")?,
+            expense_amount: amount(&r, "支出金额")?,
+        })
+    }
+
+    fn summary_record(&self) -> HashMap<&str, &str> {
+        self.summary_headers
+            .iter()
+            .map(String::as_str)
+            .zip(self.summary.iter().map(String::as_str))
+            .collect()
+    }
+}
+
+// GZIP 账单按魔数识别并解压，其余原样返回
+fn gunzip_bill(bytes: Vec<u8>) -> Result<Vec<u8>, PayError> {
+    if !bytes.starts_with(&[0x1f, 0x8b]) {
+        return Ok(bytes);
+    }
+    let mut plain = Vec::new();
+    GzDecoder::new(bytes.as_slice())
+        .read_to_end(&mut plain)
+        .map_err(|e| PayError::Other(format!("gunzip bill: {}", e)))?;
+    Ok(plain)
+}
+
+// 校验账单 / 回单摘要（GZIP 账单为解压后的摘要），hash_type 缺省为 SHA1
+pub(crate) fn verify_bill_hash(apply: &Value, bytes: &[u8]) -> Result<(), PayError> {
+    let expected = match apply.get("hash_value").and_then(|v| v.as_str()) {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+    let hash_type = apply
+        .get("hash_type")
+        .and_then(|v| v.as_str())
+        .unwrap_or("SHA1")
+        .to_ascii_uppercase();
+    let digest = match hash_type.as_str() {
+        "SHA1" => MessageDigest::sha1(),
+        "SHA256" => MessageDigest::sha256(),
+        "SM3" => MessageDigest::sm3(),
+        other => return Err(PayError::Other(format!("unsupported bill hash_type: {}", other))),
+    };
+    let actual: String = hash(digest, bytes)
+        .map_err(|e| PayError::Crypto(format!("bill hash: {}", e)))?
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(PayError::BillHashMismatch {
+            hash_type,
+            expected: expected.to_string(),
+            actual,
+        });
+    }
+    Ok(())
+}
+
+impl WechatClient {
+    /// 申请交易账单，bill_type 为 ALL / SUCCESS / REFUND；服务商模式按配置的子商户申请
+    pub async fn apply_trade_bill(&self, bill_date: &str, bill_type: &str) -> Result<Value, PayError> {
+        let query = json!({
+            "bill_date": bill_date,
+            "bill_type": bill_type,
+            "sub_mchid": self.service_sub_mchid(),
+        });
+        self.sign_and_get("/v3/bill/tradebill", &self.with_tar_type(query))
+            .await
+    }
+
+    /// 下载并解析交易账单
+    pub async fn trade_bill(&self, bill_date: &str, bill_type: &str) -> Result<WechatBill, PayError> {
+        let apply = self.apply_trade_bill(bill_date, bill_type).await?;
+        let bytes = self.download_bill(&apply).await?;
+        Ok(WechatBill::parse(&String::from_utf8_lossy(&bytes)))
+    }
+
+    /// 申请资金账单，account_type 为 BASIC（基本账户）/ OPERATION（运营账户）/ FEES（手续费账户）
+    pub async fn apply_fundflow_bill(
+        &self,
+        bill_date: &str,
+        account_type: &str,
+    ) -> Result<Value, PayError> {
+        let query = json!({ "bill_date": bill_date, "account_type": account_type });
+        self.sign_and_get("/v3/bill/fundflowbill", &self.with_tar_type(query))
+            .await
+    }
+
+    /// 下载并解析资金账单，raw 可直接交给 SettlementReport::add_wechat_fundflow
+    pub async fn fundflow_bill(
+        &self,
+        bill_date: &str,
+        account_type: &str,
+    ) -> Result<WechatBill, PayError> {
+        let apply = self.apply_fundflow_bill(bill_date, account_type).await?;
+        let bytes = self.download_bill(&apply).await?;
+        Ok(WechatBill::parse(&String::from_utf8_lossy(&bytes)))
+    }
+
+    /// 申请单个子商户资金账单（服务商），返回 download_bill_list
+    ///
+    /// account_type 为 BASIC / OPERATION / FEES；账单文件以 AEAD_AES_256_GCM 加密。
+    pub async fn apply_sub_merchant_fundflow_bill(
+        &self,
+        sub_mchid: &str,
+        bill_date: &str,
+        account_type: &str,
+    ) -> Result<Value, PayError> {
+        if !matches!(self.mode, Mode::Service) {
+            return Err(PayError::Other(
+                "sub-merchant fundflow bill requires service mode".into(),
+            ));
+        }
+        let query = json!({
+            "sub_mchid": sub_mchid,
+            "bill_date": bill_date,
+            "account_type": account_type,
+            "algorithm": "AEAD_AES_256_GCM",
+        });
+        self.sign_and_get("/v3/bill/sub-merchant-fundflowbill", &self.with_tar_type(query))
+            .await
+    }
+
+    /// 下载、解密并解析子商户资金账单，按 bill_sequence 顺序返回各分片
+    pub async fn sub_merchant_fundflow_bill(
+        &self,
+        sub_mchid: &str,
+        bill_date: &str,
+        account_type: &str,
+    ) -> Result<Vec<WechatBill>, PayError> {
+        let apply = self
+            .apply_sub_merchant_fundflow_bill(sub_mchid, bill_date, account_type)
+            .await?;
+        let mut parts: Vec<Value> = apply
+            .get("download_bill_list")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+        parts.sort_by_key(|p| p.get("bill_sequence").and_then(|v| v.as_i64()).unwrap_or(0));
+        let mut bills = Vec::with_capacity(parts.len());
+        for part in &parts {
+            let field = |k: &str| part.get(k).and_then(|v| v.as_str()).unwrap_or("");
+            let encrypted = self.download(field("download_url")).await?;
+            let key = decrypt_with_merchant_key(&self.cfg, field("encrypt_key"))
+                .map_err(|e| PayError::Crypto(format!("decrypt bill key: {}", e)))?;
+            let plain = aes_gcm_decrypt_bytes(&key, field("nonce").as_bytes(), &encrypted)
+                .map_err(|e| PayError::Crypto(format!("decrypt bill: {}", e)))?;
+            let plain = gunzip_bill(plain)?;
+            verify_bill_hash(part, &plain)?;
+            bills.push(WechatBill::parse(&String::from_utf8_lossy(&plain)));
+        }
+        Ok(bills)
+    }
+
+    /// 申请分账账单，返回 download_url / hash_type / hash_value
+    pub async fn apply_profitsharing_bill(&self, bill_date: &str) -> Result<Value, PayError> {
+        let query = json!({ "bill_date": bill_date, "sub_mchid": self.service_sub_mchid() });
+        self.sign_and_get("/v3/profitsharing/bills", &self.with_tar_type(query))
+            .await
+    }
+
+    /// 按申请账单的应答下载账单原文（GZIP 账单自动解压），并校验 hash_value
+    pub async fn download_bill(&self, apply: &Value) -> Result<Vec<u8>, PayError> {
+        let download_url = apply
+            .get("download_url")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| PayError::Other(format!("bill download_url missing: {}", apply)))?;
+        let bytes = gunzip_bill(self.download(download_url).await?)?;
+        verify_bill_hash(apply, &bytes)?;
+        Ok(bytes)
+    }
+
+    /// 下载并解析分账账单
+    pub async fn profitsharing_bill(&self, bill_date: &str) -> Result<WechatBill, PayError> {
+        let apply = self.apply_profitsharing_bill(bill_date).await?;
+        let bytes = self.download_bill(&apply).await?;
+        Ok(WechatBill::parse(&String::from_utf8_lossy(&bytes)))
+    }
+
+    // 开启 with_bill_gzip 时追加 tar_type=GZIP
+    fn with_tar_type(&self, mut query: Value) -> Value {
+        if self.bill_gzip {
+            query["tar_type"] = json!("GZIP");
+        }
+        query
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trade_bill_field_with_comma() {
+        let text = "\u{feff}交易时间,商户订单号,交易状态,商品名称,商户数据包,手续费,费率,订单金额\r\n\
+            `2024-01-01 10:00:00,`T1,`SUCCESS,`咖啡,大杯,`{\"a\":1,\"b\":2},`0.01,`0.60%,`1.00\r\n\
+            总交易单数,应结订单总金额,手续费总金额,订单总金额\r\n\
+            `1,`1.00,`0.01,`1.00\r\n";
+        let bill = WechatBill::parse(text);
+        let records = bill.trade_records().unwrap();
+        assert_eq!(records.len(), 1);
+        let r = &records[0];
+        assert_eq!(r.out_trade_no, "T1");
+        assert_eq!(r.goods_name, "咖啡,大杯");
+        assert_eq!(r.attach, r#"{"a":1,"b":2}"#);
+        assert_eq!(r.fee, Decimal::new(1, 2));
+        assert_eq!(r.rate, "0.60%");
+        assert_eq!(r.order_amount, Decimal::ONE);
+        let summary = bill.trade_summary().unwrap();
+        assert_eq!(summary.total_count, 1);
+        assert_eq!(summary.fee_total, Decimal::new(1, 2));
+    }
+}
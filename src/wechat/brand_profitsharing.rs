@@ -0,0 +1,146 @@
+use crate::config::Mode;
+use crate::errors::PayError;
+use crate::wechat::client::WechatClient;
+use serde_json::{json, Value};
+
+impl WechatClient {
+    /// 连锁品牌请求分账（/v3/brand/profitsharing/orders），需 brand_mchid
+    pub async fn brand_profitsharing(&self, order: Value) -> Result<Value, PayError> {
+        let mut body = self.brand_body(order)?;
+        let count = body
+            .get("receivers")
+            .and_then(|v| v.as_array())
+            .map_or(0, |r| r.len());
+        let pointers: Vec<String> = (0..count).map(|i| format!("/receivers/{}/name", i)).collect();
+        let pointers: Vec<&str> = pointers.iter().map(String::as_str).collect();
+        let serial = self.encrypt_fields(&mut body, &pointers).await?;
+        let url = self.endpoint("/v3/brand/profitsharing/orders");
+        self.sign_and_post_with_serial("POST", &url, &body, serial.as_deref())
+            .await
+    }
+
+    /// 查询连锁品牌分账结果
+    pub async fn query_brand_profitsharing(
+        &self,
+        transaction_id: &str,
+        out_order_no: &str,
+    ) -> Result<Value, PayError> {
+        let sub_mchid = self.brand_sub_mchid()?;
+        let url = self.endpoint(&format!(
+            "/v3/brand/profitsharing/orders?sub_mchid={}&transaction_id={}&out_order_no={}",
+            sub_mchid,
+            transaction_id,
+            urlencoding::encode(out_order_no)
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 连锁品牌完结分账
+    pub async fn finish_brand_profitsharing(&self, order: Value) -> Result<Value, PayError> {
+        let body = self.brand_body(order)?;
+        let url = self.endpoint("/v3/brand/profitsharing/finish-order");
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    /// 查询连锁品牌订单剩余待分金额
+    pub async fn query_brand_profitsharing_amounts(
+        &self,
+        transaction_id: &str,
+    ) -> Result<Value, PayError> {
+        self.brand_sub_mchid()?;
+        let url = self.endpoint(&format!(
+            "/v3/brand/profitsharing/orders/{}/amounts",
+            transaction_id
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 查询连锁品牌最大分账比例
+    pub async fn query_brand_config(&self, brand_mchid: &str) -> Result<Value, PayError> {
+        self.brand_sub_mchid()?;
+        let url = self.endpoint(&format!(
+            "/v3/brand/profitsharing/brand-configs/{}",
+            brand_mchid
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 连锁品牌分账回退
+    pub async fn brand_profitsharing_return(&self, order: Value) -> Result<Value, PayError> {
+        let body = self.brand_body(order)?;
+        let url = self.endpoint("/v3/brand/profitsharing/returnorders");
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    /// 查询连锁品牌分账回退结果
+    pub async fn query_brand_profitsharing_return(
+        &self,
+        out_order_no: &str,
+        out_return_no: &str,
+    ) -> Result<Value, PayError> {
+        let sub_mchid = self.brand_sub_mchid()?;
+        let url = self.endpoint(&format!(
+            "/v3/brand/profitsharing/returnorders?sub_mchid={}&out_order_no={}&out_return_no={}",
+            sub_mchid,
+            urlencoding::encode(out_order_no),
+            urlencoding::encode(out_return_no)
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 连锁品牌添加分账接收方，name 以平台证书加密
+    pub async fn add_brand_receiver(&self, receiver: Value) -> Result<Value, PayError> {
+        let mut body = self.brand_receiver_body(receiver)?;
+        let serial = self.encrypt_fields(&mut body, &["/name"]).await?;
+        let url = self.endpoint("/v3/brand/profitsharing/receivers/add");
+        self.sign_and_post_with_serial("POST", &url, &body, serial.as_deref())
+            .await
+    }
+
+    /// 连锁品牌删除分账接收方
+    pub async fn delete_brand_receiver(&self, receiver: Value) -> Result<Value, PayError> {
+        let body = self.brand_receiver_body(receiver)?;
+        let url = self.endpoint("/v3/brand/profitsharing/receivers/delete");
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    // 连锁品牌分账仅服务商可用，子商户号取配置
+    fn brand_sub_mchid(&self) -> Result<&str, PayError> {
+        if !matches!(self.mode, Mode::Service) {
+            return Err(PayError::Other(
+                "brand profitsharing requires service mode".into(),
+            ));
+        }
+        self.cfg
+            .sub_mchid
+            .as_deref()
+            .ok_or_else(|| PayError::Other("sub_mchid required for brand profitsharing".into()))
+    }
+
+    fn brand_body(&self, mut body: Value) -> Result<Value, PayError> {
+        let sub_mchid = self.brand_sub_mchid()?;
+        if body.get("sub_mchid").is_none() {
+            body["sub_mchid"] = json!(sub_mchid);
+        }
+        if body.get("appid").is_none() {
+            if let Some(appid) = &self.cfg.appid {
+                body["appid"] = json!(appid);
+            }
+        }
+        Ok(body)
+    }
+
+    // 接收方接口以品牌主商户号 brand_mchid 区分，不传 sub_mchid
+    fn brand_receiver_body(&self, mut body: Value) -> Result<Value, PayError> {
+        self.brand_sub_mchid()?;
+        if body.get("brand_mchid").is_none() {
+            return Err(PayError::Other("brand_mchid required".into()));
+        }
+        if body.get("appid").is_none() {
+            if let Some(appid) = &self.cfg.appid {
+                body["appid"] = json!(appid);
+            }
+        }
+        Ok(body)
+    }
+}
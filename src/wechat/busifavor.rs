@@ -0,0 +1,212 @@
+use crate::errors::PayError;
+use crate::model::{parse_with_mode, ParseMode, RawPayload, WithExtras};
+use crate::wechat::client::WechatClient;
+use crate::wechat::notify::WechatNotify;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// 满减券规则，金额单位分
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct FixedNormalCoupon {
+    pub discount_amount: i64,
+    pub transaction_minimum: i64,
+}
+
+/// 折扣券规则，discount_percent 为 1-99 的整数（88 即 8.8 折）
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DiscountCoupon {
+    pub discount_percent: i64,
+    pub transaction_minimum: i64,
+}
+
+/// 换购券规则
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ExchangeCoupon {
+    pub exchange_price: i64,
+    pub transaction_minimum: i64,
+}
+
+/// 核销规则
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BusifavorUseRule {
+    /// 可用时间，结构与官方文档一致
+    pub coupon_available_time: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fixed_normal_coupon: Option<FixedNormalCoupon>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discount_coupon: Option<DiscountCoupon>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exchange_coupon: Option<ExchangeCoupon>,
+    /// OFF_LINE / MINI_PROGRAMS / SELF_CONSUME / PAYMENT_CODE
+    pub use_method: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mini_programs_appid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mini_programs_path: Option<String>,
+}
+
+/// 发放规则
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BusifavorSendRule {
+    pub max_coupons: i64,
+    pub max_coupons_per_user: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_coupons_by_day: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub natural_person_limit: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prevent_api_abuse: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transferable: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shareable: Option<bool>,
+}
+
+/// 商家券批次创建请求
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BusifavorStock {
+    pub stock_name: String,
+    /// 缺省为本商户号
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub belong_merchant: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    pub goods_name: String,
+    /// NORMAL / DISCOUNT / EXCHANGE
+    pub stock_type: String,
+    pub coupon_use_rule: BusifavorUseRule,
+    pub stock_send_rule: BusifavorSendRule,
+    pub out_request_no: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_entrance: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_pattern_info: Option<Value>,
+    /// WECHATPAY_MODE / MERCHANT_API / MERCHANT_UPLOAD
+    pub coupon_code_mode: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notify_config: Option<Value>,
+}
+
+impl From<BusifavorStock> for Value {
+    fn from(stock: BusifavorStock) -> Value {
+        serde_json::to_value(stock).expect("stock serialize")
+    }
+}
+
+/// 商家券领券 / 核销事件通知
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BusifavorCouponNotice {
+    /// 通知类型，如 COUPON.SEND
+    #[serde(default)]
+    pub event_type: String,
+    pub coupon_code: String,
+    pub stock_id: String,
+    #[serde(default)]
+    pub openid: Option<String>,
+    #[serde(default)]
+    pub unionid: Option<String>,
+    #[serde(default)]
+    pub send_time: Option<String>,
+    #[serde(default)]
+    pub send_channel: Option<String>,
+    #[serde(default)]
+    pub send_merchant: Option<String>,
+    #[serde(default)]
+    pub attach_info: Option<Value>,
+    /// 未声明的字段
+    #[serde(flatten)]
+    pub extras: Map<String, Value>,
+    #[serde(skip)]
+    pub raw: Value,
+}
+
+impl WithExtras for BusifavorCouponNotice {
+    fn extras(&self) -> &Map<String, Value> {
+        &self.extras
+    }
+}
+
+impl RawPayload for BusifavorCouponNotice {
+    fn raw(&self) -> &Value {
+        &self.raw
+    }
+}
+
+impl WechatClient {
+    /// 创建商家券批次
+    pub async fn create_busifavor_stock(&self, stock: impl Into<Value>) -> Result<Value, PayError> {
+        let mut stock = stock.into();
+        if stock.get("belong_merchant").is_none() {
+            stock["belong_merchant"] = json!(self.cfg.mchid);
+        }
+        let url = self.endpoint("/v3/marketing/busifavor/stocks");
+        self.sign_and_post("POST", &url, &stock).await
+    }
+
+    /// 查询商家券批次详情
+    pub async fn query_busifavor_stock(&self, stock_id: &str) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!("/v3/marketing/busifavor/stocks/{}", stock_id));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 发放消费卡（商家券），body 含 appid、openid、out_request_no、send_time
+    pub async fn send_busifavor_coupon(&self, card_id: &str, mut body: Value) -> Result<Value, PayError> {
+        if body.get("appid").is_none() {
+            if let Some(appid) = &self.cfg.appid {
+                body["appid"] = json!(appid);
+            }
+        }
+        let url = self.endpoint(&format!("/v3/marketing/busifavor/coupons/{}/send", card_id));
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    /// 核销用户券，body 含 coupon_code、stock_id、use_time、use_request_no 等
+    pub async fn use_busifavor_coupon(&self, mut body: Value) -> Result<Value, PayError> {
+        if body.get("appid").is_none() {
+            if let Some(appid) = &self.cfg.appid {
+                body["appid"] = json!(appid);
+            }
+        }
+        let url = self.endpoint("/v3/marketing/busifavor/coupons/use");
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    /// 查询用户单张券详情
+    pub async fn query_busifavor_coupon(&self, openid: &str, coupon_code: &str) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/marketing/busifavor/users/{}/coupons/{}/appids/{}",
+            openid,
+            coupon_code,
+            self.cfg.appid.as_deref().unwrap_or_default()
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 修改批次预算，body 含 target_max_coupons 或 target_max_coupons_by_day、modify_request_no
+    pub async fn modify_busifavor_budget(&self, stock_id: &str, body: Value) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!("/v3/marketing/busifavor/stocks/{}/budget", stock_id));
+        self.sign_and_post("PATCH", &url, &body).await
+    }
+
+    /// 设置商家券事件通知地址
+    pub async fn set_busifavor_callback(&self, notify_url: &str) -> Result<Value, PayError> {
+        let url = self.endpoint("/v3/marketing/busifavor/callbacks");
+        let body = json!({ "mchid": self.cfg.mchid, "notify_url": notify_url });
+        self.sign_and_post("POST", &url, &body).await
+    }
+}
+
+impl WechatNotify {
+    /// 验签并解密商家券事件通知
+    pub async fn verify_busifavor_coupon(
+        &self,
+        headers: &HashMap<String, String>,
+        body: &str,
+    ) -> Result<BusifavorCouponNotice, PayError> {
+        let plain = self.verify_event(headers, body).await?;
+        let mut notice: BusifavorCouponNotice = parse_with_mode(plain.clone(), ParseMode::Lenient)?;
+        notice.raw = plain;
+        Ok(notice)
+    }
+}
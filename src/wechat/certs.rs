@@ -1,27 +1,66 @@
-use crate::config::WechatConfig;
-use crate::utils::{
-    aes_gcm_decrypt, extract_pubkey_from_cert, gen_nonce, now_ts, retry_async, rsa_sign_sha256_pem,
-};
+use crate::config::{SignAlgorithm, WechatConfig};
+use crate::errors::PayError;
+use crate::utils::{extract_pubkey_from_cert, gen_nonce, now_ts, retry_async};
+use crate::wechat::crypto::{auth_schema, decrypt_resource, sign_with_serial};
+use crate::wechat::failover;
 use reqwest::Client;
 use serde_json::Value;
 use std::collections::HashMap;
+use arc_swap::ArcSwap;
+use openssl::pkey::PKey;
+use openssl::x509::X509;
+use chrono::{DateTime, FixedOffset, Utc};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use url::Url;
+
+/// 临近过期时两次主动刷新的最小间隔，避免微信尚未签发新证书时反复下载
+const MIN_PROACTIVE_INTERVAL: Duration = Duration::from_secs(600);
+
+/// 平台证书有效期，取自 /v3/certificates 应答
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CertValidity {
+    pub effective_time: DateTime<FixedOffset>,
+    pub expire_time: DateTime<FixedOffset>,
+}
+
 pub struct PlatformCerts {
-    pub map: Arc<Mutex<HashMap<String, String>>>,
+    /// 证书序列号 -> 平台公钥 PEM；读取为无锁快照，刷新时整体替换
+    pub map: Arc<ArcSwap<HashMap<String, String>>>,
+    /// 证书序列号 -> 有效期；配置预置的证书没有记录
+    validity: ArcSwap<HashMap<String, CertValidity>>,
+    last_refresh: Mutex<Option<Instant>>,
     client: Client,
     cfg: Arc<WechatConfig>,
 }
 impl PlatformCerts {
     pub fn new(cfg: Arc<WechatConfig>) -> Self {
         Self {
-            map: Arc::new(Mutex::new(HashMap::new())),
+            map: Arc::new(ArcSwap::from_pointee(seeded_certs(&cfg))),
+            validity: ArcSwap::from_pointee(HashMap::new()),
+            last_refresh: Mutex::new(None),
             client: Client::new(),
             cfg,
         }
     }
+    /// 复用已有的 HTTP 连接池
+    pub(crate) fn with_http(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
     pub async fn refresh(&self) -> anyhow::Result<()> {
-        let url = "https://api.mch.weixin.qq.com/v3/certificates";
+        // 公钥模式商户没有平台证书，/v3/certificates 不可用
+        if self.cfg.wechatpay_public_key().is_some() {
+            return Ok(());
+        }
+        // 国密商户需下载 SM2 平台证书
+        let host = self.cfg.api_host();
+        let url = match self.cfg.sign_algorithm {
+            SignAlgorithm::Rsa => format!("{}/v3/certificates", host),
+            SignAlgorithm::Sm2 => format!("{}/v3/certificates?algorithm_type=SM2", host),
+        };
+        let url = url.as_str();
+        *self.last_refresh.lock().unwrap() = Some(Instant::now());
         let ts = now_ts();
         let nonce = gen_nonce(32);
         let method = "GET";
@@ -32,28 +71,46 @@ impl PlatformCerts {
             parsed.path().to_string()
         };
         let sign_str = format!("{}\n{}\n{}\n{}\n\n", method, path, ts, nonce);
-        let signature = rsa_sign_sha256_pem(&self.cfg.private_key_pem, &sign_str)?;
+        let (serial_no, signature) = sign_with_serial(&self.cfg, &sign_str)?;
         let auth = format!(
-            r#"WECHATPAY2-SHA256-RSA2048 mchid="{}",nonce_str="{}",timestamp="{}",serial_no="{}",signature="{}""#,
-            self.cfg.mchid, nonce, ts, self.cfg.serial_no, signature
+            r#"{} mchid="{}",nonce_str="{}",timestamp="{}",serial_no="{}",signature="{}""#,
+            auth_schema(&self.cfg), self.cfg.mchid, nonce, ts, serial_no, signature
         );
         let client = &self.client;
-        let txt = retry_async(3, || async {
-            let r = client
-                .get(url)
-                .header("Authorization", auth.clone())
-                .header("Accept", "application/json")
-                .header("User-Agent", "rust_pay_wf")
-                .send()
-                .await?;
-            Ok::<String, reqwest::Error>(r.text().await?)
-        })
-        .await?;
+        // 签名串只含路径，切换域名不影响签名
+        let txt = loop {
+            let active = failover::active_host(&self.cfg);
+            let url = match url.strip_prefix(host) {
+                Some(rest) if active != host => format!("{}{}", active, rest),
+                _ => url.to_string(),
+            };
+            let result = retry_async(3, || async {
+                let r = client
+                    .get(&url)
+                    .header("Authorization", auth.clone())
+                    .header("Accept", "application/json")
+                    .header("User-Agent", "rust_pay_wf")
+                    .send()
+                    .await?;
+                r.text().await
+            })
+            .await
+            .map_err(PayError::Http);
+            match result {
+                Ok(txt) => {
+                    failover::report_success(&self.cfg, active);
+                    break txt;
+                }
+                Err(e) if failover::report_failure(&self.cfg, active, &e) => continue,
+                Err(e) => return Err(e.into()),
+            }
+        };
         println!("[refresh]  body={}", txt);
         let v: Value = serde_json::from_str(&txt)?;
         if let Some(arr) = v.get("data").and_then(|d| d.as_array()) {
-            let mut m = self.map.lock().unwrap();
-            m.clear();
+            // 先完整构建新表再替换，刷新失败时保留旧证书；配置预置的证书始终保留
+            let mut m = seeded_certs(&self.cfg);
+            let mut validity = HashMap::new();
             for cert in arr {
                 if let (Some(serial), Some(resource)) =
                     (cert.get("serial_no"), cert.get("encrypt_certificate"))
@@ -67,25 +124,153 @@ impl PlatformCerts {
                         .get("associated_data")
                         .and_then(|c| c.as_str())
                         .unwrap_or("");
-                    let pem = aes_gcm_decrypt(&self.cfg.api_v3_key, aad, nonce_r, cipher)?;
+                    let algorithm = resource
+                        .get("algorithm")
+                        .and_then(|c| c.as_str())
+                        .unwrap_or("AEAD_AES_256_GCM");
+                    let pem = decrypt_resource(&self.cfg, algorithm, aad, nonce_r, cipher)?;
                     let pub_pem = extract_pubkey_from_cert(&pem)?; // 提取公钥
-                    println!("[refresh] store cert serial={} pub_pem={}", serial.as_str().unwrap_or_default().to_string(), pub_pem);
-                    m.insert(serial.as_str().unwrap_or_default().to_string(), pub_pem);
+                    println!("[refresh] store cert serial={} pub_pem={}", serial.as_str().unwrap_or_default(), pub_pem);
+                    let serial = serial.as_str().unwrap_or_default().to_string();
+                    let time = |key: &str| {
+                        cert.get(key)
+                            .and_then(|t| t.as_str())
+                            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                    };
+                    if let (Some(effective_time), Some(expire_time)) =
+                        (time("effective_time"), time("expire_time"))
+                    {
+                        validity.insert(
+                            serial.clone(),
+                            CertValidity {
+                                effective_time,
+                                expire_time,
+                            },
+                        );
+                    }
+                    m.insert(serial, pub_pem);
                 }
             }
+            self.map.store(Arc::new(m));
+            self.validity.store(Arc::new(validity));
         }
         Ok(())
     }
     pub fn get_by_serial(&self, serial: &str) -> Option<String> {
-        let m = self.map.lock().unwrap();
-        m.get(serial).cloned()
+        self.map.load().get(serial).cloned()
     }
-    // 获取第一个证书的公钥（键值对）
+    // 获取用于加密的证书公钥（键值对）：优先已生效且过期时间最晚的证书
     pub fn get_first_cert(&self) -> Option<(String, String)> {
-        let cert_map = self.map.lock().unwrap();
-        cert_map.iter()
-            .next()
+        let now = Utc::now();
+        let validity = self.validity.load();
+        self.map
+            .load()
+            .iter()
+            .max_by_key(|(k, _)| {
+                validity
+                    .get(*k)
+                    .filter(|v| v.effective_time <= now)
+                    .map(|v| v.expire_time)
+            })
             .map(|(k, v)| (k.clone(), v.clone()))
     }
 
+    /// 证书有效期
+    pub fn validity(&self, serial: &str) -> Option<CertValidity> {
+        self.validity.load().get(serial).copied()
+    }
+
+    /// 最晚过期的证书也将在 within 内过期
+    pub fn expiring_within(&self, within: chrono::Duration) -> bool {
+        self.validity
+            .load()
+            .values()
+            .map(|v| v.expire_time)
+            .max()
+            .is_some_and(|t| t.signed_duration_since(Utc::now()) < within)
+    }
+
+    /// 证书临近过期（cert_refresh_ahead_hours，默认 24 小时）时主动刷新，返回是否刷新
+    ///
+    /// 两次主动刷新至少间隔 10 分钟。
+    pub async fn refresh_if_expiring(&self) -> anyhow::Result<bool> {
+        let hours = self.cfg.cert_refresh_ahead_hours.unwrap_or(24);
+        let ahead = chrono::Duration::hours(i64::from(hours));
+        if !self.expiring_within(ahead) {
+            return Ok(false);
+        }
+        // 检查与占位在同一把锁内完成，并发调用只有一个会发起刷新
+        {
+            let mut last = self.last_refresh.lock().unwrap();
+            if last.is_some_and(|t| t.elapsed() < MIN_PROACTIVE_INTERVAL) {
+                return Ok(false);
+            }
+            *last = Some(Instant::now());
+        }
+        tracing::info!("platform certs expire within {}h, refreshing", ahead.num_hours());
+        self.refresh().await?;
+        Ok(true)
+    }
+    /// 写入单个平台证书公钥（复制后替换，不阻塞并发读取）
+    pub fn insert(&self, serial: &str, public_key_pem: &str) {
+        self.map.rcu(|m| {
+            let mut m = HashMap::clone(m);
+            m.insert(serial.to_string(), public_key_pem.to_string());
+            m
+        });
+    }
+}
+
+/// 配置中预置的平台公钥：公钥模式的公钥、platform_certs 与 platform_certs_dir，
+/// 无法解析的条目记录日志后跳过
+fn seeded_certs(cfg: &WechatConfig) -> HashMap<String, String> {
+    let mut m = HashMap::new();
+    if let Some((id, pem)) = cfg.wechatpay_public_key() {
+        m.insert(id.to_string(), pem.to_string());
+    }
+    if let Some(dir) = &cfg.platform_certs_dir {
+        match std::fs::read_dir(dir) {
+            Ok(entries) => {
+                for path in entries.flatten().map(|e| e.path()) {
+                    if path.extension().and_then(|e| e.to_str()) != Some("pem") {
+                        continue;
+                    }
+                    let stem = path
+                        .file_stem()
+                        .and_then(|s| s.to_str())
+                        .unwrap_or_default();
+                    match std::fs::read_to_string(&path)
+                        .map_err(anyhow::Error::from)
+                        .and_then(|pem| public_key_entry(stem, &pem))
+                    {
+                        Ok((serial, pub_pem)) => {
+                            m.insert(serial, pub_pem);
+                        }
+                        Err(e) => tracing::warn!("skip platform cert {}: {}", path.display(), e),
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("read platform_certs_dir {}: {}", dir, e),
+        }
+    }
+    for (serial, pem) in &cfg.platform_certs {
+        match public_key_entry(serial, pem) {
+            Ok((_, pub_pem)) => {
+                m.insert(serial.clone(), pub_pem);
+            }
+            Err(e) => tracing::warn!("skip platform cert {}: {}", serial, e),
+        }
+    }
+    m
+}
+
+// 证书取其序列号与公钥；公钥 PEM 原样使用，序列号取 fallback_serial
+fn public_key_entry(fallback_serial: &str, pem: &str) -> anyhow::Result<(String, String)> {
+    if pem.contains("BEGIN CERTIFICATE") {
+        let cert = X509::from_pem(pem.as_bytes())?;
+        let serial = cert.serial_number().to_bn()?.to_hex_str()?.to_string();
+        return Ok((serial, extract_pubkey_from_cert(pem)?));
+    }
+    PKey::public_key_from_pem(pem.as_bytes())?;
+    Ok((fallback_serial.to_string(), pem.to_string()))
 }
@@ -1,24 +1,71 @@
 use crate::config::{Mode, WechatConfig};
-use crate::errors::PayError;
-use crate::utils::{
-    gen_nonce, now_ts,
-    rsa_sign_sha256_pem,
+use crate::errors::{PayError, RetryClass};
+use crate::quota::QuotaManager;
+use crate::config::SignAlgorithm;
+use crate::utils::{gen_nonce, now_ts};
+use crate::wechat::crypto::{
+    auth_schema, cut_over_to_secondary, revert_to_primary, serial_rejected, sign_message,
+    sign_with_serial,
 };
 use crate::wechat::certs::PlatformCerts;
+use crate::wechat::failover;
 use crate::wechat::notify::WechatNotify;
-use reqwest::Client;
+use crate::utils::parse_private_key;
+use once_cell::sync::OnceCell;
+use openssl::pkcs12::Pkcs12;
+use openssl::x509::X509;
+use reqwest::{Client, Identity, Method, StatusCode};
 use serde_json::{json, Value};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use url::Url;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+
+/// 已签名但未发送的请求，供自有 HTTP 栈、消息队列或网关设备转发
+#[derive(Clone, Debug)]
+pub struct SignedRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    /// GET 请求为空串
+    pub body: String,
+}
+
+/// 微信支付应答及其元信息；Request-ID 为应答头中的唯一请求号，向微信支付反馈问题时需提供
+#[derive(Clone, Debug)]
+pub struct ApiResponse<T> {
+    pub status: u16,
+    /// 应答头，名称为小写
+    pub headers: HashMap<String, String>,
+    pub request_id: Option<String>,
+    pub body: T,
+}
+
+impl<T> ApiResponse<T> {
+    /// 转换应答体，保留状态码与应答头
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> ApiResponse<U> {
+        ApiResponse {
+            status: self.status,
+            headers: self.headers,
+            request_id: self.request_id,
+            body: f(self.body),
+        }
+    }
+}
 
 pub struct WechatClient {
-    cfg: Arc<WechatConfig>,
-    http: Client,
-    certs: Arc<PlatformCerts>,
-    base_url: String,
-    mode: Mode,
+    pub(crate) cfg: Arc<WechatConfig>,
+    pub(crate) http: Client,
+    pub(crate) certs: Arc<PlatformCerts>,
+    pub(crate) base_url: String,
+    pub(crate) mode: Mode,
     max_retries: usize,
+    quota: Option<Arc<QuotaManager>>,
+    pub(crate) bill_gzip: bool,
+    // 携带商户 API 证书的 HTTP 客户端，首次调用需双向 TLS 的接口时创建
+    tls_http: OnceCell<Client>,
 }
 
 impl WechatClient {
@@ -28,12 +75,18 @@ impl WechatClient {
             .build()
             .expect("client");
         let certs = Arc::new(PlatformCerts::new(cfg.clone()));
+        Self::with_parts(cfg, mode, http, certs)
+    }
 
-        // 根据模式设置基础URL
-        let base_url = match mode {
-            Mode::Sandbox => "https://api.mch.weixin.qq.com/sandboxnew".to_string(),
-            _ => "https://api.mch.weixin.qq.com".to_string(),
-        };
+    /// 复用已有的 HTTP 连接池与平台证书缓存
+    pub(crate) fn with_parts(
+        cfg: Arc<WechatConfig>,
+        mode: Mode,
+        http: Client,
+        certs: Arc<PlatformCerts>,
+    ) -> Self {
+
+        let base_url = cfg.api_host().to_string();
 
         Self {
             cfg,
@@ -42,38 +95,77 @@ impl WechatClient {
             base_url,
             mode,
             max_retries: 3,
+            quota: None,
+            bill_gzip: false,
+            tls_http: OnceCell::new(),
         }
     }
 
-    fn endpoint(&self, path: &str) -> String {
+    // Sandbox 模式未配置 base_url 时拒绝发送，避免测试请求落到生产环境
+    pub(crate) fn ensure_sandbox_target(&self) -> Result<(), PayError> {
+        if matches!(self.mode, Mode::Sandbox) && self.cfg.base_url.is_none() {
+            return Err(PayError::Other(
+                "sandbox mode requires wechat base_url (e.g. a local mock server)".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// 携带商户 API 证书的 HTTP 客户端，用于需双向 TLS 的 V2 接口
+    pub(crate) fn tls_http(&self) -> Result<&Client, PayError> {
+        self.tls_http.get_or_try_init(|| {
+            let identity = Identity::from_pem(&client_identity_pem(&self.cfg)?)
+                .map_err(|e| PayError::Crypto(format!("client identity: {}", e)))?;
+            // Identity::from_pem 仅 rustls 后端支持
+            crate::client::Pay::client_builder()
+                .use_rustls_tls()
+                .identity(identity)
+                .build()
+                .map_err(PayError::Http)
+        })
+    }
+
+    /// 按商户号（服务商模式下为子商户号）限制并发与 TPS
+    pub fn with_quota(mut self, quota: Arc<QuotaManager>) -> Self {
+        self.quota = Some(quota);
+        self
+    }
+
+    /// 申请账单时带上 tar_type=GZIP，下载后自动解压
+    pub fn with_bill_gzip(mut self, enabled: bool) -> Self {
+        self.bill_gzip = enabled;
+        self
+    }
+
+    pub(crate) fn endpoint(&self, path: &str) -> String {
         format!("{}{}", self.base_url, path)
     }
 
     // 服务商模式下的URL路径不同
-    fn get_service_url(&self, path: &str) -> String {
+    pub(crate) fn get_service_url(&self, path: &str) -> String {
         if let Mode::Service = self.mode {
             // 服务商模式URL前缀为/partner
             if path.contains("/v3/pay/transactions/") {
                 let path = path.replace("/v3/pay/transactions/", "/v3/pay/partner/transactions/");
                 return self.endpoint(&path);
             }
-            return self.endpoint(path);
+            self.endpoint(path)
         } else {
             self.endpoint(path)
         }
     }
 
     // 构建服务商模式参数
-    fn build_service_params(&self, mut params: Value) -> Value {
+    pub(crate) fn build_service_params(&self, mut params: Value) -> Value {
         if let Mode::Service = self.mode {
             // 设置appid
-            if !params.get("appid").is_some() && !params.get("sp_appid").is_some() {
+            if params.get("appid").is_none() && params.get("sp_appid").is_none() {
                 if let Some(appid) = &self.cfg.appid {
                     params["sp_appid"] = json!(appid.clone());
                 }
             }
             // 添加服务商模式必需参数
-            if !params.get("sp_appid").is_some() {
+            if params.get("sp_appid").is_none() {
                 if let Some(sp_appid) = &self.cfg.appid {
                     params["sp_appid"] = json!(sp_appid.clone());
                 } else if let Some(appid) = &self.cfg.appid_mp {
@@ -81,36 +173,32 @@ impl WechatClient {
                 }
             }
 
-            if !params.get("sp_mchid").is_some() {
+            if params.get("sp_mchid").is_none() {
                 params["sp_mchid"] = json!(self.cfg.mchid.clone());
             }
 
-            if !params.get("sub_mchid").is_some() {
+            if params.get("sub_mchid").is_none() {
                 if let Some(sub_mchid) = &self.cfg.sub_mchid {
                     params["sub_mchid"] = json!(sub_mchid.clone());
                 }
             }
             let old_params = params.clone();
             // 处理payer字段
-            if let Some(payer) = params.get_mut("payer") {
-                if let Value::Object(payer_obj) = payer {
-                    // 服务商模式下使用sub_openid而不是openid
-                    if old_params.get("sub_appid").is_some() {
-                        if let Some(openid) = payer_obj.remove("openid") {
-                            payer_obj.insert("sub_openid".to_string(), openid);
-                        }
-                    } else {
-                        if let Some(openid) = payer_obj.remove("openid") {
-                            payer_obj.insert("sp_openid".to_string(), openid);
-                        }
+            if let Some(Value::Object(payer_obj)) = params.get_mut("payer") {
+                // 服务商模式下使用sub_openid而不是openid
+                if old_params.get("sub_appid").is_some() {
+                    if let Some(openid) = payer_obj.remove("openid") {
+                        payer_obj.insert("sub_openid".to_string(), openid);
                     }
+                } else if let Some(openid) = payer_obj.remove("openid") {
+                    payer_obj.insert("sp_openid".to_string(), openid);
                 }
             }
         } else {
             params["mchid"] = json!(self.cfg.mchid.clone());
             params["appid"] = json!(self.cfg.appid.clone());
         }
-        if !params.get("notify_url").is_some() {
+        if params.get("notify_url").is_none() {
             if let Some(notify_url) = &self.cfg.notify_url {
                 params["notify_url"] = json!(notify_url.clone());
             }
@@ -118,9 +206,18 @@ impl WechatClient {
         params
     }
 
-    pub async fn mp(&self, mut order: Value) -> Result<Value, PayError> {
+    // 调起支付的 signType，国密商户为 SM2
+    fn pay_sign_type(&self) -> &'static str {
+        match self.cfg.sign_algorithm {
+            SignAlgorithm::Rsa => "RSA",
+            SignAlgorithm::Sm2 => "SM2",
+        }
+    }
+
+    pub async fn mp(&self, order: impl Into<Value>) -> Result<Value, PayError> {
+        let mut order = order.into();
         if let Mode::Service = self.mode {
-            if !order.get("sub_appid").is_some() {
+            if order.get("sub_appid").is_none() {
                 if let Some(appid) = &self.cfg.appid_mp {
                     order["sub_appid"] = json!(appid.clone());
                 }
@@ -134,37 +231,21 @@ impl WechatClient {
         let url = self.get_service_url("/v3/pay/transactions/jsapi");
         let resp = self.sign_and_post("POST", &url, &order).await?;
         if let Some(prepay_id) = resp.get("prepay_id").and_then(|v| v.as_str()) {
-            let time_stamp = now_ts();
-            let nonce_str = gen_nonce(32);
-            let package = format!("prepay_id={}", prepay_id);
-
             // 根据模式确定appid
             let appid = if let Mode::Service = self.mode {
                 order.get("sp_appid").and_then(|v| v.as_str()).unwrap_or("")
             } else {
                 order.get("appid").and_then(|v| v.as_str()).unwrap_or("")
             };
-
-            let sign_src = format!("{}\n{}\n{}\n{}\n", appid, time_stamp, nonce_str, package);
-
-            let pay_sign = rsa_sign_sha256_pem(&self.cfg.private_key_pem, &sign_src)
-                .map_err(|e| PayError::Crypto(format!("{}", e)))?;
-
-            return Ok(json!({
-                "appId": appid,
-                "timeStamp": time_stamp,
-                "nonceStr": nonce_str,
-                "package": package,
-                "signType": "RSA",
-                "paySign": pay_sign
-            }));
+            return self.jsapi_pay_params(appid, prepay_id);
         }
         Ok(resp)
     }
 
-    pub async fn miniapp(&self, mut order: Value) -> Result<Value, PayError> {
+    pub async fn miniapp(&self, order: impl Into<Value>) -> Result<Value, PayError> {
+        let mut order = order.into();
         if let Mode::Service = self.mode {
-            if !order.get("sub_appid").is_some() {
+            if order.get("sub_appid").is_none() {
                 if let Some(appid) = &self.cfg.appid_mini {
                     order["sub_appid"] = json!(appid.clone());
                 }
@@ -179,37 +260,21 @@ impl WechatClient {
         let resp = self.sign_and_post("POST", &url, &order).await?;
 
         if let Some(prepay_id) = resp.get("prepay_id").and_then(|v| v.as_str()) {
-            let time_stamp = now_ts();
-            let nonce_str = gen_nonce(32);
-            let package = format!("prepay_id={}", prepay_id);
-
             // 根据模式确定appid
             let appid = if let Mode::Service = self.mode {
                 order.get("sp_appid").and_then(|v| v.as_str()).unwrap_or("")
             } else {
                 order.get("appid").and_then(|v| v.as_str()).unwrap_or("")
             };
-
-            let sign_src = format!("{}\n{}\n{}\n{}\n", appid, time_stamp, nonce_str, package);
-
-            let pay_sign = rsa_sign_sha256_pem(&self.cfg.private_key_pem, &sign_src)
-                .map_err(|e| PayError::Crypto(format!("{}", e)))?;
-
-            return Ok(json!({
-                "appId": appid,
-                "timeStamp": time_stamp,
-                "nonceStr": nonce_str,
-                "package": package,
-                "signType": "RSA",
-                "paySign": pay_sign
-            }));
+            return self.jsapi_pay_params(appid, prepay_id);
         }
         Ok(resp)
     }
 
-    pub async fn h5(&self, mut order: Value) -> Result<Value, PayError> {
+    pub async fn h5(&self, order: impl Into<Value>) -> Result<Value, PayError> {
+        let mut order = order.into();
         if let Mode::Service = self.mode {
-            if !order.get("sub_appid").is_some() {
+            if order.get("sub_appid").is_none() {
                 if let Some(appid) = &self.cfg.appid_mini {
                     order["sub_appid"] = json!(appid.clone());
                 }
@@ -224,9 +289,10 @@ impl WechatClient {
         Ok(resp)
     }
 
-    pub async fn app(&self, mut order: Value) -> Result<Value, PayError> {
+    pub async fn app(&self, order: impl Into<Value>) -> Result<Value, PayError> {
+        let mut order = order.into();
         if let Mode::Service = self.mode {
-            if !order.get("sub_appid").is_some() {
+            if order.get("sub_appid").is_none() {
                 if let Some(appid) = &self.cfg.appid_app {
                     order["sub_appid"] = json!(appid.clone());
                 }
@@ -239,56 +305,126 @@ impl WechatClient {
         // 使用服务商模式URL
         let url = self.get_service_url("/v3/pay/transactions/app");
         let resp = self.sign_and_post("POST", &url, &order).await?;
+        if let Some(prepay_id) = resp.get("prepay_id").and_then(|v| v.as_str()) {
+            // 服务商模式：下单带 sub_appid 时用 sub_appid 调起，partnerid 为子商户号
+            let field = |k: &str| order.get(k).and_then(|v| v.as_str()).unwrap_or("");
+            let (appid, partnerid) = if let Mode::Service = self.mode {
+                let appid = match field("sub_appid") {
+                    "" => field("sp_appid"),
+                    sub_appid => sub_appid,
+                };
+                (appid, field("sub_mchid"))
+            } else {
+                (field("appid"), self.cfg.mchid.as_str())
+            };
+
+            return self.app_pay_params(appid, partnerid, prepay_id);
+        }
         Ok(resp)
     }
 
-    pub async fn native(&self, mut order: Value) -> Result<Value, PayError> {
-        // 构建符合服务商模式的参数
-        order = self.build_service_params(order);
+    /// JSAPI / 小程序调起支付参数（二次签名）
+    pub(crate) fn jsapi_pay_params(&self, appid: &str, prepay_id: &str) -> Result<Value, PayError> {
+        let time_stamp = now_ts();
+        let nonce_str = gen_nonce(32);
+        let package = format!("prepay_id={}", prepay_id);
+        let sign_src = format!("{}\n{}\n{}\n{}\n", appid, time_stamp, nonce_str, package);
+        let pay_sign = sign_message(&self.cfg, &sign_src)?;
+        Ok(json!({
+            "appId": appid,
+            "timeStamp": time_stamp,
+            "nonceStr": nonce_str,
+            "package": package,
+            "signType": self.pay_sign_type(),
+            "paySign": pay_sign
+        }))
+    }
 
-        // 使用服务商模式URL
-        let url = self.get_service_url("/v3/pay/transactions/native");
-        let resp = self.sign_and_post("POST", &url, &order).await?;
-        Ok(resp)
+    /// APP 调起支付参数（二次签名）
+    pub(crate) fn app_pay_params(
+        &self,
+        appid: &str,
+        partnerid: &str,
+        prepay_id: &str,
+    ) -> Result<Value, PayError> {
+        let timestamp = now_ts();
+        let noncestr = gen_nonce(32);
+        let sign_src = format!("{}\n{}\n{}\n{}\n", appid, timestamp, noncestr, prepay_id);
+        let sign = sign_message(&self.cfg, &sign_src)?;
+        Ok(json!({
+            "appid": appid,
+            "partnerid": partnerid,
+            "prepayid": prepay_id,
+            "package": "Sign=WXPay",
+            "noncestr": noncestr,
+            "timestamp": timestamp,
+            "sign": sign
+        }))
     }
 
-    pub async fn micropay(&self, mut order: Value) -> Result<Value, PayError> {
+    pub async fn native(&self, order: impl Into<Value>) -> Result<Value, PayError> {
+        let mut order = order.into();
         // 构建符合服务商模式的参数
         order = self.build_service_params(order);
 
         // 使用服务商模式URL
-        let url = self.get_service_url("/v3/pay/transactions/micropay");
+        let url = self.get_service_url("/v3/pay/transactions/native");
         let resp = self.sign_and_post("POST", &url, &order).await?;
         Ok(resp)
     }
 
+    /// 按微信支付订单号查询；params 只含 out_trade_no 时按商户订单号查询
     pub async fn query(&self, mut params: Value) -> Result<Value, PayError> {
+        if params.get("transaction_id").is_none() {
+            if let Some(out_trade_no) = params.get("out_trade_no").and_then(|v| v.as_str()) {
+                return self.query_by_out_trade_no(out_trade_no).await;
+            }
+        }
         // 构建符合服务商模式的参数
         params = self.build_service_params(params);
 
-        // 使用服务商模式URL
-        let url = if let Mode::Service = self.mode {
-            "/v3/pay/partner/transactions/id/{transaction_id}".replace(
-                "{transaction_id}",
-                params
-                    .get("transaction_id")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or(""),
-            )
+        // 商户号通过查询参数传递
+        let transaction_id = params
+            .get("transaction_id")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        if let Mode::Service = self.mode {
+            let path = format!("/v3/pay/partner/transactions/id/{}", transaction_id);
+            let query = json!({
+                "sp_mchid": self.cfg.mchid,
+                "sub_mchid": params.get("sub_mchid"),
+            });
+            self.sign_and_get(&path, &query).await
         } else {
-            "/v3/pay/transactions/id/{transaction_id}".replace(
-                "{transaction_id}",
-                params
-                    .get("transaction_id")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or(""),
+            let path = format!("/v3/pay/transactions/id/{}", transaction_id);
+            self.sign_and_get(&path, &json!({ "mchid": self.cfg.mchid })).await
+        }
+    }
+
+    /// 按商户订单号查询订单
+    ///
+    /// 商户号作为查询参数参与签名：直连模式为 mchid，服务商模式为 sp_mchid 与 sub_mchid。
+    pub async fn query_by_out_trade_no(&self, out_trade_no: &str) -> Result<Value, PayError> {
+        let out_trade_no = urlencoding::encode(out_trade_no);
+        if let Mode::Service = self.mode {
+            let sub_mchid = self
+                .cfg
+                .sub_mchid
+                .as_deref()
+                .ok_or_else(|| PayError::Other("sub_mchid required in service mode".into()))?;
+            let path = format!("/v3/pay/partner/transactions/out-trade-no/{}", out_trade_no);
+            self.sign_and_get(
+                &path,
+                &json!({ "sp_mchid": self.cfg.mchid, "sub_mchid": sub_mchid }),
             )
-        };
-        let url = self.endpoint(&url);
-        let resp = self.sign_and_post("GET", &url, &params).await?;
-        Ok(resp)
+            .await
+        } else {
+            let path = format!("/v3/pay/transactions/out-trade-no/{}", out_trade_no);
+            self.sign_and_get(&path, &json!({ "mchid": self.cfg.mchid })).await
+        }
     }
 
+    /// 关闭订单，成功时微信返回 204，结果为 Value::Null
     pub async fn close(&self, mut params: Value) -> Result<Value, PayError> {
         // 构建符合服务商模式的参数
         params = self.build_service_params(params);
@@ -326,7 +462,7 @@ impl WechatClient {
         } else {
             "/v3/refund/domestic/refunds"
         };
-        let url = self.endpoint(&url);
+        let url = self.endpoint(url);
         if let Some(obj) = order.as_object_mut() {
             obj.remove("sub_appid");
             obj.remove("sp_mchid");
@@ -336,51 +472,21 @@ impl WechatClient {
         Ok(resp)
     }
 
-    pub async fn query_refund(&self, mut params: Value) -> Result<Value, PayError> {
-        // 构建符合服务商模式的参数
-        params = self.build_service_params(params);
-
-        // 使用服务商模式URL
-        let url = if let Mode::Service = self.mode {
-            "/v3/refund/domestic/refunds/{out_refund_no}".replace(
-                "{out_refund_no}",
-                params
-                    .get("out_refund_no")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or(""),
-            )
-        } else {
-            "/v3/refund/domestic/refunds/{out_refund_no}".replace(
-                "{out_refund_no}",
-                params
-                    .get("out_refund_no")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or(""),
-            )
-        };
-        let url = self.endpoint(&url);
-        if let Some(obj) = params.as_object_mut() {
-            obj.remove("sub_appid");
-            obj.remove("sp_mchid");
-            obj.remove("sp_appid");
-        }
-        let resp = self.sign_and_post("GET", &url, &params).await?;
-        Ok(resp)
-    }
-
-    pub async fn transfer(&self, mut order: Value) -> Result<Value, PayError> {
-        // 构建符合服务商模式的参数
-        order = self.build_service_params(order);
-
-        // 使用服务商模式URL
-        let url = if let Mode::Service = self.mode {
-            "/v3/transfer/batches"
-        } else {
-            "/v3/transfer/batches"
-        };
-        let url = self.endpoint(&url);
-        let resp = self.sign_and_post("POST", &url, &order).await?;
-        Ok(resp)
+    /// 查询单笔退款，服务商模式带 sub_mchid 查询参数
+    pub async fn query_refund(&self, params: Value) -> Result<Value, PayError> {
+        let out_refund_no = params
+            .get("out_refund_no")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        let sub_mchid = params
+            .get("sub_mchid")
+            .and_then(|v| v.as_str())
+            .or_else(|| self.service_sub_mchid());
+        let path = format!(
+            "/v3/refund/domestic/refunds/{}",
+            urlencoding::encode(out_refund_no)
+        );
+        self.sign_and_get(&path, &json!({ "sub_mchid": sub_mchid })).await
     }
 
     pub async fn refresh_platform_certs(&self) -> Result<(), PayError> {
@@ -391,81 +497,294 @@ impl WechatClient {
         Ok(())
     }
 
+    /// 恢复使用主商户证书签名，返回此前是否已切换到备用证书
+    ///
+    /// 切换在 CUT_OVER_TTL（24 小时）后自动失效；主证书问题排除后可调用本方法立即切回。
+    pub fn revert_signing_identity(&self) -> bool {
+        revert_to_primary(&self.cfg)
+    }
+
+    /// 启动后台任务，每隔 interval 刷新平台证书并记录证书轮换；需在 tokio 运行时内调用
+    ///
+    /// 刷新失败时保留旧证书，下个周期重试；丢弃返回的 JoinHandle 不会停止任务，需 abort 停止。
+    pub fn start_cert_refresher(&self, interval: Duration) -> JoinHandle<()> {
+        let certs = self.certs.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                let before: HashSet<String> = certs.map.load().keys().cloned().collect();
+                if let Err(e) = certs.refresh().await {
+                    tracing::warn!("platform cert refresh failed: {}", e);
+                    continue;
+                }
+                let after: HashSet<String> = certs.map.load().keys().cloned().collect();
+                for serial in after.difference(&before) {
+                    tracing::info!("platform cert added: {}", serial);
+                }
+                for serial in before.difference(&after) {
+                    tracing::info!("platform cert removed: {}", serial);
+                }
+            }
+        })
+    }
+
+    /// 生成 APIv3 请求的 Authorization 头
+    pub(crate) fn authorization(&self, method: &str, url: &str, body_str: &str) -> Result<String, PayError> {
+        let timestamp = now_ts();
+        let nonce = gen_nonce(32);
+        let path = path_and_query(url)?;
+
+        // 预分配签名串，避免多次格式化
+        let mut sign_str = String::with_capacity(
+            method.len() + path.len() + timestamp.len() + nonce.len() + body_str.len() + 5,
+        );
+        for part in [method, path, &timestamp, &nonce, body_str] {
+            sign_str.push_str(part);
+            sign_str.push('\n');
+        }
+        let (serial_no, signature) = sign_with_serial(&self.cfg, &sign_str)?;
+
+        // 服务商模式使用服务商商户号
+        let schema = auth_schema(&self.cfg);
+        let mut auth = String::with_capacity(160 + signature.len());
+        auth.push_str(schema);
+        auth.push_str(" mchid=\"");
+        auth.push_str(&self.cfg.mchid);
+        auth.push_str("\",nonce_str=\"");
+        auth.push_str(&nonce);
+        auth.push_str("\",timestamp=\"");
+        auth.push_str(&timestamp);
+        auth.push_str("\",serial_no=\"");
+        auth.push_str(&serial_no);
+        auth.push_str("\",signature=\"");
+        auth.push_str(&signature);
+        auth.push('"');
+        Ok(auth)
+    }
+
+    /// 签名 GET 下载文件（账单、电子回单等），返回原始字节
+    /// download_url 的响应不是 JSON，不能走 sign_and_post
+    pub async fn download(&self, url: &str) -> Result<Vec<u8>, PayError> {
+        tracing::info!("download: url={}", url);
+        let auth = self.authorization("GET", url, "")?;
+        let resp = self
+            .http
+            .get(url)
+            .header("Authorization", auth)
+            .header("User-Agent", "rust_pay_wf")
+            .send()
+            .await?;
+        let status = resp.status();
+        let bytes = resp.bytes().await?;
+        if !status.is_success() {
+            return Err(PayError::Other(format!(
+                "download failed: {} - {}",
+                status,
+                String::from_utf8_lossy(&bytes)
+            )));
+        }
+        Ok(bytes.to_vec())
+    }
+
+    /// 仅签名不发送：生成 Authorization 等请求头与请求体
+    ///
+    /// body 中含平台证书加密的敏感字段时传入 wechatpay_serial。
+    pub fn build_signed_request(
+        &self,
+        method: &str,
+        url: &str,
+        body: &Value,
+        wechatpay_serial: Option<&str>,
+    ) -> Result<SignedRequest, PayError> {
+        let method = method.to_ascii_uppercase();
+        let body = request_body(&method, body);
+        let mut headers = vec![
+            ("Authorization".to_string(), self.authorization(&method, url, &body)?),
+            ("Accept".to_string(), "application/json".to_string()),
+            ("User-Agent".to_string(), "rust_pay_wf".to_string()),
+        ];
+        if !body.is_empty() {
+            headers.push(("Content-Type".to_string(), "application/json".to_string()));
+        }
+        if let Some(serial) = wechatpay_serial {
+            headers.push(("Wechatpay-Serial".to_string(), serial.to_string()));
+        }
+        Ok(SignedRequest {
+            method,
+            url: url.to_string(),
+            headers,
+            body,
+        })
+    }
+
+    /// 拼接 API 完整地址（含服务商模式路径转换），配合 build_signed_request 使用
+    pub fn api_url(&self, path: &str) -> String {
+        self.get_service_url(path)
+    }
+
+    /// 拼接带查询参数的完整地址；query 为 JSON 对象，值 URL 编码，null 忽略
+    ///
+    /// 查询串是 URL 的一部分，会进入签名串。
+    pub fn url_with_query(&self, path: &str, query: &Value) -> String {
+        let pairs: Vec<String> = query
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter_map(|(k, v)| {
+                let v = match v {
+                    Value::Null => return None,
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                Some(format!("{}={}", k, urlencoding::encode(&v)))
+            })
+            .collect();
+        let mut url = self.endpoint(path);
+        if !pairs.is_empty() {
+            url.push(if path.contains('?') { '&' } else { '?' });
+            url.push_str(&pairs.join("&"));
+        }
+        url
+    }
+
+    /// 带查询参数的 GET 请求
+    pub async fn sign_and_get(&self, path: &str, query: &Value) -> Result<Value, PayError> {
+        let url = self.url_with_query(path, query);
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
     pub async fn sign_and_post(
         &self,
         method: &str,
         url: &str,
         body: &Value,
     ) -> Result<Value, PayError> {
-        let body_str = if method == "GET" {
-            "".to_string()
-        } else {
-            body.to_string()
-        };
+        self.sign_and_post_with_serial(method, url, body, None).await
+    }
+
+    /// 同 sign_and_post，body 中含平台证书加密的敏感字段时需带上 Wechatpay-Serial 头
+    pub async fn sign_and_post_with_serial(
+        &self,
+        method: &str,
+        url: &str,
+        body: &Value,
+        wechatpay_serial: Option<&str>,
+    ) -> Result<Value, PayError> {
+        self.sign_and_send(method, url, body, wechatpay_serial)
+            .await
+            .map(|resp| resp.body)
+    }
+
+    /// 同 sign_and_post_with_serial，返回状态码、应答头与 Request-ID
+    ///
+    /// 请求失败时错误信息中同样带有 Request-ID。
+    pub async fn sign_and_send(
+        &self,
+        method: &str,
+        url: &str,
+        body: &Value,
+        wechatpay_serial: Option<&str>,
+    ) -> Result<ApiResponse<Value>, PayError> {
+        let method = method.to_ascii_uppercase();
+        let http_method = Method::from_bytes(method.as_bytes())
+            .map_err(|_| PayError::Other(format!("unsupported method: {}", method)))?;
+        let method = method.as_str();
+        self.ensure_sandbox_target()?;
+        let body_str = request_body(method, body);
         tracing::info!(
             "sign_and_post: method={}, url={}, body={}",
             method, url, body_str
         );
-        let timestamp = now_ts();
-        let nonce = gen_nonce(32);
-        let parsed = Url::parse(url).map_err(|e| PayError::Other(format!("parse url: {}", e)))?;
-        let path = if let Some(query) = parsed.query() {
-            format!("{}?{}", parsed.path(), query)
-        } else {
-            parsed.path().to_string()
+        let _permit = match &self.quota {
+            Some(q) => Some(q.acquire(&self.quota_key(url, body)).await),
+            None => None,
         };
-        let sign_str = format!(
-            "{}\n{}\n{}\n{}\n{}\n",
-            method, path, timestamp, nonce, body_str
-        );
-        let signature = rsa_sign_sha256_pem(&self.cfg.private_key_pem, &sign_str)
-            .map_err(|e| PayError::Crypto(format!("{}", e)))?;
-
-        // 服务商模式使用服务商商户号
-        let mchid = self.cfg.mchid.clone();
-
-        let auth = format!(
-            r#"WECHATPAY2-SHA256-RSA2048 mchid="{mchid}",nonce_str="{nonce}",timestamp="{ts}",serial_no="{serial}",signature="{sig}""#,
-            mchid = mchid,
-            nonce = nonce,
-            ts = timestamp,
-            serial = self.cfg.serial_no,
-            sig = signature
-        );
         let client = &self.http;
-        let send_req = || async {
-            let mut req = match method {
-                "GET" => client.get(url),
-                "POST" => client.post(url),
-                _ => {
-                    return Err(PayError::Other(format!("unsupported method: {}", method)));
+        // 401 且提示证书序列号或证书无效时重试无意义；配置了备用证书时切换后重发一次
+        let unauthorized = AtomicBool::new(false);
+        loop {
+            // 签名串只含路径，切换域名不影响签名
+            let host = failover::active_host(&self.cfg);
+            let url = match url.strip_prefix(self.base_url.as_str()) {
+                Some(path) if host != self.base_url => format!("{}{}", host, path),
+                _ => url.to_string(),
+            };
+            let url = url.as_str();
+            let auth = self.authorization(method, url, &body_str)?;
+            let send_req = || async {
+                let mut req = client
+                    .request(http_method.clone(), url)
+                    .header("Authorization", auth.clone())
+                    .header("Accept", "application/json")
+                    .header("User-Agent", "rust_pay_wf");
+                if let Some(serial) = wechatpay_serial {
+                    req = req.header("Wechatpay-Serial", serial);
                 }
+                if !body_str.is_empty() {
+                    req = req
+                        .header("Content-Type", "application/json")
+                        .body(body_str.clone());
+                }
+                let resp = req.send().await?;
+                let status = resp.status();
+                let headers = header_map(resp.headers());
+                let request_id = headers.get("request-id").cloned();
+                let text = resp.text().await?;
+                if !status.is_success() {
+                    if status == StatusCode::UNAUTHORIZED && serial_rejected(&text) {
+                        unauthorized.store(true, Ordering::Relaxed);
+                    }
+                    return Err(wechat_error(status, &text, request_id.as_deref()));
+                }
+                Ok(ApiResponse {
+                    status: status.as_u16(),
+                    headers,
+                    request_id,
+                    body: json_or_null(status, &text)?,
+                })
             };
-            req = req
-                .header("Authorization", auth.clone())
-                .header("Accept", "application/json")
-                .header("User-Agent", "rust_pay_wf");
-            if method == "POST" {
-                req = req
-                    .header("Content-Type", "application/json")
-                    .body(body_str.clone());
+            let result = crate::utils::retry_async_if(
+                self.max_retries,
+                // 明确拒绝的 4xx 重试无意义
+                |e| match e {
+                    PayError::Wechat { .. } if e.retry_class() == RetryClass::Terminal => false,
+                    _ => !unauthorized.load(Ordering::Relaxed),
+                },
+                send_req,
+            )
+            .await;
+            match &result {
+                Ok(_) => failover::report_success(&self.cfg, host),
+                Err(e) if failover::report_failure(&self.cfg, host, e) => continue,
+                Err(_) => {}
             }
-            let resp = req.send().await?;
-            let status = resp.status();
-            let text = resp.text().await?;
-            if !status.is_success() {
-                return Err(PayError::Other(format!(
-                    "HTTP request failed: {} - {}",
-                    status, text
-                )));
+            if result.is_err()
+                && unauthorized.swap(false, Ordering::Relaxed)
+                && cut_over_to_secondary(&self.cfg)
+            {
+                continue;
             }
-            let v: Value = serde_json::from_str(&text)?;
-            Ok(v)
-        };
-        let v = crate::utils::retry_async(self.max_retries, send_req)
-            .await
-            .map_err(|e| PayError::Other(format!("HTTP request failed:{}", e)))?;
-        Ok(v)
+            return result.map_err(|e| match e {
+                PayError::Wechat { .. } => e,
+                e => PayError::Other(format!("HTTP request failed:{}", e)),
+            });
+        }
+    }
+
+    // 配额按商户区分：服务商复用同一客户端时取本次请求的 sub_mchid（请求体或查询串），否则取配置
+    fn quota_key(&self, url: &str, body: &Value) -> String {
+        body.get("sub_mchid")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .or_else(|| {
+                let url = url::Url::parse(url).ok()?;
+                let sub_mchid = url.query_pairs().find(|(k, _)| k == "sub_mchid")?.1;
+                Some(sub_mchid.into_owned())
+            })
+            .or_else(|| self.cfg.sub_mchid.clone())
+            .unwrap_or_else(|| self.cfg.mchid.clone())
     }
 
     /// 处理回调
@@ -481,57 +800,14 @@ impl WechatClient {
     /// 添加分账接收方
     /// 文档：https://pay.weixin.qq.com/doc/v3/partner/4012477758 [citation:1]
     /// 文档：https://pay.weixin.qq.com/doc/v3/merchant/4012528995 [citation:4]
-    pub async fn add_profitsharing_receiver(&self, mut order: Value) -> Result<Value, PayError> {
-        // 1. 验证必要参数 - 使用 get() 而不是 get_mut()
-        let receiver_type = order
-            .get("type")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| PayError::Other("Missing required field: 'type'".into()))?
-            .to_string(); // 转换为 String，获取所有权
-
-        let account = order
-            .get("account")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| PayError::Other("Missing required field: 'account'".into()))?
-            .to_string(); // 转换为 String，获取所有权
-
-        // 2. 记录请求参数
-        tracing::info!(
-            "添加分账接收方 - 类型: {}, 账号: {}",
-            receiver_type, account
-        );
-
-        // 3. 构建符合服务商模式的参数
-        order = self.build_service_params(order);
-
-        // 4. 获取URL
-        let url = self.get_service_url("/v3/profitsharing/receivers/add");
-
-        // 5. 处理接收方名称加密（如果需要）
-        // 注意：传递 receiver_type 的引用，而不是 String
-        let wechatpay_serial = self
-            .process_receiver_name_encryption(
-                &receiver_type, // 传递引用
-                &mut order,
-            )
-            .await?;
-
-        // 6. 发送请求
-        self.send_profitsharing_request("POST", &url, &order, wechatpay_serial)
-            .await
+    pub async fn add_profitsharing_receiver(&self, order: Value) -> Result<Value, PayError> {
+        self.add_receiver(order).await
     }
 
     /// 请求分账
     /// 文档：https://pay.weixin.qq.com/doc/v3/partner/4012087888 [citation:2]
-    pub async fn profitsharing(&self, mut order: Value) -> Result<Value, PayError> {
-        // 构建服务商参数
-        order = self.build_service_params(order);
-
-        let url = self.get_service_url("/v3/profitsharing/orders");
-
-        // 发送请求
-        let resp = self.sign_and_post("POST", &url, &order).await?;
-        Ok(resp)
+    pub async fn profitsharing(&self, order: Value) -> Result<Value, PayError> {
+        self.create_profitsharing_order(order).await
     }
 
     /// 查询分账结果
@@ -540,26 +816,14 @@ impl WechatClient {
         out_order_no: &str,
         transaction_id: Option<&str>,
     ) -> Result<Value, PayError> {
-        let old_url = format!("/v3/profitsharing/orders/{}", out_order_no);
-        let mut full_url = self.get_service_url(&*old_url);
-
-        if let Some(tid) = transaction_id {
-            full_url = format!("{}?transaction_id={}", full_url, tid);
-        }
-        // 发送GET请求
-        let resp = self.sign_and_post("GET", &full_url, &json!({})).await?;
-        Ok(resp)
+        self.query_profitsharing_order(out_order_no, transaction_id.unwrap_or(""))
+            .await
     }
 
     /// 解冻剩余资金（完结分账）
     /// 文档：https://pay.weixin.qq.com/doc/v3/partner/4012466860 [citation:3]
-    pub async fn unfreeze_profitsharing(&self, mut order: Value) -> Result<Value, PayError> {
-        // 构建服务商参数
-        order = self.build_service_params(order);
-        let url = self.get_service_url("/v3/profitsharing/orders/unfreeze");
-        // 发送请求
-        let resp = self.sign_and_post("POST", &url, &order).await?;
-        Ok(resp)
+    pub async fn unfreeze_profitsharing(&self, order: Value) -> Result<Value, PayError> {
+        self.finish_profitsharing_order(order).await
     }
 
     /// 请求分账回退（用于退款场景）
@@ -572,57 +836,41 @@ impl WechatClient {
         Ok(resp)
     }
 
-    /// 处理接收方名称加密
-    async fn process_receiver_name_encryption(
+    /// 使用平台证书公钥加密敏感字段（姓名、证件号、手机号、银行账号等）
+    /// 返回 (平台证书序列号, 密文)，请求时需将序列号放到 Wechatpay-Serial 头
+    pub async fn encrypt_sensitive(&self, plaintext: &str) -> Result<(String, String), PayError> {
+        let (cert_sn, public_key_pem) = self.get_platform_certificate_info().await?;
+        let cipher = crate::utils::rsa_encrypt_oaep_with_public_key_pem(&public_key_pem, plaintext)
+            .map_err(|e| PayError::Crypto(format!("encrypt sensitive field: {}", e)))?;
+        Ok((cert_sn, cipher))
+    }
+
+    /// 按 JSON Pointer 加密 body 中存在的字符串字段，返回使用的平台证书序列号
+    /// 没有任何字段需要加密时返回 None
+    pub(crate) async fn encrypt_fields(
         &self,
-        receiver_type: &str,
-        order: &mut Value,
+        body: &mut Value,
+        pointers: &[&str],
     ) -> Result<Option<String>, PayError> {
-        // 判断是否需要加密名称
-        let need_encryption = matches!(receiver_type, "MERCHANT_ID")
-            || (order.get("name").is_some()
-                && matches!(receiver_type, "PERSONAL_OPENID" | "PERSONAL_SUB_OPENID"));
-
-        if !need_encryption {
-            return Ok(None);
-        }
-
-        // 关键修复：先将 name 值提取出来，再修改 order
-        let name_str = if let Some(name_value) = order.get("name") {
-            name_value
-                .as_str()
-                .ok_or_else(|| PayError::Other("'name' must be a string".into()))?
-        } else {
-            return Err(PayError::Other(
-                "'name' field is required for this receiver type".into(),
-            ));
-        };
-
-        // 获取平台证书信息
-        let (cert_sn, public_key_pem) = self
-            .get_platform_certificate_info()
-            .await
-            .map_err(|e| PayError::Other(format!("Failed to get platform certificate: {}", e)))?;
-
-        tracing::info!("🔐 使用平台证书加密名称 - 序列号: {}", cert_sn);
-        tracing::info!("📝 原始名称: {}", name_str);
-
-        // 加密名称
-        let encrypted_name =
-            crate::utils::rsa_encrypt_oaep_with_public_key_pem(&public_key_pem, name_str)
-                .map_err(|e| PayError::Crypto(format!("Failed to encrypt receiver name: {}", e)))?;
-
-        tracing::info!("🔒 加密后名称(Base64): {}", encrypted_name);
-
-        // 现在安全地修改 order
-        if let Some(name_field) = order.get_mut("name") {
-            *name_field = json!(encrypted_name);
+        let mut serial = None;
+        for pointer in pointers {
+            let plain = match body.pointer(pointer).and_then(|v| v.as_str()) {
+                Some(v) if !v.is_empty() => v.to_string(),
+                _ => continue,
+            };
+            let (cert_sn, cipher) = self.encrypt_sensitive(&plain).await?;
+            if let Some(field) = body.pointer_mut(pointer) {
+                *field = json!(cipher);
+            }
+            serial = Some(cert_sn);
         }
-
-        Ok(Some(cert_sn))
+        Ok(serial)
     }
 
     async fn get_platform_certificate_info(&self) -> Result<(String, String), PayError> {
+        if let Err(e) = self.certs.refresh_if_expiring().await {
+            tracing::warn!("proactive platform cert refresh failed: {}", e);
+        }
         let mut certs = self.certs.get_first_cert();
 
         // 2️⃣ 如果没有，就尝试 refresh 一次再取
@@ -644,109 +892,89 @@ impl WechatClient {
 
         Ok((cert_sn, pub_pem))
     }
+}
 
-    /// 发送分账请求（完整实现）
-    async fn send_profitsharing_request(
-        &self,
-        method: &str,
-        url: &str,
-        body: &Value,
-        wechatpay_serial: Option<String>,
-    ) -> Result<Value, PayError> {
-        // 1. 准备请求体和路径
-        let body_str = body.to_string();
-        let parsed_url =
-            Url::parse(url).map_err(|e| PayError::Other(format!("Failed to parse URL: {}", e)))?;
-
-        let path_and_query = if let Some(query) = parsed_url.query() {
-            format!("{}?{}", parsed_url.path(), query)
-        } else {
-            parsed_url.path().to_string()
-        };
-
-        // 2. 生成签名所需参数
-        let timestamp = now_ts();
-        let nonce = gen_nonce(32);
-
-        // 3. 生成待签名字符串（关键步骤）
-        let sign_str = format!(
-            "{}\n{}\n{}\n{}\n{}\n",
-            method, path_and_query, timestamp, nonce, body_str
-        );
-
-
-        // 4. 使用商户私钥进行签名（注意：这里是签名，不是加密）
-        let signature = rsa_sign_sha256_pem(&self.cfg.private_key_pem, &sign_str)
-            .map_err(|e| PayError::Crypto(format!("Failed to sign request: {}", e)))?;
-
-        // 5. 构建Authorization头
-        let auth_header = format!(
-            r#"WECHATPAY2-SHA256-RSA2048 mchid="{}",nonce_str="{}",timestamp="{}",serial_no="{}",signature="{}""#,
-            self.cfg.mchid, nonce, timestamp, self.cfg.serial_no, signature
-        );
-
-        // 6. 构建HTTP请求
-        let client = &self.http;
-        let mut request_builder = match method {
-            "GET" => client.get(url),
-            "POST" => client.post(url),
-            _ => {
-                return Err(PayError::Other(format!(
-                    "Unsupported HTTP method: {}",
-                    method
-                )))
-            }
-        };
-
-        // 7. 设置请求头
-        request_builder = request_builder
-            .header("Authorization", auth_header)
-            .header("Accept", "application/json")
-            .header("User-Agent", "rust_pay_wf")
-            .header("Content-Type", "application/json");
-
-        // 8. 添加Wechatpay-Serial头（如果提供了证书序列号）
-        if let Some(serial) = wechatpay_serial {
-            request_builder = request_builder.header("Wechatpay-Serial", serial.clone());
-            tracing::info!("已设置Wechatpay-Serial头: {}", serial);
-        }
-
-        // 9. 设置请求体（POST请求）
-        if method == "POST" {
-            request_builder = request_builder.body(body_str.clone());
-            tracing::info!("请求体: {}", body_str);
-        }
+/// 取 URL 的 path 与 query 部分（签名串使用），不做完整 URL 解析
+fn path_and_query(url: &str) -> Result<&str, PayError> {
+    let rest = url
+        .split_once("://")
+        .map(|(_, rest)| rest)
+        .ok_or_else(|| PayError::Other(format!("parse url: invalid url {}", url)))?;
+    let path = rest.find('/').map(|i| &rest[i..]).unwrap_or("/");
+    Ok(path.split('#').next().unwrap_or(path))
+}
 
-        // 10. 发送请求
-        let response = request_builder
-            .send()
-            .await
-            .map_err(|e| PayError::Other(format!("HTTP request failed: {}", e)))?;
+/// 解析成功应答：关单、删除等接口返回 204 或空应答体，此时为 Value::Null
+pub(crate) fn json_or_null(status: StatusCode, text: &str) -> Result<Value, PayError> {
+    if status == StatusCode::NO_CONTENT || text.trim().is_empty() {
+        return Ok(Value::Null);
+    }
+    Ok(serde_json::from_str(text)?)
+}
 
-        let status_code = response.status();
-        let response_text = response
-            .text()
-            .await
-            .map_err(|e| PayError::Other(format!("Failed to read response: {}", e)))?;
+/// 非 2xx 应答转为 PayError::Wechat；应答体不是 JSON 时原文作为 message
+pub(crate) fn wechat_error(status: StatusCode, text: &str, request_id: Option<&str>) -> PayError {
+    let body: Value = serde_json::from_str(text).unwrap_or(Value::Null);
+    let field = |k: &str| body.get(k).and_then(|v| v.as_str()).map(str::to_string);
+    PayError::Wechat {
+        status: status.as_u16(),
+        code: field("code").unwrap_or_default(),
+        message: field("message").unwrap_or_else(|| text.to_string()),
+        request_id: request_id.unwrap_or("-").to_string(),
+    }
+}
 
-        tracing::info!("响应状态: {}, 响应体: {}", status_code, response_text);
+// 应答头转为小写名称的映射；同名头以逗号合并
+pub(crate) fn header_map(headers: &reqwest::header::HeaderMap) -> HashMap<String, String> {
+    let mut map: HashMap<String, String> = HashMap::new();
+    for (name, value) in headers {
+        let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+        map.entry(name.as_str().to_string())
+            .and_modify(|v| {
+                v.push_str(", ");
+                v.push_str(&value);
+            })
+            .or_insert(value);
+    }
+    map
+}
 
-        // 11. 处理响应
-        if !status_code.is_success() {
-            let error_summary = if !response_text.is_empty() {
-                format!("HTTP {} - {}", status_code, response_text)
-            } else {
-                format!("HTTP {}", status_code)
-            };
+// 签名与发送使用的请求体：GET / HEAD 及无请求体（Value::Null）时为空串
+fn request_body(method: &str, body: &Value) -> String {
+    if matches!(method, "GET" | "HEAD") || body.is_null() {
+        String::new()
+    } else {
+        body.to_string()
+    }
+}
 
-            return Err(PayError::Other(format!(
-                "Request failed: {}",
-                error_summary
-            )));
+// 商户 API 证书与私钥拼成 PEM，供 reqwest Identity 使用
+fn client_identity_pem(cfg: &WechatConfig) -> Result<Vec<u8>, PayError> {
+    let crypto = |e: openssl::error::ErrorStack| PayError::Crypto(format!("client cert: {}", e));
+    let (cert, key) = if let Some(path) = &cfg.client_cert_p12_path {
+        let der = std::fs::read(path)?;
+        let parsed = Pkcs12::from_der(&der)
+            .and_then(|p| p.parse2(&cfg.mchid))
+            .map_err(crypto)?;
+        match (parsed.cert, parsed.pkey) {
+            (Some(cert), Some(key)) => (cert, key),
+            _ => return Err(PayError::Crypto("client cert: p12 missing cert or key".into())),
         }
-
-        // 12. 解析JSON响应
-        serde_json::from_str(&response_text)
-            .map_err(|e| PayError::Other(format!("Failed to parse JSON response: {}", e)))
-    }
+    } else if let Some(source) = &cfg.client_cert_pem {
+        let pem = if source.contains("-----BEGIN") {
+            source.as_bytes().to_vec()
+        } else {
+            std::fs::read(source)?
+        };
+        let key = parse_private_key(&cfg.private_key_pem, cfg.private_key_passphrase.as_deref())
+            .map_err(|e| PayError::Crypto(format!("client key: {}", e)))?;
+        (X509::from_pem(&pem).map_err(crypto)?, key)
+    } else {
+        return Err(PayError::Other(
+            "client_cert_pem or client_cert_p12_path required for this API".into(),
+        ));
+    };
+    let mut pem = cert.to_pem().map_err(crypto)?;
+    pem.extend(key.private_key_to_pem_pkcs8().map_err(crypto)?);
+    Ok(pem)
 }
@@ -0,0 +1,109 @@
+use crate::config::Mode;
+use crate::errors::PayError;
+use crate::wechat::client::WechatClient;
+use serde_json::{json, Value};
+
+impl WechatClient {
+    /// 合单 JSAPI / 小程序下单，返回调起支付参数
+    pub async fn combine_jsapi(&self, order: Value) -> Result<Value, PayError> {
+        let order = self.build_combine_order(order);
+        let url = self.endpoint("/v3/combine-transactions/jsapi");
+        let resp = self.sign_and_post("POST", &url, &order).await?;
+        match resp.get("prepay_id").and_then(|v| v.as_str()) {
+            Some(prepay_id) => {
+                let appid = order["combine_appid"].as_str().unwrap_or("");
+                self.jsapi_pay_params(appid, prepay_id)
+            }
+            None => Ok(resp),
+        }
+    }
+
+    /// 合单 APP 下单，返回调起支付参数
+    pub async fn combine_app(&self, order: Value) -> Result<Value, PayError> {
+        let order = self.build_combine_order(order);
+        let url = self.endpoint("/v3/combine-transactions/app");
+        let resp = self.sign_and_post("POST", &url, &order).await?;
+        match resp.get("prepay_id").and_then(|v| v.as_str()) {
+            Some(prepay_id) => {
+                let appid = order["combine_appid"].as_str().unwrap_or("");
+                let partnerid = order["combine_mchid"].as_str().unwrap_or("");
+                self.app_pay_params(appid, partnerid, prepay_id)
+            }
+            None => Ok(resp),
+        }
+    }
+
+    /// 合单 H5 下单，返回 h5_url
+    pub async fn combine_h5(&self, order: Value) -> Result<Value, PayError> {
+        let order = self.build_combine_order(order);
+        let url = self.endpoint("/v3/combine-transactions/h5");
+        self.sign_and_post("POST", &url, &order).await
+    }
+
+    /// 合单 Native 下单，返回 code_url
+    pub async fn combine_native(&self, order: Value) -> Result<Value, PayError> {
+        let order = self.build_combine_order(order);
+        let url = self.endpoint("/v3/combine-transactions/native");
+        self.sign_and_post("POST", &url, &order).await
+    }
+
+    /// 合单查询
+    pub async fn combine_query(&self, combine_out_trade_no: &str) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/combine-transactions/out-trade-no/{}",
+            urlencoding::encode(combine_out_trade_no)
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 合单关单，sub_orders 为需关闭的子单（mchid、out_trade_no，服务商模式含 sub_mchid）
+    pub async fn combine_close(
+        &self,
+        combine_out_trade_no: &str,
+        sub_orders: Value,
+    ) -> Result<Value, PayError> {
+        let body = self.build_combine_order(json!({ "sub_orders": sub_orders }));
+        let body = json!({
+            "combine_appid": body["combine_appid"],
+            "sub_orders": body["sub_orders"],
+        });
+        let url = self.endpoint(&format!(
+            "/v3/combine-transactions/out-trade-no/{}/close",
+            urlencoding::encode(combine_out_trade_no)
+        ));
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    // 补齐合单发起方与子单商户号：
+    // 直连模式子单 mchid 为本商户；服务商模式子单 mchid 为服务商，sub_mchid 缺省取配置
+    fn build_combine_order(&self, mut order: Value) -> Value {
+        if order.get("combine_appid").is_none() {
+            if let Some(appid) = &self.cfg.appid {
+                order["combine_appid"] = json!(appid);
+            }
+        }
+        if order.get("combine_mchid").is_none() {
+            order["combine_mchid"] = json!(self.cfg.mchid);
+        }
+        if order.get("notify_url").is_none() {
+            if let Some(notify_url) = &self.cfg.notify_url {
+                order["notify_url"] = json!(notify_url);
+            }
+        }
+        if let Some(Value::Array(sub_orders)) = order.get_mut("sub_orders") {
+            for sub in sub_orders.iter_mut() {
+                if sub.get("mchid").is_none() {
+                    sub["mchid"] = json!(self.cfg.mchid);
+                }
+                if let Mode::Service = self.mode {
+                    if sub.get("sub_mchid").is_none() {
+                        if let Some(sub_mchid) = &self.cfg.sub_mchid {
+                            sub["sub_mchid"] = json!(sub_mchid);
+                        }
+                    }
+                }
+            }
+        }
+        order
+    }
+}
@@ -0,0 +1,150 @@
+use crate::errors::PayError;
+use crate::wechat::client::WechatClient;
+use crate::wechat::failover;
+use crate::wechat::notify::{ComplaintNotice, WechatNotify};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+impl WechatClient {
+    /// 创建投诉通知回调地址
+    pub async fn create_complaint_notification(&self, url: &str) -> Result<Value, PayError> {
+        let endpoint = self.endpoint("/v3/merchant-service/complaint-notifications");
+        self.sign_and_post("POST", &endpoint, &json!({ "url": url }))
+            .await
+    }
+
+    /// 查询投诉通知回调地址
+    pub async fn query_complaint_notification(&self) -> Result<Value, PayError> {
+        let endpoint = self.endpoint("/v3/merchant-service/complaint-notifications");
+        self.sign_and_post("GET", &endpoint, &json!({})).await
+    }
+
+    /// 更新投诉通知回调地址
+    pub async fn update_complaint_notification(&self, url: &str) -> Result<Value, PayError> {
+        let endpoint = self.endpoint("/v3/merchant-service/complaint-notifications");
+        self.sign_and_post("PUT", &endpoint, &json!({ "url": url }))
+            .await
+    }
+
+    /// 删除投诉通知回调地址
+    pub async fn delete_complaint_notification(&self) -> Result<Value, PayError> {
+        let endpoint = self.endpoint("/v3/merchant-service/complaint-notifications");
+        self.sign_and_post("DELETE", &endpoint, &Value::Null).await
+    }
+
+    /// 分页查询投诉单列表，日期格式 yyyy-MM-dd，跨度不超过 30 天；服务商可按被诉商户号过滤
+    pub async fn list_complaints(
+        &self,
+        begin_date: &str,
+        end_date: &str,
+        offset: u32,
+        limit: u32,
+        complainted_mchid: Option<&str>,
+    ) -> Result<Value, PayError> {
+        let mut path = format!(
+            "/v3/merchant-service/complaints-v2?limit={}&offset={}&begin_date={}&end_date={}",
+            limit, offset, begin_date, end_date
+        );
+        if let Some(mchid) = complainted_mchid {
+            path.push_str("&complainted_mchid=");
+            path.push_str(mchid);
+        }
+        let endpoint = self.endpoint(&path);
+        self.sign_and_post("GET", &endpoint, &Value::Null).await
+    }
+
+    /// 查询投诉单详情，payer_phone 为平台证书加密的密文
+    pub async fn query_complaint(&self, complaint_id: &str) -> Result<Value, PayError> {
+        let endpoint = self.endpoint(&format!(
+            "/v3/merchant-service/complaints-v2/{}",
+            complaint_id
+        ));
+        self.sign_and_post("GET", &endpoint, &Value::Null).await
+    }
+
+    /// 查询投诉单协商历史
+    pub async fn query_complaint_history(
+        &self,
+        complaint_id: &str,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Value, PayError> {
+        let endpoint = self.endpoint(&format!(
+            "/v3/merchant-service/complaints-v2/{}/negotiation-historys?limit={}&offset={}",
+            complaint_id, limit, offset
+        ));
+        self.sign_and_post("GET", &endpoint, &Value::Null).await
+    }
+
+    /// 回复用户，body 含 response_content，可选 response_images（media_id）、jump_url 等；
+    /// complainted_mchid 缺省取配置的商户号（服务商模式为子商户号）
+    pub async fn respond_complaint(&self, complaint_id: &str, body: Value) -> Result<Value, PayError> {
+        let body = self.complaint_body(body);
+        let endpoint = self.endpoint(&format!(
+            "/v3/merchant-service/complaints-v2/{}/response",
+            complaint_id
+        ));
+        self.sign_and_post("POST", &endpoint, &body).await
+    }
+
+    /// 反馈处理完成
+    pub async fn complete_complaint(&self, complaint_id: &str) -> Result<Value, PayError> {
+        let body = self.complaint_body(json!({}));
+        let endpoint = self.endpoint(&format!(
+            "/v3/merchant-service/complaints-v2/{}/complete",
+            complaint_id
+        ));
+        self.sign_and_post("POST", &endpoint, &body).await
+    }
+
+    /// 下载投诉单附件图片，参数为详情 / 协商历史中的图片 URL 或 media_id
+    ///
+    /// URL 须指向微信支付接口域名，否则返回错误。
+    pub async fn download_complaint_image(&self, media: &str) -> Result<Vec<u8>, PayError> {
+        let url = if media.contains("://") {
+            // 下载请求带商户签名，不能发往其他域名
+            if !self.is_api_url(media) {
+                return Err(PayError::Other(format!(
+                    "complaint image url is not a wechat pay host: {}",
+                    media
+                )));
+            }
+            media.to_string()
+        } else {
+            self.endpoint(&format!(
+                "/v3/merchant-service/images/{}",
+                urlencoding::encode(media)
+            ))
+        };
+        self.download(&url).await
+    }
+
+    fn is_api_url(&self, url: &str) -> bool {
+        let Ok(url) = reqwest::Url::parse(url) else {
+            return false;
+        };
+        ["https://api.mch.weixin.qq.com", self.cfg.api_host()]
+            .into_iter()
+            .chain(failover::backup_host(&self.cfg))
+            .filter_map(|host| reqwest::Url::parse(host).ok())
+            .any(|host| host.origin() == url.origin())
+    }
+
+    fn complaint_body(&self, mut body: Value) -> Value {
+        if body.get("complainted_mchid").is_none() {
+            let mchid = self.service_sub_mchid().unwrap_or(&self.cfg.mchid);
+            body["complainted_mchid"] = json!(mchid);
+        }
+        body
+    }
+
+    /// 处理投诉通知回调
+    pub async fn handle_complaint_notify(
+        &self,
+        headers: HashMap<String, String>,
+        body_str: &str,
+    ) -> Result<ComplaintNotice, PayError> {
+        let notify = WechatNotify::new(self.cfg.clone(), self.certs.clone());
+        notify.verify_complaint(&headers, body_str).await
+    }
+}
@@ -0,0 +1,182 @@
+use crate::config::{SignAlgorithm, WechatConfig};
+use crate::errors::PayError;
+use crate::utils::{
+    aes_gcm_decrypt, rsa_decrypt_oaep_with_private_key, rsa_sign_sha256_with_passphrase,
+    rsa_verify_sha256_pem, sm2_sign_sm3_with_passphrase, sm2_verify_sm3, sm4_gcm_decrypt,
+};
+use once_cell::sync::Lazy;
+use openssl::hash::{hash, MessageDigest};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+/// Authorization 头的认证类型
+pub(crate) fn auth_schema(cfg: &WechatConfig) -> &'static str {
+    match cfg.sign_algorithm {
+        SignAlgorithm::Rsa => "WECHATPAY2-SHA256-RSA2048",
+        SignAlgorithm::Sm2 => "WECHATPAY2-SM2-WITH-SM3",
+    }
+}
+
+/// 切换到备用证书后的有效期，到期后重新使用主证书（再被拒绝会再次切换）
+pub(crate) const CUT_OVER_TTL: Duration = Duration::from_secs(24 * 3600);
+
+// 主证书已被网关拒绝、改用备用证书的商户及切换时刻，键为 "商户号:主证书序列号"
+static CUT_OVER: Lazy<RwLock<HashMap<String, Instant>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+fn rotation_key(cfg: &WechatConfig) -> String {
+    format!("{}:{}", cfg.mchid, cfg.serial_no)
+}
+
+/// 当前生效的签名身份：(证书序列号, 私钥, 口令)
+fn active_identity(cfg: &WechatConfig) -> (&str, &str, Option<&str>) {
+    if let Some(secondary) = &cfg.secondary_identity {
+        let cut_over = CUT_OVER
+            .read()
+            .unwrap()
+            .get(&rotation_key(cfg))
+            .is_some_and(|since| since.elapsed() < CUT_OVER_TTL);
+        if cut_over {
+            return (
+                &secondary.serial_no,
+                &secondary.private_key_pem,
+                secondary.private_key_passphrase.as_deref(),
+            );
+        }
+    }
+    (
+        &cfg.serial_no,
+        &cfg.private_key_pem,
+        cfg.private_key_passphrase.as_deref(),
+    )
+}
+
+/// 401 应答是否表示商户证书序列号或证书本身被拒绝（而非签名串、时间戳等其他问题）
+pub(crate) fn serial_rejected(body: &str) -> bool {
+    let Ok(v) = serde_json::from_str::<serde_json::Value>(body) else {
+        return false;
+    };
+    let message = v.get("message").and_then(|m| m.as_str()).unwrap_or("");
+    v.get("code").and_then(|c| c.as_str()) == Some("SIGN_ERROR")
+        && (message.contains("序列号") || message.contains("证书"))
+}
+
+/// 主证书被网关拒绝时切换到备用证书，返回是否发生了切换
+pub(crate) fn cut_over_to_secondary(cfg: &WechatConfig) -> bool {
+    if cfg.secondary_identity.is_none() {
+        return false;
+    }
+    let mut cut_over = CUT_OVER.write().unwrap();
+    let key = rotation_key(cfg);
+    // 已切换且未过期时不重复切换；过期后主证书再被拒绝则重新计时
+    let switched = match cut_over.get(&key) {
+        Some(since) => since.elapsed() >= CUT_OVER_TTL,
+        None => true,
+    };
+    if switched {
+        cut_over.insert(key, Instant::now());
+        tracing::warn!(
+            "wechat merchant {} serial {} rejected, switch to secondary identity",
+            cfg.mchid,
+            cfg.serial_no
+        );
+    }
+    switched
+}
+
+/// 撤销切换，恢复使用主证书，返回此前是否处于切换状态
+pub(crate) fn revert_to_primary(cfg: &WechatConfig) -> bool {
+    let reverted = CUT_OVER.write().unwrap().remove(&rotation_key(cfg)).is_some();
+    if reverted {
+        tracing::info!(
+            "wechat merchant {} revert to primary serial {}",
+            cfg.mchid,
+            cfg.serial_no
+        );
+    }
+    reverted
+}
+
+/// 使用当前生效的商户私钥签名
+pub(crate) fn sign_message(cfg: &WechatConfig, message: &str) -> Result<String, PayError> {
+    sign_with_serial(cfg, message).map(|(_, signature)| signature)
+}
+
+/// 签名并返回所用证书的序列号，保证 Authorization 中的 serial_no 与签名私钥一致
+pub(crate) fn sign_with_serial(
+    cfg: &WechatConfig,
+    message: &str,
+) -> Result<(String, String), PayError> {
+    let (serial_no, private_key, passphrase) = active_identity(cfg);
+    let signed = match cfg.sign_algorithm {
+        SignAlgorithm::Rsa => rsa_sign_sha256_with_passphrase(private_key, passphrase, message),
+        SignAlgorithm::Sm2 => sm2_sign_sm3_with_passphrase(private_key, passphrase, message),
+    };
+    signed
+        .map(|signature| (serial_no.to_string(), signature))
+        .map_err(|e| PayError::Crypto(format!("{}", e)))
+}
+
+/// 用商户私钥解密平台以商户证书公钥加密的数据（如账单密钥）
+///
+/// 平台按申请请求的签名证书加密，先用当前生效的私钥；申请后恰好切换过证书时再试另一把私钥。
+pub(crate) fn decrypt_with_merchant_key(
+    cfg: &WechatConfig,
+    ciphertext_b64: &str,
+) -> anyhow::Result<Vec<u8>> {
+    let (serial_no, private_key, passphrase) = active_identity(cfg);
+    let result = rsa_decrypt_oaep_with_private_key(private_key, passphrase, ciphertext_b64);
+    match &cfg.secondary_identity {
+        Some(secondary) if result.is_err() => {
+            let (private_key, passphrase) = if serial_no == cfg.serial_no {
+                (&secondary.private_key_pem, secondary.private_key_passphrase.as_deref())
+            } else {
+                (&cfg.private_key_pem, cfg.private_key_passphrase.as_deref())
+            };
+            rsa_decrypt_oaep_with_private_key(private_key, passphrase, ciphertext_b64)
+        }
+        _ => result,
+    }
+}
+
+/// 验证平台签名，`signature_type` 取自 Wechatpay-Signature-Type 头，缺省按商户配置
+pub(crate) fn verify_signature(
+    cfg: &WechatConfig,
+    signature_type: Option<&str>,
+    public_key_pem: &str,
+    message: &str,
+    signature: &str,
+) -> anyhow::Result<bool> {
+    let sm2 = match signature_type {
+        Some(t) if !t.is_empty() => t.eq_ignore_ascii_case("WECHATPAY2-SM2-WITH-SM3"),
+        _ => cfg.sign_algorithm == SignAlgorithm::Sm2,
+    };
+    if sm2 {
+        sm2_verify_sm3(public_key_pem, message, signature)
+    } else {
+        rsa_verify_sha256_pem(public_key_pem, message, signature)
+    }
+}
+
+/// 解密回调/证书中的 resource，按 algorithm 选择 AES-256-GCM 或 SM4-GCM
+pub(crate) fn decrypt_resource(
+    cfg: &WechatConfig,
+    algorithm: &str,
+    associated_data: &str,
+    nonce: &str,
+    ciphertext: &str,
+) -> anyhow::Result<String> {
+    if algorithm.eq_ignore_ascii_case("AEAD_SM4_GCM") {
+        let key = sm4_key(&cfg.api_v3_key)?;
+        sm4_gcm_decrypt(&key, associated_data, nonce, ciphertext)
+    } else {
+        aes_gcm_decrypt(&cfg.api_v3_key, associated_data, nonce, ciphertext)
+    }
+}
+
+/// SM4 密钥为 128 位：取 APIv3 密钥 SM3 摘要的前 16 字节
+fn sm4_key(api_v3_key: &str) -> anyhow::Result<Vec<u8>> {
+    let digest = hash(MessageDigest::sm3(), api_v3_key.as_bytes())?;
+    Ok(digest[..16].to_vec())
+}
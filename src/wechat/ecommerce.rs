@@ -0,0 +1,299 @@
+//! 收付通（电商平台）接口：/v3/ecommerce/*
+//!
+//! 合单支付沿用 combine_* 方法，子单需带二级商户号 sub_mchid 与 settle_info。
+use crate::errors::PayError;
+use crate::wechat::client::WechatClient;
+use serde_json::{json, Value};
+
+/// 二级商户进件中需要平台证书加密的字段
+const ECOMMERCE_SENSITIVE_FIELDS: &[&str] = &[
+    "/id_card_info/id_card_name",
+    "/id_card_info/id_card_number",
+    "/id_card_info/id_card_address",
+    "/id_doc_info/id_doc_name",
+    "/id_doc_info/id_doc_number",
+    "/id_doc_info/id_doc_address",
+    "/account_info/account_name",
+    "/account_info/account_number",
+    "/contact_info/contact_name",
+    "/contact_info/contact_id_card_number",
+    "/contact_info/mobile_phone",
+    "/contact_info/contact_email",
+];
+
+impl WechatClient {
+    /// 二级商户进件，证件、账户与联系人敏感字段传明文，自动加密
+    pub async fn ecommerce_applyment(&self, mut applyment: Value) -> Result<Value, PayError> {
+        if applyment.get("out_request_no").is_none() {
+            return Err(PayError::Other("Missing required field: 'out_request_no'".into()));
+        }
+        let serial = self
+            .encrypt_fields(&mut applyment, ECOMMERCE_SENSITIVE_FIELDS)
+            .await?;
+        let url = self.endpoint("/v3/ecommerce/applyments/");
+        self.sign_and_post_with_serial("POST", &url, &applyment, serial.as_deref())
+            .await
+    }
+
+    /// 按申请单号查询二级商户进件状态
+    pub async fn query_ecommerce_applyment(&self, applyment_id: i64) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!("/v3/ecommerce/applyments/{}", applyment_id));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 按业务申请编号查询二级商户进件状态
+    pub async fn query_ecommerce_applyment_by_out_no(
+        &self,
+        out_request_no: &str,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/ecommerce/applyments/out-request-no/{}",
+            urlencoding::encode(out_request_no)
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 收付通申请退款，sp_appid / sub_mchid / notify_url 缺省取配置
+    pub async fn ecommerce_refund(&self, mut refund: Value) -> Result<Value, PayError> {
+        if refund.get("sp_appid").is_none() {
+            if let Some(appid) = &self.cfg.appid {
+                refund["sp_appid"] = json!(appid);
+            }
+        }
+        let mut refund = self.ecommerce_sub_mchid(refund);
+        if refund.get("notify_url").is_none() {
+            if let Some(notify_url) = &self.cfg.notify_url {
+                refund["notify_url"] = json!(notify_url);
+            }
+        }
+        let url = self.endpoint("/v3/ecommerce/refunds/apply");
+        self.sign_and_post("POST", &url, &refund).await
+    }
+
+    /// 按商户退款单号查询收付通退款
+    pub async fn query_ecommerce_refund(
+        &self,
+        sub_mchid: &str,
+        out_refund_no: &str,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/ecommerce/refunds/out-refund-no/{}?sub_mchid={}",
+            urlencoding::encode(out_refund_no),
+            sub_mchid
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 按微信退款单号查询收付通退款
+    pub async fn query_ecommerce_refund_by_id(
+        &self,
+        sub_mchid: &str,
+        refund_id: &str,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/ecommerce/refunds/id/{}?sub_mchid={}",
+            refund_id, sub_mchid
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 查询二级商户实时余额，account_type 为 BASIC / FEES / OPERATION，缺省 BASIC
+    pub async fn ecommerce_balance(
+        &self,
+        sub_mchid: &str,
+        account_type: Option<&str>,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/ecommerce/fund/balance/{}?account_type={}",
+            sub_mchid,
+            account_type.unwrap_or("BASIC")
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 查询二级商户日终余额，date 格式 yyyy-MM-dd
+    pub async fn ecommerce_dayend_balance(&self, sub_mchid: &str, date: &str) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/ecommerce/fund/enddaybalance/{}?date={}",
+            sub_mchid, date
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 查询电商平台实时余额
+    pub async fn platform_balance(&self, account_type: &str) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!("/v3/merchant/fund/balance/{}", account_type));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 查询电商平台日终余额
+    pub async fn platform_dayend_balance(&self, account_type: &str, date: &str) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/merchant/fund/dayendbalance/{}?date={}",
+            account_type, date
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 二级商户余额提现，body 含 sub_mchid、out_request_no、amount、remark 等
+    pub async fn ecommerce_withdraw(&self, body: Value) -> Result<Value, PayError> {
+        let url = self.endpoint("/v3/ecommerce/fund/withdraw");
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    /// 按商户提现单号查询二级商户提现状态
+    pub async fn query_ecommerce_withdraw(
+        &self,
+        sub_mchid: &str,
+        out_request_no: &str,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/ecommerce/fund/withdraw/out-request-no/{}?sub_mchid={}",
+            urlencoding::encode(out_request_no),
+            sub_mchid
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 按微信提现单号查询二级商户提现状态
+    pub async fn query_ecommerce_withdraw_by_id(
+        &self,
+        sub_mchid: &str,
+        withdraw_id: &str,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/ecommerce/fund/withdraw/{}?sub_mchid={}",
+            withdraw_id, sub_mchid
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 电商平台提现，body 含 out_request_no、amount、account_type 等
+    pub async fn platform_withdraw(&self, body: Value) -> Result<Value, PayError> {
+        let url = self.endpoint("/v3/merchant/fund/withdraw");
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    /// 按商户提现单号查询电商平台提现状态
+    pub async fn query_platform_withdraw(&self, out_request_no: &str) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/merchant/fund/withdraw/out-request-no/{}",
+            urlencoding::encode(out_request_no)
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 收付通请求分账，receivers[].receiver_name 传明文，自动加密；appid / sub_mchid 缺省取配置
+    pub async fn ecommerce_profitsharing(&self, order: Value) -> Result<Value, PayError> {
+        let mut body = self.ecommerce_body(order);
+        let count = body
+            .get("receivers")
+            .and_then(|v| v.as_array())
+            .map_or(0, |r| r.len());
+        let pointers: Vec<String> = (0..count)
+            .map(|i| format!("/receivers/{}/receiver_name", i))
+            .collect();
+        let pointers: Vec<&str> = pointers.iter().map(String::as_str).collect();
+        let serial = self.encrypt_fields(&mut body, &pointers).await?;
+        let url = self.endpoint("/v3/ecommerce/profitsharing/orders");
+        self.sign_and_post_with_serial("POST", &url, &body, serial.as_deref())
+            .await
+    }
+
+    /// 查询收付通分账结果
+    pub async fn query_ecommerce_profitsharing(
+        &self,
+        sub_mchid: &str,
+        transaction_id: &str,
+        out_order_no: &str,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/ecommerce/profitsharing/orders?sub_mchid={}&transaction_id={}&out_order_no={}",
+            sub_mchid,
+            transaction_id,
+            urlencoding::encode(out_order_no)
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 收付通分账回退，body 含 out_order_no / order_id、out_return_no、return_mchid、amount
+    pub async fn ecommerce_profitsharing_return(&self, body: Value) -> Result<Value, PayError> {
+        let body = self.ecommerce_sub_mchid(body);
+        let url = self.endpoint("/v3/ecommerce/profitsharing/returnorders");
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    /// 查询收付通分账回退结果
+    pub async fn query_ecommerce_profitsharing_return(
+        &self,
+        sub_mchid: &str,
+        out_order_no: &str,
+        out_return_no: &str,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/ecommerce/profitsharing/returnorders?sub_mchid={}&out_order_no={}&out_return_no={}",
+            sub_mchid,
+            urlencoding::encode(out_order_no),
+            urlencoding::encode(out_return_no)
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 完结收付通分账，body 含 transaction_id、out_order_no、description
+    pub async fn finish_ecommerce_profitsharing(&self, body: Value) -> Result<Value, PayError> {
+        let body = self.ecommerce_sub_mchid(body);
+        let url = self.endpoint("/v3/ecommerce/profitsharing/finish-order");
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    /// 查询订单剩余待分金额
+    pub async fn query_ecommerce_profitsharing_amounts(
+        &self,
+        transaction_id: &str,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/ecommerce/profitsharing/orders/{}/amounts",
+            transaction_id
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 收付通添加分账接收方，name 传明文，自动加密
+    pub async fn add_ecommerce_receiver(&self, receiver: Value) -> Result<Value, PayError> {
+        let mut body = self.ecommerce_appid(receiver);
+        let serial = self.encrypt_fields(&mut body, &["/name"]).await?;
+        let url = self.endpoint("/v3/ecommerce/profitsharing/receivers/add");
+        self.sign_and_post_with_serial("POST", &url, &body, serial.as_deref())
+            .await
+    }
+
+    /// 收付通删除分账接收方
+    pub async fn delete_ecommerce_receiver(&self, receiver: Value) -> Result<Value, PayError> {
+        let body = self.ecommerce_appid(receiver);
+        let url = self.endpoint("/v3/ecommerce/profitsharing/receivers/delete");
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    fn ecommerce_appid(&self, mut body: Value) -> Value {
+        if body.get("appid").is_none() {
+            if let Some(appid) = &self.cfg.appid {
+                body["appid"] = json!(appid);
+            }
+        }
+        body
+    }
+
+    fn ecommerce_sub_mchid(&self, mut body: Value) -> Value {
+        if body.get("sub_mchid").is_none() {
+            if let Some(sub_mchid) = &self.cfg.sub_mchid {
+                body["sub_mchid"] = json!(sub_mchid);
+            }
+        }
+        body
+    }
+
+    // 收付通分账请求带电商平台 appid 与二级商户号
+    fn ecommerce_body(&self, body: Value) -> Value {
+        self.ecommerce_sub_mchid(self.ecommerce_appid(body))
+    }
+}
@@ -0,0 +1,79 @@
+//! 接口域名容灾：主域名网络故障时切换到备用域名，并定期放行请求探测主域名是否恢复
+use crate::config::WechatConfig;
+use crate::errors::PayError;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+/// 切换到备用域名后，每隔该时长放行一次主域名探测
+pub(crate) const PROBE_INTERVAL: Duration = Duration::from_secs(60);
+
+// 已切换到备用域名的主域名及最近一次切换（或探测）时刻
+static FAILED_OVER: Lazy<RwLock<HashMap<String, Instant>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// 备用域名：优先 backup_base_url；未覆盖 base_url 时取接入区域的默认备用域名
+pub(crate) fn backup_host(cfg: &WechatConfig) -> Option<&str> {
+    match (cfg.backup_base_url.as_deref(), cfg.base_url.as_deref()) {
+        (Some(url), _) => Some(url.trim_end_matches('/')),
+        (None, None) => cfg.region.wechat_backup_host(),
+        (None, Some(_)) => None,
+    }
+}
+
+/// 本次请求应使用的域名
+pub(crate) fn active_host(cfg: &WechatConfig) -> &str {
+    let primary = cfg.api_host();
+    let Some(backup) = backup_host(cfg) else {
+        return primary;
+    };
+    // 常态只取读锁，探测到期时才升级为写锁
+    match FAILED_OVER.read().unwrap().get(primary) {
+        None => return primary,
+        Some(since) if since.elapsed() < PROBE_INTERVAL => return backup,
+        Some(_) => {}
+    }
+    let mut failed = FAILED_OVER.write().unwrap();
+    // 取得写锁后重新判断，同一时刻只放行一个探测请求
+    match failed.get_mut(primary) {
+        None => primary,
+        // 探测期间其余请求继续走备用域名
+        Some(since) if since.elapsed() >= PROBE_INTERVAL => {
+            *since = Instant::now();
+            tracing::info!("wechat probing primary host {}", primary);
+            primary
+        }
+        Some(_) => backup,
+    }
+}
+
+/// 请求主域名出现网络故障时标记切换，返回是否应改用备用域名重发
+pub(crate) fn report_failure(cfg: &WechatConfig, host: &str, err: &PayError) -> bool {
+    let network = matches!(err, PayError::Http(e) if e.is_connect() || e.is_timeout());
+    let primary = cfg.api_host();
+    if !network || host != primary || backup_host(cfg).is_none() {
+        return false;
+    }
+    let switched = FAILED_OVER
+        .write()
+        .unwrap()
+        .insert(primary.to_string(), Instant::now())
+        .is_none();
+    if switched {
+        tracing::warn!("wechat primary host {} unreachable, fail over to backup", primary);
+    }
+    true
+}
+
+/// 主域名请求成功时解除切换
+pub(crate) fn report_success(cfg: &WechatConfig, host: &str) {
+    let primary = cfg.api_host();
+    if host != primary {
+        return;
+    }
+    if FAILED_OVER.read().unwrap().contains_key(primary) {
+        FAILED_OVER.write().unwrap().remove(primary);
+        tracing::info!("wechat primary host {} recovered", primary);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::errors::PayError;
+use crate::wechat::client::WechatClient;
+use serde_json::{json, Value};
+
+impl WechatClient {
+    /// 创建电子发票卡券模板，card_appid 缺省取配置的 appid
+    pub async fn create_fapiao_card_template(&self, mut body: Value) -> Result<Value, PayError> {
+        if body.get("card_appid").is_none() {
+            if let Some(appid) = &self.cfg.appid {
+                body["card_appid"] = json!(appid);
+            }
+        }
+        let body = self.fapiao_body(body);
+        let url = self.endpoint("/v3/new-tax-control-fapiao/card-template");
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    /// 查询商户开票基础信息
+    pub async fn query_fapiao_merchant_info(&self) -> Result<Value, PayError> {
+        let url = self.endpoint(&self.fapiao_path("/v3/new-tax-control-fapiao/merchant/base-information"));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 配置开发选项（开票结果回调地址）
+    pub async fn set_fapiao_callback(&self, callback_url: &str) -> Result<Value, PayError> {
+        let url = self.endpoint("/v3/new-tax-control-fapiao/merchant/development-config");
+        let body = self.fapiao_body(json!({ "callback_url": callback_url }));
+        self.sign_and_post("PATCH", &url, &body).await
+    }
+
+    /// 获取抬头填写链接，用户填写后通过 query_fapiao_user_title 取回抬头
+    pub async fn fapiao_title_url(
+        &self,
+        fapiao_apply_id: &str,
+        openid: &str,
+        total_amount: i64,
+        source: &str,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&self.fapiao_path(&format!(
+            "/v3/new-tax-control-fapiao/user-title/title-url?fapiao_apply_id={}&appid={}&openid={}&total_amount={}&source={}",
+            urlencoding::encode(fapiao_apply_id),
+            self.cfg.appid.as_deref().unwrap_or_default(),
+            openid,
+            total_amount,
+            source
+        )));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 获取用户填写的抬头，scene 为 WITHWECHATPAY（支付后开票）等
+    pub async fn query_fapiao_user_title(
+        &self,
+        fapiao_apply_id: &str,
+        scene: &str,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&self.fapiao_path(&format!(
+            "/v3/new-tax-control-fapiao/user-title?fapiao_apply_id={}&scene={}",
+            urlencoding::encode(fapiao_apply_id),
+            scene
+        )));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 开具电子发票，buyer_information 中的 phone / email 传明文，自动以平台证书加密
+    pub async fn issue_fapiao(&self, body: Value) -> Result<Value, PayError> {
+        let mut body = self.fapiao_body(body);
+        let serial = self
+            .encrypt_fields(&mut body, &["/buyer_information/phone", "/buyer_information/email"])
+            .await?;
+        let url = self.endpoint("/v3/new-tax-control-fapiao/fapiao-applications");
+        self.sign_and_post_with_serial("POST", &url, &body, serial.as_deref())
+            .await
+    }
+
+    /// 查询发票开具结果，fapiao_id 为空时返回该申请下全部发票
+    pub async fn query_fapiao(
+        &self,
+        fapiao_apply_id: &str,
+        fapiao_id: Option<&str>,
+    ) -> Result<Value, PayError> {
+        let mut path = format!(
+            "/v3/new-tax-control-fapiao/fapiao-applications/{}",
+            urlencoding::encode(fapiao_apply_id)
+        );
+        if let Some(fapiao_id) = fapiao_id {
+            path.push_str("?fapiao_id=");
+            path.push_str(fapiao_id);
+        }
+        let url = self.endpoint(&self.fapiao_path(&path));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 冲红电子发票，body 含 reverse_reason 与 fapiao_information
+    pub async fn reverse_fapiao(&self, fapiao_apply_id: &str, body: Value) -> Result<Value, PayError> {
+        let body = self.fapiao_body(body);
+        let url = self.endpoint(&format!(
+            "/v3/new-tax-control-fapiao/fapiao-applications/{}/reverse",
+            urlencoding::encode(fapiao_apply_id)
+        ));
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    /// 获取发票下载信息，返回各发票的 download_url
+    pub async fn query_fapiao_files(&self, fapiao_apply_id: &str) -> Result<Value, PayError> {
+        let url = self.endpoint(&self.fapiao_path(&format!(
+            "/v3/new-tax-control-fapiao/fapiao-applications/{}/fapiao-files",
+            urlencoding::encode(fapiao_apply_id)
+        )));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    // 服务商模式下 body 带 sub_mchid
+    fn fapiao_body(&self, mut body: Value) -> Value {
+        if body.get("sub_mchid").is_none() {
+            if let Some(sub_mchid) = self.service_sub_mchid() {
+                body["sub_mchid"] = json!(sub_mchid);
+            }
+        }
+        body
+    }
+
+    // 服务商模式下查询串带 sub_mchid
+    fn fapiao_path(&self, path: &str) -> String {
+        match self.service_sub_mchid() {
+            Some(sub_mchid) => {
+                let sep = if path.contains('?') { '&' } else { '?' };
+                format!("{}{}sub_mchid={}", path, sep, sub_mchid)
+            }
+            None => path.to_string(),
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use crate::errors::PayError;
+use crate::wechat::client::WechatClient;
+use serde_json::{json, Value};
+
+impl WechatClient {
+    /// 创建代金券批次，belong_merchant 缺省为本商户号
+    pub async fn create_favor_stock(&self, mut stock: Value) -> Result<Value, PayError> {
+        if stock.get("belong_merchant").is_none() {
+            stock["belong_merchant"] = json!(self.cfg.mchid);
+        }
+        let url = self.endpoint("/v3/marketing/favor/coupon-stocks");
+        self.sign_and_post("POST", &url, &stock).await
+    }
+
+    /// 激活代金券批次
+    pub async fn start_favor_stock(&self, stock_id: &str) -> Result<Value, PayError> {
+        self.favor_stock_action(stock_id, "start").await
+    }
+
+    /// 暂停代金券批次
+    pub async fn pause_favor_stock(&self, stock_id: &str) -> Result<Value, PayError> {
+        self.favor_stock_action(stock_id, "pause").await
+    }
+
+    /// 重启代金券批次
+    pub async fn restart_favor_stock(&self, stock_id: &str) -> Result<Value, PayError> {
+        self.favor_stock_action(stock_id, "restart").await
+    }
+
+    /// 向用户发放代金券，body 含 stock_id、out_request_no 等；appid / stock_creator_mchid 缺省取配置
+    pub async fn send_favor_coupon(&self, openid: &str, mut body: Value) -> Result<Value, PayError> {
+        if body.get("appid").is_none() {
+            if let Some(appid) = &self.cfg.appid {
+                body["appid"] = json!(appid);
+            }
+        }
+        if body.get("stock_creator_mchid").is_none() {
+            body["stock_creator_mchid"] = json!(self.cfg.mchid);
+        }
+        let url = self.endpoint(&format!("/v3/marketing/favor/users/{}/coupons", openid));
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    /// 分页查询本商户创建的批次
+    pub async fn list_favor_stocks(&self, offset: u32, limit: u32) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/marketing/favor/stocks?offset={}&limit={}&stock_creator_mchid={}",
+            offset, limit, self.cfg.mchid
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 查询批次详情
+    pub async fn query_favor_stock(&self, stock_id: &str) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/marketing/favor/stocks/{}?stock_creator_mchid={}",
+            stock_id, self.cfg.mchid
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 查询用户的代金券详情
+    pub async fn query_favor_coupon(&self, openid: &str, coupon_id: &str) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/marketing/favor/users/{}/coupons/{}?appid={}",
+            openid,
+            coupon_id,
+            self.cfg.appid.as_deref().unwrap_or_default()
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 设置核销事件回调地址，switch 为是否开启通知
+    pub async fn set_favor_callback(&self, notify_url: &str, switch: bool) -> Result<Value, PayError> {
+        let url = self.endpoint("/v3/marketing/favor/callbacks");
+        let body = json!({
+            "mchid": self.cfg.mchid,
+            "notify_url": notify_url,
+            "switch": switch,
+        });
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    async fn favor_stock_action(&self, stock_id: &str, action: &str) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!("/v3/marketing/favor/stocks/{}/{}", stock_id, action));
+        let body = json!({ "stock_creator_mchid": self.cfg.mchid });
+        self.sign_and_post("POST", &url, &body).await
+    }
+}
@@ -0,0 +1,71 @@
+use crate::errors::PayError;
+use crate::model::{params_to_value, RawPayload};
+use crate::wechat::client::WechatClient;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// 跨境商户结算汇率
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExchangeRate {
+    /// 外币币种，如 USD
+    pub fee_type: String,
+    /// 汇率生效时间，格式 yyyyMMddHHmmss
+    pub rate_time: String,
+    /// 汇率原始值，为实际汇率乘以 10^8
+    pub rate: String,
+    /// 接口原始应答
+    #[serde(default)]
+    pub raw: Value,
+}
+
+impl RawPayload for ExchangeRate {
+    fn raw(&self) -> &Value {
+        &self.raw
+    }
+}
+
+impl ExchangeRate {
+    /// 实际汇率（1 外币兑人民币）
+    pub fn as_f64(&self) -> Option<f64> {
+        self.rate.parse::<f64>().ok().map(|r| r / 100_000_000.0)
+    }
+
+    /// 实际汇率的精确值
+    pub fn as_decimal(&self) -> Option<Decimal> {
+        self.rate.parse::<i64>().ok().map(|r| Decimal::new(r, 8))
+    }
+}
+
+impl WechatClient {
+    /// 查询结算汇率（跨境支付，V2 queryexchagerate 接口）
+    /// `date` 格式 yyyyMMdd；服务商模式下自动带上 sub_mch_id
+    pub async fn query_exchange_rate(
+        &self,
+        fee_type: &str,
+        date: &str,
+    ) -> Result<ExchangeRate, PayError> {
+        let mut params = BTreeMap::new();
+        if let Some(appid) = &self.cfg.appid {
+            params.insert("appid".to_string(), appid.clone());
+        }
+        if let Some(sub_mchid) = &self.cfg.sub_mchid {
+            params.insert("sub_mch_id".to_string(), sub_mchid.clone());
+        }
+        params.insert("fee_type".to_string(), fee_type.to_string());
+        params.insert("date".to_string(), date.to_string());
+        let resp = self.v2_post("/pay/queryexchagerate", params).await?;
+        let field = |k: &str| {
+            resp.get(k)
+                .cloned()
+                .ok_or_else(|| PayError::Other(format!("queryexchagerate: missing {}", k)))
+        };
+        Ok(ExchangeRate {
+            fee_type: field("fee_type")?,
+            rate_time: field("rate_time")?,
+            rate: field("rate")?,
+            raw: params_to_value(&resp),
+        })
+    }
+}
@@ -0,0 +1,123 @@
+use crate::errors::PayError;
+use crate::wechat::client::{json_or_null, WechatClient};
+use openssl::hash::{hash, MessageDigest};
+use reqwest::multipart::{Form, Part};
+use serde_json::{json, Value};
+use std::path::Path;
+
+impl WechatClient {
+    /// 图片上传，返回 media_id（进件、投诉回复等接口引用）
+    /// 文件以 multipart 上传，签名串中的 body 仅为 meta JSON
+    pub async fn upload_image(&self, file_name: &str, content: &[u8]) -> Result<String, PayError> {
+        let url = self.endpoint("/v3/merchant/media/upload");
+        let resp = self.upload_media(&url, file_name, content).await?;
+        media_id(resp)
+    }
+
+    /// 视频上传，返回 media_id
+    pub async fn upload_video(&self, file_name: &str, content: &[u8]) -> Result<String, PayError> {
+        let url = self.endpoint("/v3/merchant/media/video_upload");
+        let resp = self.upload_media(&url, file_name, content).await?;
+        media_id(resp)
+    }
+
+    /// 营销图片上传（/v3/marketing/favor/media/image-upload），返回图片 URL，用于批次 logo 等
+    pub async fn upload_marketing_image(&self, file_name: &str, content: &[u8]) -> Result<String, PayError> {
+        let url = self.endpoint("/v3/marketing/favor/media/image-upload");
+        let resp = self.upload_media(&url, file_name, content).await?;
+        resp.get("media_url")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .ok_or_else(|| PayError::Other(format!("media upload: missing media_url: {}", resp)))
+    }
+
+    /// 读取本地图片并上传，文件名取路径末段
+    pub async fn upload_image_file(&self, path: impl AsRef<Path>) -> Result<String, PayError> {
+        let (file_name, content) = read_media(path.as_ref())?;
+        self.upload_image(&file_name, &content).await
+    }
+
+    /// 读取本地视频并上传，文件名取路径末段
+    pub async fn upload_video_file(&self, path: impl AsRef<Path>) -> Result<String, PayError> {
+        let (file_name, content) = read_media(path.as_ref())?;
+        self.upload_video(&file_name, &content).await
+    }
+
+    pub(crate) async fn upload_media(
+        &self,
+        url: &str,
+        file_name: &str,
+        content: &[u8],
+    ) -> Result<Value, PayError> {
+        let digest = hash(MessageDigest::sha256(), content)
+            .map_err(|e| PayError::Crypto(format!("media sha256: {}", e)))?;
+        let sha256 = digest.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+        let meta = json!({ "filename": file_name, "sha256": sha256 }).to_string();
+        let auth = self.authorization("POST", url, &meta)?;
+        tracing::info!("upload_media: url={}, meta={}", url, meta);
+
+        let meta_part = Part::text(meta)
+            .mime_str("application/json")
+            .map_err(PayError::Http)?;
+        let file_part = Part::bytes(content.to_vec())
+            .file_name(file_name.to_string())
+            .mime_str(media_mime(file_name))
+            .map_err(PayError::Http)?;
+        let form = Form::new().part("meta", meta_part).part("file", file_part);
+
+        let resp = self
+            .http
+            .post(url)
+            .header("Authorization", auth)
+            .header("Accept", "application/json")
+            .header("User-Agent", "rust_pay_wf")
+            .multipart(form)
+            .send()
+            .await?;
+        let status = resp.status();
+        let text = resp.text().await?;
+        if !status.is_success() {
+            return Err(PayError::Other(format!(
+                "HTTP request failed: {} - {}",
+                status, text
+            )));
+        }
+        json_or_null(status, &text)
+    }
+}
+
+fn media_id(resp: Value) -> Result<String, PayError> {
+    resp.get("media_id")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .ok_or_else(|| PayError::Other(format!("media upload: missing media_id: {}", resp)))
+}
+
+fn read_media(path: &Path) -> Result<(String, Vec<u8>), PayError> {
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| PayError::Other(format!("invalid media path: {}", path.display())))?
+        .to_string();
+    Ok((file_name, std::fs::read(path)?))
+}
+
+fn media_mime(file_name: &str) -> &'static str {
+    let ext = file_name.rsplit('.').next().unwrap_or("").to_ascii_lowercase();
+    match ext.as_str() {
+        "png" => "image/png",
+        "bmp" => "image/bmp",
+        "jpg" | "jpeg" => "image/jpeg",
+        "avi" => "video/x-msvideo",
+        "wmv" => "video/x-ms-wmv",
+        "mpeg" => "video/mpeg",
+        "mp4" => "video/mp4",
+        "mov" => "video/quicktime",
+        "mkv" => "video/x-matroska",
+        "flv" => "video/x-flv",
+        "f4v" => "video/x-f4v",
+        "m4v" => "video/x-m4v",
+        "rmvb" => "application/vnd.rn-realmedia-vbr",
+        _ => "application/octet-stream",
+    }
+}
@@ -0,0 +1,183 @@
+//! 付款码支付（B 扫 C）：V3 无付款码下单接口，走 V2 /pay/micropay
+use crate::config::Mode;
+use crate::errors::PayError;
+use crate::poll::PaymentState;
+use crate::wechat::client::WechatClient;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// 用户输入密码的最长等待时间
+const USERPAYING_TIMEOUT: Duration = Duration::from_secs(30);
+/// recall=Y 时撤销的最多重试次数
+const REVERSE_ATTEMPTS: usize = 3;
+
+impl WechatClient {
+    /// 付款码支付
+    ///
+    /// order 兼容 V3 字段（description、amount.total、scene_info.payer_client_ip）与
+    /// V2 字段（body、total_fee、spbill_create_ip），auth_code 与终端 IP 必填。
+    /// 返回 USERPAYING、系统错误或请求超时/网络中断时按商户订单号轮询至终态，支付成功返回
+    /// 查询结果；超时仍未支付则撤销订单并返回错误。
+    pub async fn micropay(&self, order: impl Into<Value>) -> Result<Value, PayError> {
+        let order = order.into();
+        let params = self.micropay_params(&order)?;
+        let out_trade_no = params["out_trade_no"].clone();
+        let resp = match self.v2_post("/pay/micropay", params).await {
+            Ok(resp) => resp,
+            // 请求可能已到达微信，下单结果未知
+            Err(PayError::Http(e)) => {
+                tracing::warn!("wechat micropay {} request failed: {}", out_trade_no, e);
+                return self.await_or_reverse(&out_trade_no).await;
+            }
+            Err(e) => return Err(e),
+        };
+        if resp.get("result_code").map(String::as_str) == Some("SUCCESS") {
+            return Ok(map_to_value(resp));
+        }
+        let err_code = resp.get("err_code").cloned().unwrap_or_default();
+        // 需用户输入密码或结果未知时轮询确认
+        if !matches!(err_code.as_str(), "USERPAYING" | "SYSTEMERROR" | "BANKERROR") {
+            return Err(PayError::Other(format!(
+                "wechat micropay failed: {} - {}",
+                err_code,
+                resp.get("err_code_des").cloned().unwrap_or_default()
+            )));
+        }
+        self.await_or_reverse(&out_trade_no).await
+    }
+
+    // 结果未知时轮询至终态，未确认支付成功则撤销
+    async fn await_or_reverse(&self, out_trade_no: &str) -> Result<Value, PayError> {
+        let reason = match self
+            .await_payment(out_trade_no, Instant::now() + USERPAYING_TIMEOUT, false)
+            .await
+        {
+            Ok(outcome) if outcome.state == PaymentState::Paid => return Ok(outcome.raw),
+            Ok(outcome) => format!("{:?}: {}", outcome.state, outcome.raw),
+            Err(e) => e.to_string(),
+        };
+        self.reverse(out_trade_no).await?;
+        Err(PayError::Other(format!(
+            "wechat micropay {} not paid ({}), order reversed",
+            out_trade_no, reason
+        )))
+    }
+
+    /// 撤销订单（/secapi/pay/reverse），需商户 API 证书双向 TLS
+    ///
+    /// 应答 recall=Y 时按官方建议重试撤销；返回最后一次应答。
+    pub async fn reverse(&self, out_trade_no: &str) -> Result<Value, PayError> {
+        let mut params = BTreeMap::new();
+        params.insert("out_trade_no".to_string(), out_trade_no.to_string());
+        if let Some(appid) = &self.cfg.appid {
+            params.insert("appid".to_string(), appid.clone());
+        }
+        if let Some(sub_mchid) = self.service_sub_mchid() {
+            params.insert("sub_mch_id".to_string(), sub_mchid.to_string());
+        }
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let resp = self
+                .v2_post_with_cert("/secapi/pay/reverse", params.clone())
+                .await?;
+            if resp.get("result_code").map(String::as_str) == Some("SUCCESS") {
+                return Ok(map_to_value(resp));
+            }
+            let recall = resp.get("recall").map(String::as_str) == Some("Y");
+            if !recall || attempt >= REVERSE_ATTEMPTS {
+                return Err(PayError::Other(format!(
+                    "wechat reverse {} failed: {} - {}",
+                    out_trade_no,
+                    resp.get("err_code").cloned().unwrap_or_default(),
+                    resp.get("err_code_des").cloned().unwrap_or_default()
+                )));
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    }
+
+    /// 先查询订单，已支付则返回查询结果，否则撤销
+    ///
+    /// 用于付款码支付结果不明的收尾；查询失败（如 ORDER_NOT_EXIST）时同样撤销。
+    pub async fn query_or_reverse(&self, out_trade_no: &str) -> Result<Value, PayError> {
+        match self.query_by_out_trade_no(out_trade_no).await {
+            Ok(resp)
+                if matches!(
+                    resp.get("trade_state").and_then(|v| v.as_str()),
+                    Some("SUCCESS") | Some("REFUND")
+                ) =>
+            {
+                Ok(resp)
+            }
+            Ok(_) => self.reverse(out_trade_no).await,
+            Err(e) => {
+                tracing::warn!("wechat query {} failed before reverse: {}", out_trade_no, e);
+                self.reverse(out_trade_no).await
+            }
+        }
+    }
+
+    fn micropay_params(&self, order: &Value) -> Result<BTreeMap<String, String>, PayError> {
+        let text = |pointers: &[&str]| {
+            pointers.iter().find_map(|p| match order.pointer(p) {
+                Some(Value::String(s)) => Some(s.clone()),
+                Some(Value::Number(n)) => Some(n.to_string()),
+                _ => None,
+            })
+        };
+        let required = |pointers: &[&str], name: &str| {
+            text(pointers).ok_or_else(|| PayError::Other(format!("Missing required field: '{}'", name)))
+        };
+        let mut params = BTreeMap::new();
+        params.insert("auth_code".to_string(), required(&["/auth_code"], "auth_code")?);
+        params.insert(
+            "out_trade_no".to_string(),
+            required(&["/out_trade_no"], "out_trade_no")?,
+        );
+        params.insert(
+            "body".to_string(),
+            required(&["/body", "/description"], "description")?,
+        );
+        params.insert(
+            "total_fee".to_string(),
+            required(&["/total_fee", "/amount/total"], "amount.total")?,
+        );
+        params.insert(
+            "spbill_create_ip".to_string(),
+            required(
+                &["/spbill_create_ip", "/scene_info/payer_client_ip"],
+                "scene_info.payer_client_ip",
+            )?,
+        );
+        for (key, pointers) in [
+            ("attach", ["/attach"]),
+            ("goods_tag", ["/goods_tag"]),
+            ("device_info", ["/device_info"]),
+            ("time_expire", ["/time_expire"]),
+        ] {
+            if let Some(v) = text(&pointers) {
+                params.insert(key.to_string(), v);
+            }
+        }
+        let appid = text(&["/appid"]).or_else(|| self.cfg.appid.clone());
+        if let Some(appid) = appid {
+            params.insert("appid".to_string(), appid);
+        }
+        if let Mode::Service = self.mode {
+            let sub_mchid = text(&["/sub_mchid"]).or_else(|| self.cfg.sub_mchid.clone());
+            if let Some(sub_mchid) = sub_mchid {
+                params.insert("sub_mch_id".to_string(), sub_mchid);
+            }
+            if let Some(sub_appid) = text(&["/sub_appid"]) {
+                params.insert("sub_appid".to_string(), sub_appid);
+            }
+        }
+        Ok(params)
+    }
+}
+
+pub(crate) fn map_to_value(map: BTreeMap<String, String>) -> Value {
+    Value::Object(map.into_iter().map(|(k, v)| (k, Value::String(v))).collect())
+}
@@ -1,4 +1,26 @@
+pub mod applyment;
+pub mod bill;
+pub mod brand_profitsharing;
+pub mod busifavor;
 pub mod certs;
 pub mod client;
+pub mod combine;
+pub mod complaint;
+pub(crate) mod crypto;
+pub mod ecommerce;
+pub(crate) mod failover;
+pub mod fapiao;
+pub mod favor;
+pub mod global;
+pub mod media;
+pub mod micropay;
+pub mod model;
 pub mod notify;
-pub use client::WechatClient;
+pub mod papay;
+pub mod payscore;
+pub mod poll;
+pub mod profitsharing;
+pub mod promotion;
+pub mod transfer;
+pub mod v2;
+pub use client::{ApiResponse, SignedRequest, WechatClient};
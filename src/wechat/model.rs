@@ -0,0 +1,272 @@
+//! 微信支付 V3 交易接口的类型化请求 / 应答
+//!
+//! 下单方法接收 `impl Into<Value>`，既可传入这里的结构体，也可继续传 serde_json::Value。
+//! appid / mchid / notify_url 等留空时由客户端按配置补齐。
+use crate::errors::PayError;
+use crate::model::{parse_with_mode, ParseMode, RawPayload, WithExtras};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+pub use crate::order::{
+    WechatGoodsDetail as GoodsDetail, WechatH5Info as H5Info, WechatSceneInfo as SceneInfo,
+    WechatStoreInfo as StoreInfo,
+};
+
+/// 订单金额
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Amount {
+    /// 总金额，单位分
+    pub total: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+}
+
+impl Amount {
+    pub fn cny(total: i64) -> Self {
+        Self {
+            total,
+            currency: Some("CNY".into()),
+        }
+    }
+}
+
+/// 支付者；服务商模式下 openid 会按 sub_appid 自动转为 sub_openid / sp_openid
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Payer {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub openid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sp_openid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub_openid: Option<String>,
+}
+
+impl Payer {
+    pub fn openid(openid: impl Into<String>) -> Self {
+        Self {
+            openid: Some(openid.into()),
+            ..Default::default()
+        }
+    }
+}
+
+/// 结算信息
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SettleInfo {
+    /// 是否指定分账
+    pub profit_sharing: bool,
+}
+
+/// 商品详情（detail）
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct OrderDetail {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost_price: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invoice_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub goods_detail: Vec<GoodsDetail>,
+}
+
+/// JSAPI / 小程序下单（mp、miniapp）
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct JsapiOrder {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub appid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub_appid: Option<String>,
+    pub description: String,
+    pub out_trade_no: String,
+    /// 交易结束时间，rfc3339 格式
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_expire: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attach: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notify_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub goods_tag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub support_fapiao: Option<bool>,
+    pub amount: Amount,
+    pub payer: Payer,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<OrderDetail>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scene_info: Option<SceneInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settle_info: Option<SettleInfo>,
+}
+
+/// H5 下单，scene_info 必填且需带 h5_info
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct H5Order {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub appid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub_appid: Option<String>,
+    pub description: String,
+    pub out_trade_no: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_expire: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attach: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notify_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub goods_tag: Option<String>,
+    pub amount: Amount,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<OrderDetail>,
+    pub scene_info: SceneInfo,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settle_info: Option<SettleInfo>,
+}
+
+/// Native / APP 下单
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct NativeOrder {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub appid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub_appid: Option<String>,
+    pub description: String,
+    pub out_trade_no: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_expire: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attach: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notify_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub goods_tag: Option<String>,
+    pub amount: Amount,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<OrderDetail>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scene_info: Option<SceneInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settle_info: Option<SettleInfo>,
+}
+
+/// APP 下单字段与 Native 相同
+pub type AppOrder = NativeOrder;
+
+macro_rules! into_value {
+    ($($t:ty),*) => {
+        $(impl From<$t> for Value {
+            fn from(order: $t) -> Value {
+                serde_json::to_value(order).expect("order serialize")
+            }
+        })*
+    };
+}
+into_value!(JsapiOrder, H5Order, NativeOrder);
+
+/// 下单应答：JSAPI / APP 返回 prepay_id，Native 返回 code_url，H5 返回 h5_url
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PrepayResponse {
+    pub prepay_id: Option<String>,
+    pub code_url: Option<String>,
+    pub h5_url: Option<String>,
+    #[serde(skip)]
+    pub raw: Value,
+}
+
+impl PrepayResponse {
+    pub fn from_value(value: Value) -> Result<Self, PayError> {
+        let mut resp: Self = serde_json::from_value(value.clone())?;
+        resp.raw = value;
+        Ok(resp)
+    }
+}
+
+impl RawPayload for PrepayResponse {
+    fn raw(&self) -> &Value {
+        &self.raw
+    }
+}
+
+/// 订单金额信息（查询 / 回调）
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TransactionAmount {
+    pub total: Option<i64>,
+    pub payer_total: Option<i64>,
+    pub currency: Option<String>,
+    pub payer_currency: Option<String>,
+}
+
+/// 交易详情：查询订单应答与支付成功回调解密后的内容
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Transaction {
+    #[serde(default)]
+    pub appid: Option<String>,
+    #[serde(default)]
+    pub mchid: Option<String>,
+    #[serde(default)]
+    pub sp_appid: Option<String>,
+    #[serde(default)]
+    pub sp_mchid: Option<String>,
+    #[serde(default)]
+    pub sub_appid: Option<String>,
+    #[serde(default)]
+    pub sub_mchid: Option<String>,
+    pub out_trade_no: String,
+    #[serde(default)]
+    pub transaction_id: Option<String>,
+    /// JSAPI / NATIVE / APP / MICROPAY / MWEB / FACEPAY
+    #[serde(default)]
+    pub trade_type: Option<String>,
+    /// SUCCESS / REFUND / NOTPAY / CLOSED / REVOKED / USERPAYING / PAYERROR
+    pub trade_state: String,
+    #[serde(default)]
+    pub trade_state_desc: Option<String>,
+    #[serde(default)]
+    pub bank_type: Option<String>,
+    #[serde(default)]
+    pub attach: Option<String>,
+    #[serde(default)]
+    pub success_time: Option<String>,
+    #[serde(default)]
+    pub payer: Option<Payer>,
+    #[serde(default)]
+    pub amount: Option<TransactionAmount>,
+    #[serde(default)]
+    pub scene_info: Option<Value>,
+    #[serde(default)]
+    pub promotion_detail: Option<Vec<Value>>,
+    /// 未声明的字段
+    #[serde(flatten)]
+    pub extras: Map<String, Value>,
+    #[serde(skip)]
+    pub raw: Value,
+}
+
+impl Transaction {
+    /// 宽松解析查询应答或回调内容
+    pub fn from_value(value: Value) -> Result<Self, PayError> {
+        Self::from_value_with_mode(value, ParseMode::Lenient)
+    }
+
+    pub fn from_value_with_mode(value: Value, mode: ParseMode) -> Result<Self, PayError> {
+        let mut tx: Self = parse_with_mode(value.clone(), mode)?;
+        tx.raw = value;
+        Ok(tx)
+    }
+
+    pub fn is_paid(&self) -> bool {
+        self.trade_state == "SUCCESS"
+    }
+}
+
+impl WithExtras for Transaction {
+    fn extras(&self) -> &Map<String, Value> {
+        &self.extras
+    }
+}
+
+impl RawPayload for Transaction {
+    fn raw(&self) -> &Value {
+        &self.raw
+    }
+}
@@ -1,16 +1,137 @@
 use crate::config::WechatConfig;
 use crate::errors::PayError;
-use crate::utils::{aes_gcm_decrypt, rsa_verify_sha256_pem};
+use crate::model::{parse_with_mode, ParseMode, RawPayload, WithExtras};
+use crate::channel::Channel;
+use crate::notify::{NotifyRequest, NotifyValidators};
 use crate::wechat::certs::PlatformCerts;
+use crate::wechat::crypto::{decrypt_resource, verify_signature};
+use crate::wechat::model::Transaction;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
+
+/// 投诉通知解密后的内容
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ComplaintNotice {
+    /// 通知类型，如 COMPLAINT.CREATE / COMPLAINT.STATE_CHANGE
+    #[serde(default)]
+    pub event_type: String,
+    pub complaint_id: String,
+    /// 动作类型，如 CREATE_COMPLAINT、CONTINUE_COMPLAINT、USER_RESPONSE 等
+    pub action_type: String,
+    /// 被投诉的商户号（服务商模式下返回）
+    pub complainted_mchid: Option<String>,
+    pub service_order_id: Option<String>,
+    pub out_trade_no: Option<String>,
+    /// 未声明的字段
+    #[serde(flatten)]
+    pub extras: serde_json::Map<String, serde_json::Value>,
+    /// 解密后的原始通知内容
+    #[serde(skip)]
+    pub raw: serde_json::Value,
+}
+
+impl WithExtras for ComplaintNotice {
+    fn extras(&self) -> &serde_json::Map<String, serde_json::Value> {
+        &self.extras
+    }
+}
+
+impl RawPayload for ComplaintNotice {
+    fn raw(&self) -> &serde_json::Value {
+        &self.raw
+    }
+}
+
+/// 退款通知金额
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RefundNoticeAmount {
+    pub total: i64,
+    pub refund: i64,
+    pub payer_total: i64,
+    pub payer_refund: i64,
+}
+
+/// 退款通知解密后的内容（REFUND.SUCCESS / REFUND.ABNORMAL / REFUND.CLOSED）
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RefundNotice {
+    #[serde(default)]
+    pub event_type: String,
+    /// 直连商户号
+    pub mchid: Option<String>,
+    /// 服务商模式下的服务商与子商户号
+    pub sp_mchid: Option<String>,
+    pub sub_mchid: Option<String>,
+    pub out_trade_no: String,
+    pub transaction_id: String,
+    pub out_refund_no: String,
+    pub refund_id: String,
+    /// SUCCESS / CLOSED / ABNORMAL
+    pub refund_status: String,
+    pub success_time: Option<String>,
+    /// 退款入账账户，如 招商银行信用卡0403、支付用户零钱
+    pub user_received_account: String,
+    pub amount: RefundNoticeAmount,
+    /// 未声明的字段
+    #[serde(flatten)]
+    pub extras: serde_json::Map<String, serde_json::Value>,
+    /// 解密后的原始通知内容
+    #[serde(skip)]
+    pub raw: serde_json::Value,
+}
+
+impl RefundNotice {
+    pub fn is_success(&self) -> bool {
+        self.refund_status == "SUCCESS"
+    }
+}
+
+impl WithExtras for RefundNotice {
+    fn extras(&self) -> &serde_json::Map<String, serde_json::Value> {
+        &self.extras
+    }
+}
+
+impl RawPayload for RefundNotice {
+    fn raw(&self) -> &serde_json::Value {
+        &self.raw
+    }
+}
+
+/// 按 event_type 区分的通知内容
+#[derive(Clone, Debug)]
+pub enum WechatNotice {
+    /// TRANSACTION.*
+    Payment(Box<Transaction>),
+    /// REFUND.*
+    Refund(RefundNotice),
+    /// COMPLAINT.*
+    Complaint(ComplaintNotice),
+    /// 其他通知，保留 event_type 与解密后的内容
+    Other {
+        event_type: String,
+        resource: serde_json::Value,
+    },
+}
+
 pub struct WechatNotify {
-    cfg: Arc<WechatConfig>,
+    pub(crate) cfg: Arc<WechatConfig>,
     certs: Arc<PlatformCerts>,
+    validators: NotifyValidators,
 }
 impl WechatNotify {
     pub fn new(cfg: Arc<WechatConfig>, certs: Arc<PlatformCerts>) -> Self {
-        Self { cfg, certs }
+        Self {
+            cfg,
+            certs,
+            validators: NotifyValidators::new(),
+        }
+    }
+
+    /// 设置验签解密后执行的业务校验器
+    pub fn with_validators(mut self, validators: NotifyValidators) -> Self {
+        self.validators = validators;
+        self
     }
     pub async fn verify_and_decrypt(
         &self,
@@ -35,19 +156,32 @@ impl WechatNotify {
             .map(String::as_str)
             .unwrap_or("");
         let msg = format!("{}\n{}\n{}\n", ts, nonce, body);
+        // 证书临近过期时先主动刷新，失败不影响使用旧证书验签
+        if let Err(e) = self.certs.refresh_if_expiring().await {
+            tracing::warn!("proactive platform cert refresh failed: {}", e);
+        }
         // 1️⃣ 优先从缓存拿
         let mut pub_pem = self.certs.get_by_serial(serial);
 
         // 2️⃣ 如果没有，就尝试 refresh 一次再取
+        let mut refresh_err = None;
         if pub_pem.is_none() {
-            if let Err(e) = self.certs.refresh().await {
-                return Err(PayError::Crypto(format!("refresh certs failed: {}", e)));
+            match self.certs.refresh().await {
+                Ok(()) => pub_pem = self.certs.get_by_serial(serial),
+                Err(e) => refresh_err = Some(e.to_string()),
             }
-            pub_pem = self.certs.get_by_serial(serial);
         }
-        // 3️⃣ 还是没有，就报错
-        let pub_pem = pub_pem.ok_or_else(|| {
-            PayError::Other(format!("platform cert {} not found after refresh", serial))
+        // 3️⃣ 证书下载不可用（如公钥模式商户）时回退到配置的平台公钥
+        if pub_pem.is_none() {
+            pub_pem = self
+                .cfg
+                .platform_public_key_pem
+                .clone()
+                .filter(|k| !k.is_empty());
+        }
+        let pub_pem = pub_pem.ok_or_else(|| match &refresh_err {
+            Some(e) => PayError::Crypto(format!("refresh certs failed: {}", e)),
+            None => PayError::Other(format!("platform cert {} not found after refresh", serial)),
         })?;
      
         if pub_pem.is_empty() {
@@ -55,14 +189,15 @@ impl WechatNotify {
                 "wechat notify platform public key empty".to_string(),
             ));
         }
-        let ok = rsa_verify_sha256_pem(&pub_pem, &msg, signature)
+        let signature_type = headers.get("wechatpay-signature-type").map(String::as_str);
+        let ok = verify_signature(&self.cfg, signature_type, &pub_pem, &msg, signature)
             .map_err(|e| PayError::Crypto(format!("{}", e)))?;
         if !ok {
             return Err(PayError::Other(
                 "wechat notify invalid signature".to_string(),
             ));
         }
-        let v: serde_json::Value = serde_json::from_str(body).map_err(|e| PayError::Json(e))?;
+        let v: serde_json::Value = serde_json::from_str(body).map_err(PayError::Json)?;
         if let Some(resource) = v.get("resource") {
             let ad = resource
                 .get("associated_data")
@@ -73,12 +208,113 @@ impl WechatNotify {
                 .get("ciphertext")
                 .and_then(|v| v.as_str())
                 .unwrap_or("");
-            let plain = aes_gcm_decrypt(&self.cfg.api_v3_key, ad, nonce_r, ciphertext)
+            let algorithm = resource
+                .get("algorithm")
+                .and_then(|v| v.as_str())
+                .unwrap_or("AEAD_AES_256_GCM");
+            let plain = decrypt_resource(&self.cfg, algorithm, ad, nonce_r, ciphertext)
                 .map_err(|e| PayError::Crypto(format!("{}", e)))?;
             let pj: serde_json::Value =
-                serde_json::from_str(&plain).map_err(|e| PayError::Json(e))?;
+                serde_json::from_str(&plain).map_err(PayError::Json)?;
+            self.validators.validate(Channel::Wechat, &pj)?;
             return Ok(pj);
         }
+        self.validators.validate(Channel::Wechat, &v)?;
         Ok(v)
     }
+
+    /// 同 verify_and_decrypt，直接接收 NotifyRequest
+    pub async fn verify_request(
+        &self,
+        req: &NotifyRequest,
+    ) -> Result<serde_json::Value, PayError> {
+        self.verify_and_decrypt(&req.headers, req.body_str()?).await
+    }
+
+    /// 验签并解密投诉通知（宽松解析）
+    pub async fn verify_complaint(
+        &self,
+        headers: &HashMap<String, String>,
+        body: &str,
+    ) -> Result<ComplaintNotice, PayError> {
+        self.verify_complaint_with_mode(headers, body, ParseMode::Lenient)
+            .await
+    }
+
+    /// 验签并解密投诉通知，按 mode 处理未知字段
+    pub async fn verify_complaint_with_mode(
+        &self,
+        headers: &HashMap<String, String>,
+        body: &str,
+        mode: ParseMode,
+    ) -> Result<ComplaintNotice, PayError> {
+        let plain = self.verify_event(headers, body).await?;
+        let mut notice: ComplaintNotice = parse_with_mode(plain.clone(), mode)?;
+        notice.raw = plain;
+        Ok(notice)
+    }
+
+    /// 验签并解密退款通知（宽松解析）
+    pub async fn verify_refund(
+        &self,
+        headers: &HashMap<String, String>,
+        body: &str,
+    ) -> Result<RefundNotice, PayError> {
+        self.verify_refund_with_mode(headers, body, ParseMode::Lenient)
+            .await
+    }
+
+    /// 验签并解密退款通知，按 mode 处理未知字段
+    pub async fn verify_refund_with_mode(
+        &self,
+        headers: &HashMap<String, String>,
+        body: &str,
+        mode: ParseMode,
+    ) -> Result<RefundNotice, PayError> {
+        let plain = self.verify_event(headers, body).await?;
+        let mut notice: RefundNotice = parse_with_mode(plain.clone(), mode)?;
+        notice.raw = plain;
+        Ok(notice)
+    }
+
+    /// 验签解密后按 event_type 解析为对应的通知类型
+    pub async fn verify_notice(
+        &self,
+        headers: &HashMap<String, String>,
+        body: &str,
+    ) -> Result<WechatNotice, PayError> {
+        let plain = self.verify_event(headers, body).await?;
+        let event_type = plain["event_type"].as_str().unwrap_or_default().to_string();
+        let notice = if event_type.starts_with("TRANSACTION.") {
+            WechatNotice::Payment(Box::new(Transaction::from_value(plain)?))
+        } else if event_type.starts_with("REFUND.") {
+            let mut notice: RefundNotice = parse_with_mode(plain.clone(), ParseMode::Lenient)?;
+            notice.raw = plain;
+            WechatNotice::Refund(notice)
+        } else if event_type.starts_with("COMPLAINT.") {
+            let mut notice: ComplaintNotice = parse_with_mode(plain.clone(), ParseMode::Lenient)?;
+            notice.raw = plain;
+            WechatNotice::Complaint(notice)
+        } else {
+            WechatNotice::Other {
+                event_type,
+                resource: plain,
+            }
+        };
+        Ok(notice)
+    }
+
+    // 验签解密，并把通知外层的 event_type 写入解密后的内容
+    pub(crate) async fn verify_event(
+        &self,
+        headers: &HashMap<String, String>,
+        body: &str,
+    ) -> Result<serde_json::Value, PayError> {
+        let mut plain = self.verify_and_decrypt(headers, body).await?;
+        let envelope: serde_json::Value = serde_json::from_str(body)?;
+        if let Some(event_type) = envelope.get("event_type") {
+            plain["event_type"] = event_type.clone();
+        }
+        Ok(plain)
+    }
 }
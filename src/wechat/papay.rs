@@ -0,0 +1,173 @@
+use crate::errors::PayError;
+use crate::wechat::client::WechatClient;
+use crate::wechat::notify::WechatNotify;
+use crate::wechat::v2::{from_xml, sign_md5};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+/// 签约 / 解约结果通知（V2 XML，MD5 签名）
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PapayContractNotice {
+    /// ADD（签约）/ DELETE（解约）
+    pub change_type: String,
+    pub contract_code: String,
+    pub contract_id: String,
+    pub plan_id: String,
+    pub openid: String,
+    pub request_serial: Option<String>,
+    pub operate_time: Option<String>,
+    /// 解约方式：0 未解约，1 有效期过自动解约，2 用户主动解约，3 商户 API 解约，4 商户平台解约，5 注销
+    pub contract_termination_mode: Option<String>,
+    pub contract_expired_time: Option<String>,
+    /// 通知的全部字段
+    pub raw: BTreeMap<String, String>,
+}
+
+impl PapayContractNotice {
+    pub fn is_signed(&self) -> bool {
+        self.change_type == "ADD"
+    }
+}
+
+impl WechatClient {
+    /// 生成公众号纯签约跳转链接（/papay/entrustweb，V2 MD5 签名）
+    ///
+    /// params 需含 plan_id、contract_code、request_serial、contract_display_account，
+    /// appid / mch_id / notify_url / timestamp / version 缺省时自动补齐。
+    pub fn papay_entrust_web_url(&self, params: Value) -> Result<String, PayError> {
+        let api_key = self
+            .cfg
+            .api_v2_key
+            .as_deref()
+            .ok_or_else(|| PayError::Other("wechat api_v2_key missing".into()))?;
+        let mut map = BTreeMap::new();
+        if let Some(obj) = params.as_object() {
+            for (k, v) in obj {
+                let v = match v {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                map.insert(k.clone(), v);
+            }
+        }
+        if let Some(appid) = &self.cfg.appid {
+            map.entry("appid".into()).or_insert_with(|| appid.clone());
+        }
+        map.entry("mch_id".into())
+            .or_insert_with(|| self.cfg.mchid.clone());
+        if let Some(notify_url) = &self.cfg.notify_url {
+            map.entry("notify_url".into())
+                .or_insert_with(|| notify_url.clone());
+        }
+        map.entry("timestamp".into())
+            .or_insert_with(|| chrono::Utc::now().timestamp().to_string());
+        map.entry("version".into()).or_insert_with(|| "1.0".into());
+        let sign = sign_md5(&map, api_key)?;
+        map.insert("sign".into(), sign);
+        let query = map
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, urlencoding::encode(v)))
+            .collect::<Vec<_>>()
+            .join("&");
+        Ok(format!(
+            "{}/papay/entrustweb?{}",
+            self.cfg.api_host(),
+            query
+        ))
+    }
+
+    /// 按委托代扣协议号查询签约关系
+    pub async fn query_papay_contract(
+        &self,
+        plan_id: &str,
+        out_contract_code: &str,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/papay/contracts/plan-id/{}/out-contract-code/{}?appid={}",
+            plan_id,
+            urlencoding::encode(out_contract_code),
+            self.cfg.appid.as_deref().unwrap_or_default()
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 商户解约
+    pub async fn terminate_papay_contract(
+        &self,
+        contract_id: &str,
+        remark: &str,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!("/v3/papay/contracts/{}/terminate", contract_id));
+        let body = self.papay_body(json!({ "contract_termination_remark": remark }));
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    /// 扣款前预通知用户，body 含 estimated_amount 等
+    pub async fn notify_papay_deduction(
+        &self,
+        contract_id: &str,
+        body: Value,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!("/v3/papay/contracts/{}/notify", contract_id));
+        let body = self.papay_body(body);
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    /// 申请扣款，body 含 contract_id、out_trade_no、description、amount 等；结果以支付通知返回
+    pub async fn papay_apply(&self, order: Value) -> Result<Value, PayError> {
+        let mut body = self.papay_body(order);
+        if body.get("notify_url").is_none() {
+            if let Some(notify_url) = &self.cfg.notify_url {
+                body["notify_url"] = json!(notify_url);
+            }
+        }
+        let url = self.endpoint("/v3/papay/pay/transactions/apply");
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    fn papay_body(&self, mut body: Value) -> Value {
+        if body.get("appid").is_none() {
+            if let Some(appid) = &self.cfg.appid {
+                body["appid"] = json!(appid);
+            }
+        }
+        body
+    }
+}
+
+impl WechatNotify {
+    /// 验签并解析签约 / 解约结果通知（V2 XML，使用 api_v2_key 校验 MD5 签名）
+    pub fn verify_papay_contract(&self, body: &str) -> Result<PapayContractNotice, PayError> {
+        let api_key = self
+            .cfg
+            .api_v2_key
+            .as_deref()
+            .ok_or_else(|| PayError::Other("wechat api_v2_key missing".into()))?;
+        let params = from_xml(body)?;
+        let sign = params.get("sign").cloned().unwrap_or_default();
+        if !sign.eq_ignore_ascii_case(&sign_md5(&params, api_key)?) {
+            return Err(PayError::Other("papay contract notify invalid signature".into()));
+        }
+        if params.get("result_code").is_some_and(|c| c != "SUCCESS") {
+            return Err(PayError::Other(format!(
+                "papay contract notify failed: {}",
+                params.get("err_code_des").cloned().unwrap_or_default()
+            )));
+        }
+        let get = |k: &str| params.get(k).cloned().unwrap_or_default();
+        let opt = |k: &str| params.get(k).cloned();
+        Ok(PapayContractNotice {
+            change_type: get("change_type"),
+            contract_code: get("contract_code"),
+            contract_id: get("contract_id"),
+            plan_id: get("plan_id"),
+            openid: get("openid"),
+            request_serial: opt("request_serial"),
+            operate_time: opt("operate_time"),
+            contract_termination_mode: opt("contract_termination_mode"),
+            contract_expired_time: opt("contract_expired_time"),
+            raw: params,
+        })
+    }
+}
@@ -0,0 +1,151 @@
+use crate::errors::PayError;
+use crate::wechat::client::WechatClient;
+use serde_json::{json, Value};
+
+impl WechatClient {
+    /// 创建支付分服务订单，appid / notify_url 缺省取配置，body 需含 service_id
+    pub async fn create_payscore_order(&self, order: Value) -> Result<Value, PayError> {
+        let body = self.payscore_body_with_notify(order);
+        let url = self.endpoint("/v3/payscore/serviceorder");
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    /// 按商户服务订单号查询支付分订单
+    pub async fn query_payscore_order(
+        &self,
+        service_id: &str,
+        out_order_no: &str,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/payscore/serviceorder?service_id={}&out_order_no={}&appid={}",
+            service_id,
+            urlencoding::encode(out_order_no),
+            self.cfg.appid.as_deref().unwrap_or_default()
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 取消支付分订单
+    pub async fn cancel_payscore_order(
+        &self,
+        out_order_no: &str,
+        service_id: &str,
+        reason: &str,
+    ) -> Result<Value, PayError> {
+        let body = self.payscore_body(json!({ "service_id": service_id, "reason": reason }));
+        self.payscore_order_action(out_order_no, "cancel", &body).await
+    }
+
+    /// 修改支付分订单金额，body 含 service_id、post_payments、total_amount、reason 等
+    pub async fn modify_payscore_order(&self, out_order_no: &str, body: Value) -> Result<Value, PayError> {
+        let body = self.payscore_body(body);
+        self.payscore_order_action(out_order_no, "modify", &body).await
+    }
+
+    /// 完结支付分订单，body 含 service_id、post_payments、total_amount、time_range 等
+    pub async fn complete_payscore_order(&self, out_order_no: &str, body: Value) -> Result<Value, PayError> {
+        let body = self.payscore_body(body);
+        self.payscore_order_action(out_order_no, "complete", &body).await
+    }
+
+    /// 商户申请用户授权，返回 apply_permissions_token
+    pub async fn apply_payscore_permissions(&self, body: Value) -> Result<Value, PayError> {
+        let body = self.payscore_body_with_notify(body);
+        let url = self.endpoint("/v3/payscore/permissions");
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    /// 按授权协议号查询用户授权状态
+    pub async fn query_payscore_permissions(
+        &self,
+        service_id: &str,
+        authorization_code: &str,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/payscore/permissions/authorization-code/{}?service_id={}",
+            urlencoding::encode(authorization_code),
+            service_id
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 按 openid 查询用户授权状态
+    pub async fn query_payscore_permissions_by_openid(
+        &self,
+        service_id: &str,
+        openid: &str,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/payscore/permissions/openid/{}?appid={}&service_id={}",
+            openid,
+            self.cfg.appid.as_deref().unwrap_or_default(),
+            service_id
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 按授权协议号解除用户授权
+    pub async fn terminate_payscore_permissions(
+        &self,
+        service_id: &str,
+        authorization_code: &str,
+        reason: &str,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/payscore/permissions/authorization-code/{}/terminate",
+            urlencoding::encode(authorization_code)
+        ));
+        let body = json!({ "service_id": service_id, "reason": reason });
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    /// 按 openid 解除用户授权
+    pub async fn terminate_payscore_permissions_by_openid(
+        &self,
+        service_id: &str,
+        openid: &str,
+        reason: &str,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/payscore/permissions/openid/{}/terminate",
+            openid
+        ));
+        let body = self.payscore_body(json!({ "service_id": service_id, "reason": reason }));
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    async fn payscore_order_action(
+        &self,
+        out_order_no: &str,
+        action: &str,
+        body: &Value,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/payscore/serviceorder/{}/{}",
+            urlencoding::encode(out_order_no),
+            action
+        ));
+        self.sign_and_post("POST", &url, body).await
+    }
+
+    // 支付分接口以 appid + service_id 区分服务，appid 缺省取配置
+    fn payscore_body(&self, mut body: Value) -> Value {
+        if body.get("appid").is_none() {
+            if let Some(appid) = &self.cfg.appid {
+                body["appid"] = json!(appid);
+            }
+        }
+        body
+    }
+
+    // 创建订单与申请授权需回调地址，缺省取配置
+    fn payscore_body_with_notify(&self, body: Value) -> Value {
+        let mut body = self.payscore_body(body);
+        if body.get("notify_url").is_none() {
+            if let Some(notify_url) = &self.cfg.notify_url {
+                body["notify_url"] = json!(notify_url);
+            }
+        }
+        body
+    }
+}
@@ -0,0 +1,55 @@
+use crate::errors::PayError;
+use crate::poll::{PaymentOutcome, PaymentState, PollBackoff};
+use crate::wechat::client::WechatClient;
+use serde_json::{json, Value};
+use std::time::Instant;
+
+impl WechatClient {
+    /// 轮询订单直到终态或超过截止时间（Native / 付款码等场景）
+    ///
+    /// NOTPAY、USERPAYING 继续轮询；查询出错时继续重试直到截止。
+    /// `close_on_timeout` 为 true 时超时后调用关单。
+    pub async fn await_payment(
+        &self,
+        out_trade_no: &str,
+        deadline: Instant,
+        close_on_timeout: bool,
+    ) -> Result<PaymentOutcome, PayError> {
+        let mut backoff = PollBackoff::new(deadline);
+        let mut last = Value::Null;
+        loop {
+            match self.query_by_out_trade_no(out_trade_no).await {
+                Ok(resp) => {
+                    let state = match resp.get("trade_state").and_then(|v| v.as_str()) {
+                        Some("SUCCESS") | Some("REFUND") => Some(PaymentState::Paid),
+                        Some("CLOSED") | Some("REVOKED") => Some(PaymentState::Closed),
+                        Some("PAYERROR") => Some(PaymentState::Failed),
+                        _ => None,
+                    };
+                    if let Some(state) = state {
+                        return Ok(PaymentOutcome {
+                            state,
+                            closed_on_timeout: false,
+                            raw: resp,
+                        });
+                    }
+                    last = resp;
+                }
+                Err(e) => tracing::warn!("await_payment query {} failed: {}", out_trade_no, e),
+            }
+            if !backoff.wait().await {
+                break;
+            }
+        }
+        let closed = close_on_timeout
+            && self
+                .close(json!({ "out_trade_no": out_trade_no }))
+                .await
+                .is_ok();
+        Ok(PaymentOutcome {
+            state: PaymentState::TimedOut,
+            closed_on_timeout: closed,
+            raw: last,
+        })
+    }
+}
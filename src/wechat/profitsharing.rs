@@ -0,0 +1,110 @@
+use crate::config::Mode;
+use crate::errors::PayError;
+use crate::wechat::client::WechatClient;
+use serde_json::{json, Value};
+
+impl WechatClient {
+    /// 请求分账（/v3/profitsharing/orders）
+    ///
+    /// appid 缺省取配置，服务商模式下 sub_mchid 缺省取配置；
+    /// receivers[].name 以平台证书加密并带上 Wechatpay-Serial。
+    pub async fn create_profitsharing_order(&self, order: Value) -> Result<Value, PayError> {
+        let mut body = self.profitsharing_body(order);
+        let count = body
+            .get("receivers")
+            .and_then(|v| v.as_array())
+            .map_or(0, |r| r.len());
+        let pointers: Vec<String> = (0..count).map(|i| format!("/receivers/{}/name", i)).collect();
+        let pointers: Vec<&str> = pointers.iter().map(String::as_str).collect();
+        let serial = self.encrypt_fields(&mut body, &pointers).await?;
+        let url = self.endpoint("/v3/profitsharing/orders");
+        self.sign_and_post_with_serial("POST", &url, &body, serial.as_deref())
+            .await
+    }
+
+    /// 按商户分账单号查询分账结果
+    pub async fn query_profitsharing_order(
+        &self,
+        out_order_no: &str,
+        transaction_id: &str,
+    ) -> Result<Value, PayError> {
+        let mut path = format!(
+            "/v3/profitsharing/orders/{}?transaction_id={}",
+            urlencoding::encode(out_order_no),
+            transaction_id
+        );
+        if let Some(sub_mchid) = self.service_sub_mchid() {
+            path.push_str("&sub_mchid=");
+            path.push_str(sub_mchid);
+        }
+        let url = self.endpoint(&path);
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 解冻剩余资金（完结分账），unfreeze 后订单不可再分账
+    pub async fn finish_profitsharing_order(&self, order: Value) -> Result<Value, PayError> {
+        let body = self.profitsharing_body(order);
+        let url = self.endpoint("/v3/profitsharing/orders/unfreeze");
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    /// 查询订单剩余待分金额
+    pub async fn query_profitsharing_amounts(&self, transaction_id: &str) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/profitsharing/transactions/{}/amounts",
+            transaction_id
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 添加分账接收方（/v3/profitsharing/receivers/add）
+    ///
+    /// 接收方类型为 MERCHANT_ID 时 name 必填；name 以平台证书 RSA-OAEP 加密并带上 Wechatpay-Serial。
+    pub async fn add_receiver(&self, receiver: Value) -> Result<Value, PayError> {
+        let receiver_type = receiver.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        let has_name = receiver
+            .get("name")
+            .and_then(|v| v.as_str())
+            .is_some_and(|n| !n.is_empty());
+        if receiver_type == "MERCHANT_ID" && !has_name {
+            return Err(PayError::Other(
+                "'name' field is required for MERCHANT_ID receiver".into(),
+            ));
+        }
+        let mut body = self.profitsharing_body(receiver);
+        let serial = self.encrypt_fields(&mut body, &["/name"]).await?;
+        let url = self.endpoint("/v3/profitsharing/receivers/add");
+        self.sign_and_post_with_serial("POST", &url, &body, serial.as_deref())
+            .await
+    }
+
+    /// 删除分账接收方（/v3/profitsharing/receivers/delete）
+    pub async fn delete_receiver(&self, receiver: Value) -> Result<Value, PayError> {
+        let body = self.profitsharing_body(receiver);
+        let url = self.endpoint("/v3/profitsharing/receivers/delete");
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    // 服务商模式下的子商户号
+    pub(crate) fn service_sub_mchid(&self) -> Option<&str> {
+        match self.mode {
+            Mode::Service => self.cfg.sub_mchid.as_deref(),
+            _ => None,
+        }
+    }
+
+    // 分账接口不使用 sp_appid / sp_mchid：直连与服务商均传 appid，服务商另传 sub_mchid
+    pub(crate) fn profitsharing_body(&self, mut body: Value) -> Value {
+        if body.get("appid").is_none() {
+            if let Some(appid) = &self.cfg.appid {
+                body["appid"] = json!(appid);
+            }
+        }
+        if body.get("sub_mchid").is_none() {
+            if let Some(sub_mchid) = self.service_sub_mchid() {
+                body["sub_mchid"] = json!(sub_mchid);
+            }
+        }
+        body
+    }
+}
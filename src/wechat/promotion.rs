@@ -0,0 +1,55 @@
+//! V2 企业付款到零钱（/mmpaymkttransfers/*），需商户 API 证书双向 TLS
+use crate::errors::PayError;
+use crate::wechat::client::WechatClient;
+use std::collections::BTreeMap;
+
+impl WechatClient {
+    /// 企业付款到零钱
+    ///
+    /// params 含 partner_trade_no、openid、check_name（NO_CHECK / FORCE_CHECK）、amount（分）、desc 等，
+    /// mch_appid / mchid 缺省取配置。result_code 为 FAIL 时返回 PayError::WechatV2；
+    /// err_code 为 SYSTEMERROR 时付款结果未知，须先查询，确认失败后再用原 partner_trade_no 重试。
+    pub async fn promotion_transfer(
+        &self,
+        mut params: BTreeMap<String, String>,
+    ) -> Result<BTreeMap<String, String>, PayError> {
+        if let Some(appid) = &self.cfg.appid {
+            params
+                .entry("mch_appid".into())
+                .or_insert_with(|| appid.clone());
+        }
+        params
+            .entry("mchid".into())
+            .or_insert_with(|| self.cfg.mchid.clone());
+        let resp = self
+            .v2_send("/mmpaymkttransfers/promotion/transfers", params, true)
+            .await?;
+        check_result(resp)
+    }
+
+    /// 查询企业付款结果
+    pub async fn query_promotion_transfer(
+        &self,
+        partner_trade_no: &str,
+    ) -> Result<BTreeMap<String, String>, PayError> {
+        let mut params = BTreeMap::new();
+        params.insert("partner_trade_no".to_string(), partner_trade_no.to_string());
+        if let Some(appid) = &self.cfg.appid {
+            params.insert("appid".to_string(), appid.clone());
+        }
+        let resp = self
+            .v2_post_with_cert("/mmpaymkttransfers/gettransferinfo", params)
+            .await?;
+        check_result(resp)
+    }
+}
+
+fn check_result(resp: BTreeMap<String, String>) -> Result<BTreeMap<String, String>, PayError> {
+    if resp.get("result_code").map(String::as_str) == Some("FAIL") {
+        return Err(PayError::WechatV2 {
+            err_code: resp.get("err_code").cloned().unwrap_or_default(),
+            err_code_des: resp.get("err_code_des").cloned().unwrap_or_default(),
+        });
+    }
+    Ok(resp)
+}
@@ -0,0 +1,166 @@
+use crate::errors::PayError;
+use crate::wechat::bill::verify_bill_hash;
+use crate::wechat::client::WechatClient;
+use serde_json::{json, Value};
+
+impl WechatClient {
+    /// 发起商家转账批次
+    ///
+    /// transfer_detail_list[].user_name / user_id_card 以平台证书 RSA-OAEP 加密并带上 Wechatpay-Serial，
+    /// 调用方传明文即可。
+    pub async fn transfer(&self, order: Value) -> Result<Value, PayError> {
+        let mut order = self.build_service_params(order);
+        let count = order
+            .get("transfer_detail_list")
+            .and_then(|v| v.as_array())
+            .map_or(0, |d| d.len());
+        let pointers: Vec<String> = (0..count)
+            .flat_map(|i| {
+                [
+                    format!("/transfer_detail_list/{}/user_name", i),
+                    format!("/transfer_detail_list/{}/user_id_card", i),
+                ]
+            })
+            .collect();
+        let pointers: Vec<&str> = pointers.iter().map(String::as_str).collect();
+        let serial = self.encrypt_fields(&mut order, &pointers).await?;
+        let url = self.endpoint("/v3/transfer/batches");
+        self.sign_and_post_with_serial("POST", &url, &order, serial.as_deref())
+            .await
+    }
+
+    /// 按微信批次单号查询转账批次
+    ///
+    /// query 可含 need_query_detail（缺省 false）、offset、limit、detail_status（ALL / SUCCESS / FAIL）。
+    pub async fn query_transfer_batch(&self, batch_id: &str, query: Value) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/transfer/batches/batch-id/{}?{}",
+            batch_id,
+            transfer_query(&query)
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 按商家批次单号查询转账批次，query 同 query_transfer_batch
+    pub async fn query_transfer_batch_by_out_no(
+        &self,
+        out_batch_no: &str,
+        query: Value,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/transfer/batches/out-batch-no/{}?{}",
+            urlencoding::encode(out_batch_no),
+            transfer_query(&query)
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 按微信明细单号查询转账明细，失败时返回 fail_reason
+    pub async fn query_transfer_detail(
+        &self,
+        batch_id: &str,
+        detail_id: &str,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/transfer/batches/batch-id/{}/details/detail-id/{}",
+            batch_id, detail_id
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 按商家批次单号与商家明细单号查询转账明细
+    pub async fn query_transfer_detail_by_out_no(
+        &self,
+        out_batch_no: &str,
+        out_detail_no: &str,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/transfer/batches/out-batch-no/{}/details/out-detail-no/{}",
+            urlencoding::encode(out_batch_no),
+            urlencoding::encode(out_detail_no)
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 转账电子回单申请受理（按商家批次单号）
+    pub async fn apply_transfer_bill_receipt(&self, out_batch_no: &str) -> Result<Value, PayError> {
+        let url = self.endpoint("/v3/transfer/bill-receipt");
+        let body = json!({ "out_batch_no": out_batch_no });
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    /// 查询转账电子回单，受理完成后返回 download_url / hash_value
+    pub async fn query_transfer_bill_receipt(&self, out_batch_no: &str) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/transfer/bill-receipt/{}",
+            urlencoding::encode(out_batch_no)
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 转账明细电子回单申请受理，accept_type 为 BATCH_TRANSFER / TRANSFER_TO_POCKET / TRANSFER_TO_BANK
+    pub async fn apply_transfer_detail_receipt(
+        &self,
+        accept_type: &str,
+        out_batch_no: &str,
+        out_detail_no: &str,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint("/v3/transfer-detail/electronic-receipts");
+        let body = json!({
+            "accept_type": accept_type,
+            "out_batch_no": out_batch_no,
+            "out_detail_no": out_detail_no,
+        });
+        self.sign_and_post("POST", &url, &body).await
+    }
+
+    /// 查询转账明细电子回单，受理完成后返回 download_url / hash_type / hash_value
+    pub async fn query_transfer_detail_receipt(
+        &self,
+        accept_type: &str,
+        out_batch_no: &str,
+        out_detail_no: &str,
+    ) -> Result<Value, PayError> {
+        let url = self.endpoint(&format!(
+            "/v3/transfer-detail/electronic-receipts?accept_type={}&out_batch_no={}&out_detail_no={}",
+            accept_type,
+            urlencoding::encode(out_batch_no),
+            urlencoding::encode(out_detail_no)
+        ));
+        self.sign_and_post("GET", &url, &Value::Null).await
+    }
+
+    /// 下载转账电子回单（PDF），download_url 取自查询结果
+    pub async fn download_transfer_receipt(&self, download_url: &str) -> Result<Vec<u8>, PayError> {
+        self.download(download_url).await
+    }
+
+    /// 按回单查询结果下载回单并校验 hash_value，受理未完成时报错
+    pub async fn download_transfer_receipt_checked(&self, receipt: &Value) -> Result<Vec<u8>, PayError> {
+        let download_url = receipt
+            .get("download_url")
+            .and_then(|v| v.as_str())
+            .filter(|u| !u.is_empty())
+            .ok_or_else(|| PayError::Other(format!("receipt not ready: {}", receipt)))?;
+        let bytes = self.download(download_url).await?;
+        verify_bill_hash(receipt, &bytes)?;
+        Ok(bytes)
+    }
+}
+
+// 批次查询参数拼到 URL 上参与签名，need_query_detail 为必填
+fn transfer_query(query: &Value) -> String {
+    let need_detail = query
+        .get("need_query_detail")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let mut pairs = vec![format!("need_query_detail={}", need_detail)];
+    for key in ["offset", "limit", "detail_status"] {
+        match query.get(key) {
+            Some(Value::String(v)) => pairs.push(format!("{}={}", key, urlencoding::encode(v))),
+            Some(Value::Number(v)) => pairs.push(format!("{}={}", key, v)),
+            _ => {}
+        }
+    }
+    pairs.join("&")
+}
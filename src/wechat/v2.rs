@@ -0,0 +1,230 @@
+use crate::errors::PayError;
+use crate::utils::gen_nonce;
+use crate::wechat::client::WechatClient;
+use crate::wechat::failover;
+use openssl::hash::{hash, MessageDigest};
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::collections::BTreeMap;
+
+// 应答不带 sign 的接口（企业付款及其查询），其余接口成功应答必须验签通过
+const UNSIGNED_RESPONSE_PATHS: &[&str] = &[
+    "/mmpaymkttransfers/promotion/transfers",
+    "/mmpaymkttransfers/gettransferinfo",
+];
+
+/// V2 MD5 签名：参数按 key 排序拼接，末尾追加 &key=API密钥，结果大写
+pub fn sign_md5(params: &BTreeMap<String, String>, api_key: &str) -> Result<String, PayError> {
+    let src = sign_source(params, api_key);
+    let digest = hash(MessageDigest::md5(), src.as_bytes())
+        .map_err(|e| PayError::Crypto(format!("v2 md5 sign: {}", e)))?;
+    Ok(digest.iter().map(|b| format!("{:02X}", b)).collect())
+}
+
+/// V2 HMAC-SHA256 签名：待签串同 MD5，以 API 密钥为 HMAC 密钥，结果大写
+pub fn sign_hmac_sha256(params: &BTreeMap<String, String>, api_key: &str) -> Result<String, PayError> {
+    let src = sign_source(params, api_key);
+    let key = PKey::hmac(api_key.as_bytes())
+        .map_err(|e| PayError::Crypto(format!("v2 hmac key: {}", e)))?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &key)
+        .map_err(|e| PayError::Crypto(format!("v2 hmac sign: {}", e)))?;
+    let digest = signer
+        .update(src.as_bytes())
+        .and_then(|_| signer.sign_to_vec())
+        .map_err(|e| PayError::Crypto(format!("v2 hmac sign: {}", e)))?;
+    Ok(digest.iter().map(|b| format!("{:02X}", b)).collect())
+}
+
+/// V2 签名方式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum V2SignType {
+    Md5,
+    HmacSha256,
+}
+
+impl V2SignType {
+    /// 按参数中的 sign_type 字段判断，缺省 MD5
+    pub fn of(params: &BTreeMap<String, String>) -> Self {
+        match params.get("sign_type").map(String::as_str) {
+            Some("HMAC-SHA256") => V2SignType::HmacSha256,
+            _ => V2SignType::Md5,
+        }
+    }
+}
+
+pub fn sign(
+    params: &BTreeMap<String, String>,
+    api_key: &str,
+    sign_type: V2SignType,
+) -> Result<String, PayError> {
+    match sign_type {
+        V2SignType::Md5 => sign_md5(params, api_key),
+        V2SignType::HmacSha256 => sign_hmac_sha256(params, api_key),
+    }
+}
+
+/// 校验 V2 应答或通知的 sign；未携带 sign 时返回 false
+pub fn verify_sign(
+    params: &BTreeMap<String, String>,
+    api_key: &str,
+    sign_type: V2SignType,
+) -> Result<bool, PayError> {
+    let expected = match params.get("sign") {
+        Some(s) => s,
+        None => return Ok(false),
+    };
+    Ok(sign(params, api_key, sign_type)?.eq_ignore_ascii_case(expected))
+}
+
+fn sign_source(params: &BTreeMap<String, String>, api_key: &str) -> String {
+    let mut src = params
+        .iter()
+        .filter(|(k, v)| k.as_str() != "sign" && !v.is_empty())
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join("&");
+    src.push_str("&key=");
+    src.push_str(api_key);
+    src
+}
+
+/// 参数序列化为 V2 的扁平 XML
+pub fn to_xml(params: &BTreeMap<String, String>) -> String {
+    let mut xml = String::from("<xml>");
+    for (k, v) in params {
+        xml.push_str(&format!("<{k}><![CDATA[{v}]]></{k}>", k = k, v = v));
+    }
+    xml.push_str("</xml>");
+    xml
+}
+
+/// 解析 V2 响应的扁平 XML
+pub fn from_xml(xml: &str) -> Result<BTreeMap<String, String>, PayError> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut map = BTreeMap::new();
+    let mut current: Option<String> = None;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if name != "xml" {
+                    current = Some(name);
+                }
+            }
+            Ok(Event::Text(t)) => {
+                if let Some(k) = &current {
+                    let v = t
+                        .unescape()
+                        .map_err(|e| PayError::Other(format!("v2 xml: {}", e)))?;
+                    map.insert(k.clone(), v.to_string());
+                }
+            }
+            Ok(Event::CData(c)) => {
+                if let Some(k) = &current {
+                    map.insert(k.clone(), String::from_utf8_lossy(&c.into_inner()).to_string());
+                }
+            }
+            Ok(Event::End(_)) => current = None,
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(e) => return Err(PayError::Other(format!("v2 xml: {}", e))),
+        }
+    }
+    Ok(map)
+}
+
+impl WechatClient {
+    /// 调用 V2 XML 接口，自动补充 mch_id / nonce_str / sign
+    ///
+    /// params 含 sign_type=HMAC-SHA256 时使用 HMAC-SHA256，否则 MD5；
+    /// 应答带 sign 时按同一方式验签，return_code 非 SUCCESS 时报错。
+    pub async fn v2_post(
+        &self,
+        path: &str,
+        mut params: BTreeMap<String, String>,
+    ) -> Result<BTreeMap<String, String>, PayError> {
+        params
+            .entry("mch_id".into())
+            .or_insert_with(|| self.cfg.mchid.clone());
+        self.v2_send(path, params, false).await
+    }
+
+    /// 同 v2_post，使用商户 API 证书双向 TLS（需配置 client_cert_pem 或 client_cert_p12_path）
+    pub async fn v2_post_with_cert(
+        &self,
+        path: &str,
+        mut params: BTreeMap<String, String>,
+    ) -> Result<BTreeMap<String, String>, PayError> {
+        params
+            .entry("mch_id".into())
+            .or_insert_with(|| self.cfg.mchid.clone());
+        self.v2_send(path, params, true).await
+    }
+
+    // 补 nonce_str 与 sign 后发送；商户号字段名因接口而异，由调用方填写
+    pub(crate) async fn v2_send(
+        &self,
+        path: &str,
+        mut params: BTreeMap<String, String>,
+        with_cert: bool,
+    ) -> Result<BTreeMap<String, String>, PayError> {
+        let api_key = self
+            .cfg
+            .api_v2_key
+            .clone()
+            .ok_or_else(|| PayError::Other("wechat api_v2_key missing".into()))?;
+        params
+            .entry("nonce_str".into())
+            .or_insert_with(|| gen_nonce(32));
+        let sign_type = V2SignType::of(&params);
+        let signature = sign(&params, &api_key, sign_type)?;
+        params.insert("sign".into(), signature);
+
+        self.ensure_sandbox_target()?;
+        let body = to_xml(&params);
+        let client = if with_cert { self.tls_http()? } else { &self.http };
+        let text = loop {
+            let host = failover::active_host(&self.cfg);
+            let url = format!("{}{}", host, path);
+            // 请求体含 openid、付款码等敏感信息，不记录
+            tracing::debug!("v2_post: url={}", url);
+            let sent = async {
+                client
+                    .post(&url)
+                    .header("Content-Type", "text/xml; charset=utf-8")
+                    .body(body.clone())
+                    .send()
+                    .await?
+                    .text()
+                    .await
+            };
+            match sent.await.map_err(PayError::Http) {
+                Ok(text) => {
+                    failover::report_success(&self.cfg, host);
+                    break text;
+                }
+                Err(e) if failover::report_failure(&self.cfg, host, &e) => continue,
+                Err(e) => return Err(e),
+            }
+        };
+        let resp = from_xml(&text)?;
+        if resp.get("return_code").map(String::as_str) != Some("SUCCESS") {
+            return Err(PayError::Other(format!(
+                "wechat v2 {} failed: {}",
+                path,
+                resp.get("return_msg").cloned().unwrap_or_default()
+            )));
+        }
+        // 应答不回传 sign_type，按请求的签名方式验签；缺少 sign 视为验签失败
+        if !UNSIGNED_RESPONSE_PATHS.contains(&path) && !verify_sign(&resp, &api_key, sign_type)? {
+            return Err(PayError::Crypto(format!(
+                "wechat v2 {} invalid response signature",
+                path
+            )));
+        }
+        Ok(resp)
+    }
+}